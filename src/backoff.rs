@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+/// Exponential backoff that doubles a base delay up to a cap
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base: Duration,
+    cap: Duration,
+    multiplier: f64,
+    current: Option<Duration>,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, cap: Duration) -> Self {
+        Self {
+            base,
+            cap,
+            multiplier: 2.0,
+            current: None,
+        }
+    }
+
+    /// Return the delay to wait before the next retry and advance the backoff
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = match self.current {
+            None => self.base,
+            Some(previous) => previous.mul_f64(self.multiplier),
+        }
+        .min(self.cap);
+        self.current = Some(delay);
+        delay
+    }
+
+    /// Go back to the base delay after a successful attempt
+    pub fn reset(&mut self) {
+        self.current = None;
+    }
+}
+
+/// Restart delay that grows with each crash in a row (`backoff_multiplier`). Unlike
+/// [`Backoff`], the base comes with each crash, since pattern and exit code rules can set
+/// their own delay, and a server that stayed up for the stability window starts over.
+#[derive(Debug, Clone)]
+pub struct RestartBackoff {
+    multiplier: f64,
+    cap: Duration,
+    stability_window: Duration,
+    consecutive: i32,
+}
+
+impl RestartBackoff {
+    pub fn new(multiplier: f64, cap: Duration, stability_window: Duration) -> Self {
+        Self {
+            multiplier,
+            cap,
+            stability_window,
+            consecutive: 0,
+        }
+    }
+
+    /// The delay before restarting a server that crashed after running for `uptime`, for a
+    /// crash whose own delay is `base`. A cap below `base` never shortens it.
+    pub fn delay(&mut self, base: Duration, uptime: Duration) -> Duration {
+        if uptime >= self.stability_window {
+            self.consecutive = 0;
+        }
+        let cap = self.cap.max(base);
+        let delay = Duration::try_from_secs_f64(
+            base.as_secs_f64() * self.multiplier.powi(self.consecutive),
+        )
+        .map_or(cap, |delay| delay.min(cap));
+        if delay < cap {
+            self.consecutive += 1;
+        }
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_until_cap() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(2));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(4));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(5));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_backoff_reset() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(10));
+        backoff.next_delay();
+        backoff.next_delay();
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_backoff_base_above_cap() {
+        let mut backoff = Backoff::new(Duration::from_secs(10), Duration::from_secs(3));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_restart_backoff_grows_until_the_server_stays_up() {
+        let secs = Duration::from_secs;
+        let mut backoff = RestartBackoff::new(3.0, secs(20), secs(30));
+        assert_eq!(backoff.delay(secs(2), secs(1)), secs(2));
+        assert_eq!(backoff.delay(secs(2), secs(1)), secs(6));
+        assert_eq!(backoff.delay(secs(2), secs(1)), secs(18));
+        assert_eq!(backoff.delay(secs(2), secs(1)), secs(20));
+        assert_eq!(backoff.delay(secs(2), secs(1)), secs(20));
+        // A rule's longer delay is kept even above the cap
+        assert_eq!(backoff.delay(secs(45), secs(1)), secs(45));
+        // Running for the stability window starts over from the base
+        assert_eq!(backoff.delay(secs(2), secs(30)), secs(2));
+        assert_eq!(backoff.delay(secs(2), secs(29)), secs(6));
+    }
+}
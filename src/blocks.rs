@@ -0,0 +1,193 @@
+/// Lines a block may hold when `max_block_lines` isn't set
+pub const DEFAULT_MAX_BLOCK_LINES: usize = 200;
+
+/// Where a multi-line block such as a stack trace starts and ends (`block_start`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockDelimiters {
+    pub start: String,
+    /// Text on the block's last line; without it a blank line ends the block
+    pub end: Option<String>,
+    pub max_lines: usize,
+}
+
+/// What became of one line fed to a `BlockCollector`
+#[derive(Debug, Default, PartialEq)]
+pub struct Fed {
+    /// The line belongs to a block, so it isn't matched on its own
+    pub in_block: bool,
+    /// A block this line completed, its lines joined with newlines
+    pub completed: Option<String>,
+}
+
+/// Groups the output lines of one stream into blocks: from a line containing the start
+/// text until the end text, a blank line (without end text), the next block's start or
+/// `max_lines`, whichever comes first
+pub struct BlockCollector {
+    delimiters: BlockDelimiters,
+    lines: Vec<String>,
+}
+
+impl BlockCollector {
+    pub fn new(delimiters: BlockDelimiters) -> Self {
+        Self {
+            delimiters,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn feed(&mut self, line: &str) -> Fed {
+        let starts = line.contains(self.delimiters.start.as_str());
+        if self.lines.is_empty() {
+            if !starts {
+                return Fed::default();
+            }
+            self.lines.push(line.to_string());
+            return Fed {
+                in_block: true,
+                completed: self.take_if_full(),
+            };
+        }
+
+        if starts {
+            // Back-to-back traces: the new one ends the current one
+            let completed = self.finish();
+            self.lines.push(line.to_string());
+            return Fed {
+                in_block: true,
+                completed: completed.or_else(|| self.take_if_full()),
+            };
+        }
+        let completed = match &self.delimiters.end {
+            Some(end) if line.contains(end.as_str()) => {
+                self.lines.push(line.to_string());
+                self.finish()
+            }
+            None if line.trim().is_empty() => self.finish(),
+            _ => {
+                self.lines.push(line.to_string());
+                self.take_if_full()
+            }
+        };
+        Fed {
+            in_block: true,
+            completed,
+        }
+    }
+
+    /// Whether a block has started and not ended yet
+    pub fn in_progress(&self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    /// The block in progress, e.g. when the stream ends
+    pub fn finish(&mut self) -> Option<String> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        let block = self.lines.join("\n");
+        self.lines.clear();
+        Some(block)
+    }
+
+    fn take_if_full(&mut self) -> Option<String> {
+        if self.lines.len() >= self.delimiters.max_lines {
+            self.finish()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(delimiters: BlockDelimiters, output: &str) -> (Vec<String>, Vec<&str>) {
+        let mut collector = BlockCollector::new(delimiters);
+        let mut blocks = Vec::new();
+        let mut single_lines = Vec::new();
+        for line in output.lines() {
+            let fed = collector.feed(line);
+            if !fed.in_block {
+                single_lines.push(line);
+            }
+            blocks.extend(fed.completed);
+        }
+        blocks.extend(collector.finish());
+        (blocks, single_lines)
+    }
+
+    #[test]
+    fn test_node_trace_ends_at_a_blank_line() {
+        let output = "\
+GET /users 200
+TypeError: Cannot read properties of undefined (reading 'id')
+    at getUser (/app/src/users.js:14:22)
+    at Layer.handle (/app/node_modules/express/lib/router/layer.js:95:5)
+
+GET /health 200
+";
+        let delimiters = BlockDelimiters {
+            start: "Error:".to_string(),
+            end: None,
+            max_lines: DEFAULT_MAX_BLOCK_LINES,
+        };
+        let (blocks, single_lines) = collect(delimiters, output);
+        assert_eq!(
+            blocks,
+            [
+                "TypeError: Cannot read properties of undefined (reading 'id')\n    at getUser (/app/src/users.js:14:22)\n    at Layer.handle (/app/node_modules/express/lib/router/layer.js:95:5)"
+            ]
+        );
+        assert_eq!(single_lines, ["GET /users 200", "GET /health 200"]);
+    }
+
+    #[test]
+    fn test_rust_panic_ends_at_the_end_text_or_the_size_guard() {
+        let output = "\
+thread 'main' panicked at src/main.rs:12:5:
+called `Option::unwrap()` on a `None` value
+
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+listening on 3000
+";
+        let delimiters = BlockDelimiters {
+            start: "panicked at".to_string(),
+            end: Some("RUST_BACKTRACE".to_string()),
+            max_lines: DEFAULT_MAX_BLOCK_LINES,
+        };
+        let (blocks, single_lines) = collect(delimiters.clone(), output);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].lines().count(), 4);
+        assert!(blocks[0].ends_with("to display a backtrace"));
+        assert_eq!(single_lines, ["listening on 3000"]);
+
+        // A trace that never ends is cut at max_lines, and the rest is matched line by line
+        let endless = format!(
+            "{}\n{}",
+            output.lines().next().unwrap(),
+            "  frame\n".repeat(5)
+        );
+        let (blocks, single_lines) = collect(
+            BlockDelimiters {
+                max_lines: 3,
+                ..delimiters
+            },
+            &endless,
+        );
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].lines().count(), 3);
+        assert_eq!(single_lines.len(), 3);
+    }
+
+    #[test]
+    fn test_a_new_start_ends_the_current_block() {
+        let delimiters = BlockDelimiters {
+            start: "Error:".to_string(),
+            end: None,
+            max_lines: DEFAULT_MAX_BLOCK_LINES,
+        };
+        let (blocks, _) = collect(delimiters, "Error: a\n  at x\nError: b\n  at y");
+        assert_eq!(blocks, ["Error: a\n  at x", "Error: b\n  at y"]);
+    }
+}
@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+/// `restart_burst` and `restart_refill` from dev-cli.json
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RestartBudget {
+    /// Restarts allowed back to back
+    pub burst: u32,
+    /// Restarts earned back per minute
+    pub refill_per_minute: f64,
+    /// Stop instead of waiting when the budget is used up
+    pub strict: bool,
+}
+
+/// Token bucket limiting crash restarts: each restart takes a token, and tokens come back
+/// at a steady rate up to the bucket size. Bursts are fine; a sustained storm is slowed
+/// down to the refill rate.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket
+    pub fn new(budget: &RestartBudget, now: Instant) -> Self {
+        Self {
+            capacity: f64::from(budget.burst),
+            refill_per_sec: budget.refill_per_minute / 60.0,
+            tokens: f64::from(budget.burst),
+            updated: now,
+        }
+    }
+
+    /// Take a token for a restart, or return how long until one is available
+    pub fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.refill_per_sec,
+            ))
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(burst: u32, refill_per_minute: f64, now: Instant) -> TokenBucket {
+        TokenBucket::new(
+            &RestartBudget {
+                burst,
+                refill_per_minute,
+                strict: false,
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn test_burst_then_wait_for_the_refill() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut bucket = bucket(3, 30.0, start);
+        for _ in 0..3 {
+            assert_eq!(bucket.try_take(start), Ok(()));
+        }
+        // One token every 2 seconds
+        assert_eq!(bucket.try_take(start), Err(Duration::from_secs(2)));
+        assert_eq!(bucket.try_take(at(500)), Err(Duration::from_millis(1500)));
+        assert_eq!(bucket.try_take(at(2000)), Ok(()));
+        assert!(bucket.try_take(at(2000)).is_err());
+    }
+
+    #[test]
+    fn test_refill_stops_at_the_burst_size() {
+        let start = Instant::now();
+        let mut bucket = bucket(2, 60.0, start);
+        assert_eq!(bucket.try_take(start), Ok(()));
+        // An hour of quiet earns back only what the bucket holds
+        let later = start + Duration::from_secs(3600);
+        assert_eq!(bucket.try_take(later), Ok(()));
+        assert_eq!(bucket.try_take(later), Ok(()));
+        assert_eq!(bucket.try_take(later), Err(Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use crate::cli_config::CliConfig;
+use crate::error::{Result, ServerError};
+use crate::process;
+use crate::state::{SpawnedProcess, StateStore};
+use crate::theme::{self, Icon};
+
+/// Kill server processes a previous dev session started but never saw exit,
+/// e.g. because dev itself was killed with SIGKILL
+pub fn run(config_path: &Path, profile: Option<&str>) -> Result<()> {
+    let cli_config = CliConfig::read_for_session(config_path, profile)?;
+    let Some(state_file) = cli_config.state_file else {
+        return Err(ServerError::ConfigError(format!(
+            "--cleanup needs state_file in {} to know which processes dev started",
+            config_path.display()
+        )));
+    };
+
+    let store = StateStore::new(state_file);
+    let spawned = store.take_spawned()?;
+    if spawned.is_empty() {
+        println!(
+            "{} No lingering processes recorded",
+            theme::icon(Icon::Cleanup)
+        );
+        return Ok(());
+    }
+
+    let mut killed = 0;
+    for recorded in &spawned {
+        if cleanup_process(recorded) {
+            killed += 1;
+        }
+    }
+    println!(
+        "{} Cleaned up {} of {} recorded process(es)",
+        theme::icon(Icon::Cleanup),
+        killed,
+        spawned.len()
+    );
+    Ok(())
+}
+
+/// Kill one recorded process tree; returns whether anything was killed
+fn cleanup_process(recorded: &SpawnedProcess) -> bool {
+    let Some(live) = process::command_line(recorded.pid) else {
+        println!("   PID {} has already exited", recorded.pid);
+        return false;
+    };
+    if !command_matches(&recorded.command, &live) {
+        println!(
+            "{} Skipping PID {}: it now runs '{}', not the recorded '{}'",
+            theme::icon(Icon::Warning),
+            recorded.pid,
+            live,
+            recorded.command
+        );
+        return false;
+    }
+
+    // Children first, so none of them is left behind when the root goes away
+    #[cfg(not(windows))]
+    for child in process::descendant_pids(recorded.pid).into_iter().rev() {
+        process::signal_pid(child, true);
+    }
+    process::signal_pid(recorded.pid, true);
+    println!(
+        "{} Killed PID {}: {}",
+        theme::icon(Icon::Cleanup),
+        recorded.pid,
+        live
+    );
+    true
+}
+
+/// Whether a live process is still the one that was recorded, guarding against PID reuse
+pub fn command_matches(recorded: &str, live: &str) -> bool {
+    !recorded.is_empty() && recorded.trim() == live.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_matches_guards_against_pid_reuse() {
+        assert!(command_matches("sh -c npm run dev", "sh -c npm run dev\n"));
+        assert!(!command_matches(
+            "sh -c npm run dev",
+            "/usr/bin/vim notes.txt"
+        ));
+        // Nothing was recorded, so there's no way to tell it's ours
+        assert!(!command_matches("", "sh -c npm run dev"));
+    }
+}
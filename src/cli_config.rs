@@ -1,100 +1,1176 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt;
 use std::fs;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::blocks::{BlockDelimiters, DEFAULT_MAX_BLOCK_LINES};
+use crate::conditions::{self, RestartRule};
 use crate::error::{Result, ServerError};
+use crate::exit_rules::{self, ExitCodeRule};
+use crate::exit_status;
+use crate::hooks::HookGroup;
+use crate::levels::LevelColorizer;
+use crate::monitor::{CountPerLine, MAX_READ_BUFFER_SIZE};
+use crate::patterns::{self, ErrorPatterns, PatternRule};
+use crate::prompt;
+use crate::remote::RemoteConfig;
+use crate::rerun::RunMode;
+#[cfg(unix)]
+use crate::signals;
+use crate::status::status;
+use crate::theme::{self, Icon, Theme};
+use crate::trial::{self, TrialOutcome};
 
-const CONFIG_FILE: &str = "dev-cli.json";
+pub const CONFIG_FILE: &str = "dev-cli.json";
+
+/// Environment variable selecting a profile when `--profile` isn't given
+pub const PROFILE_ENV: &str = "DEV_PROFILE";
+
+/// Environment variable replacing `run_command` from the config file, e.g. in CI
+pub const RUN_COMMAND_ENV: &str = "DEV_CLI_RUN_COMMAND";
+
+/// Environment variable replacing `error_pattern` from the config file; commas separate
+/// several patterns
+pub const ERROR_PATTERN_ENV: &str = "DEV_CLI_ERROR_PATTERN";
+
+/// How many files an `extends` chain may span, counting the file that starts it
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// Profile selected through `DEV_PROFILE`, if set and non-empty
+pub fn profile_from_env() -> Option<String> {
+    env::var(PROFILE_ENV)
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// Parse an octal umask such as "0077" or "022"
+fn parse_umask(value: &str) -> Result<u32> {
+    u32::from_str_radix(value.trim(), 8)
+        .ok()
+        .filter(|mask| *mask <= 0o777)
+        .ok_or_else(|| {
+            ServerError::ConfigError(format!(
+                "umask must be an octal mode between 0000 and 0777, got '{}'",
+                value
+            ))
+        })
+}
 
 /// CLI configuration that gets saved to dev-cli.json
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CliConfig {
     pub run_command: String,
-    pub error_pattern: String,
+    /// One pattern or a list of them; the server restarts when any of them is in a line
+    pub error_pattern: ErrorPatterns,
+    /// "server" keeps run_command running; "command" reruns it to completion on file changes
+    #[serde(default, skip_serializing_if = "RunMode::is_server")]
+    pub mode: RunMode,
+    /// Files and directories watched in "command" mode (default: the project directory)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watch_paths: Vec<String>,
+    /// Seconds to wait before the first start, e.g. for a tunnel or container to come up (not between restarts)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_delay_secs: Option<u64>,
+    /// Count the attempt as a failed start if no output arrives within this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_output_timeout_secs: Option<u64>,
+    /// Stop dev with an error if the server isn't ready within this many seconds of starting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_timeout_secs: Option<u64>,
+    /// Warn, but keep waiting, if the server isn't ready within this many seconds of starting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_warn_after_secs: Option<u64>,
+    /// Restart when the server closes stdout and stderr but its process keeps running
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub restart_on_eof: bool,
+    /// Restart when the server exits with an error (default: true); false ends the session instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_on_nonzero_exit: Option<bool>,
+    /// What to do for particular exit codes, e.g. `[{"code": 137, "action": "restart", "notify": "OOM-killed"}]`; unmatched exits follow the defaults
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_code_rules: Vec<ExitCodeRule>,
+    /// Restart when an error pattern matches (default: true); false only reports the match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_on_error: Option<bool>,
+    /// Ask "Restart now? [Y/n/q]" before restarting after an error pattern (interactive terminals only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm_restart: bool,
+    /// Seconds `confirm_restart` waits for an answer before restarting (default: 10; 0 waits forever)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm_restart_timeout_secs: Option<u64>,
+    /// Give up after this many consecutive spawn failures (unlimited when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_spawn_retries: Option<u32>,
+    /// Stop instead of restarting once the server has been restarted after a crash this many times
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
+    /// Message printed when `max_restarts` is reached; `{count}` and `{last_error}` are filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_restarts_message: Option<String>,
+    /// Crash restarts allowed back to back before `restart_refill` limits them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_burst: Option<u32>,
+    /// Crash restarts earned back per minute, up to `restart_burst`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_refill: Option<f64>,
+    /// Stop instead of waiting for a restart to be earned back
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub restart_budget_strict: bool,
+    /// Grow the restart delay by this factor for each crash in a row, e.g. 2 to double it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_multiplier: Option<f64>,
+    /// Upper bound in seconds for the restart delay grown by `backoff_multiplier`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_restart_delay_secs: Option<u64>,
+    /// Seconds a server must run before a crash counts as new rather than in a row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stability_window_secs: Option<u64>,
+    /// Upper bound in seconds for the spawn-failure backoff delay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawn_backoff_cap_secs: Option<u64>,
+    /// Output text that means the server is up and serving
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_pattern: Option<String>,
+    /// Command run after each start until it exits with 0, meaning the server is ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_command: Option<String>,
+    /// Milliseconds before the first `ready_command` retry, doubling up to 5s (default: 500)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_command_interval_ms: Option<u64>,
+    /// Seconds one run of `ready_command` may take before it's killed and counts as not ready (default: 5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_command_timeout_secs: Option<u64>,
+    /// Line printed to stdout the first time the server becomes ready (default: DEV_READY)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_marker: Option<String>,
+    /// File written the first time the server becomes ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_file: Option<String>,
+    /// Command started (without waiting) the first time the server becomes ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_ready: Option<String>,
+    /// Command run to completion before each restart, told why through `DEV_RESTART_REASON`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_restart: Option<String>,
+    /// Command run to completion when the server exits normally and the session ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_clean_exit: Option<String>,
+    /// Color each output line by its leading level token (INFO, WARN, ERROR, ...)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub parse_levels: bool,
+    /// Level name → color name used when `parse_levels` is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level_patterns: Option<HashMap<String, String>>,
+    /// Highlight the part of an error line that matched an error pattern
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub highlight_match: bool,
+    /// Prefix each output line with the number of the attempt that printed it (`#3 | ...`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub attempt_prefix: bool,
+    /// Show stderr lines in red as errors; by default stderr is just another output stream
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stderr_is_error: bool,
+    /// Read and match the server's stdout (default: true); false leaves it on the terminal untouched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_stdout: Option<bool>,
+    /// Read and match the server's stderr (default: true); false leaves it on the terminal untouched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_stderr: Option<bool>,
+    /// Only ever ask the server to exit (SIGTERM / CTRL_BREAK); never force-kill it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_force_kill: bool,
+    /// Keep dev and the server running when the terminal closes (Unix); by default they are stopped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nohup_mode: bool,
+    /// Seconds to wait for a killed server to exit before giving up (default: 5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shutdown_timeout_secs: Option<u64>,
+    /// Milliseconds between checks whether a killed server has exited (default: 50)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shutdown_poll_interval_ms: Option<u64>,
+    /// Seconds into a shutdown wait before progress is printed (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shutdown_warn_after_secs: Option<u64>,
+    /// Seconds between "still waiting" warnings while a `no_force_kill` shutdown is pending;
+    /// the wait itself never ends early
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graceful_shutdown_warn_secs: Option<u64>,
+    /// Extra error patterns with per-pattern settings such as `restart_delay_secs`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pattern_rules: Vec<PatternRule>,
+    /// Restart when a combination of patterns, uptime and exit code is met, e.g. `[{"all": [{"pattern": "[Error"}, {"uptime_at_least_secs": 10}]}]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restart_when: Vec<RestartRule>,
+    /// Restart only after this many error matches (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_threshold: Option<u32>,
+    /// Only count matches from the last this-many seconds toward `error_threshold`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_window_secs: Option<u64>,
+    /// "once" (default): a line adds one toward `error_threshold`; "all": one per match in it
+    #[serde(default, skip_serializing_if = "CountPerLine::is_once")]
+    pub count_per_line: CountPerLine,
+    /// Log every error match with its running count toward `error_threshold`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub report_matches: bool,
+    /// Output lines meaning the server is wedged; they restart it like errors but are reported as stalls
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stall_patterns: Vec<String>,
+    /// Text that starts a multi-line block such as a stack trace, matched and counted as one error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_start: Option<String>,
+    /// Text on a block's last line; without it a blank line ends the block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_end: Option<String>,
+    /// Lines after which a block is cut off and matched (default: 200)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_block_lines: Option<usize>,
+    /// Output lines that are never printed, e.g. a deprecation warning on every request; still matched against the error patterns
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mute_patterns: Vec<String>,
+    /// Report an error identical to the previous restart's as a repeat count instead of in full
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dedupe_errors: bool,
+    /// Bytes buffered per output stream; larger buffers mean fewer reads for very long lines (default: 8192)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_buffer_size: Option<usize>,
+    /// Byte that separates output records, e.g. 0 for null-delimited output (default: 10, newline)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<u8>,
+    /// Shared file of extra error patterns (one per line, `#` comments, or a JSON array)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patterns_file: Option<String>,
+    /// Like `patterns_file`, but every entry is a regular expression whose named groups are reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex_patterns_file: Option<String>,
+    /// Built-in regex error patterns added to the others (`universal`); used on its own when
+    /// `error_pattern` is empty and nothing else is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern_set: Option<String>,
+    /// File where state such as the last known good command is remembered between sessions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_file: Option<String>,
+    /// Offer the last known good command after this many consecutive start failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_after_failures: Option<u32>,
+    /// Command run for every lifecycle event with a JSON event object on stdin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_command: Option<String>,
+    /// File every lifecycle event is appended to as one JSON line, e.g. for `tail -f | jq`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_log: Option<String>,
+    /// Restart the server when its process tree uses more memory than this (needs the resource-limits feature)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+    /// Restart the server when its process tree uses more CPU than this; 100 = one full core
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cpu_percent: Option<f32>,
+    /// Seconds between resource usage samples (default: 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_sample_interval_secs: Option<u64>,
+    /// Seconds a resource limit must stay exceeded before restarting (default: 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_limit_window_secs: Option<u64>,
+    /// Signals that count as a crash when they end the server (Unix; default: all)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crash_signals: Vec<String>,
+    /// Signals that mean the server was stopped on purpose, so it isn't restarted (Unix)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_signals: Vec<String>,
+    /// Run `run_command` on this host over SSH instead of locally
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteConfig>,
+    /// Prefix set for dev's output: "emoji" (default), "minimal", "nerdfont" or "plain"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Print the exact program, arguments, environment and directory before each spawn
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub echo_command: bool,
+    /// Environment variables set for the dev command; `${vault:<path>}` in a value is filled in by `secret_command`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Start the dev command with an empty environment instead of dev's own, plus `env`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub env_clear: bool,
+    /// Variables kept from dev's environment when `env_clear` is set, e.g. ["PATH", "HOME"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_inherit: Vec<String>,
+    /// Command that prints the secret at `{path}` to stdout, e.g. "vault kv get -field=value {path}"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_command: Option<String>,
+    /// Command the dev command runs under, e.g. "time -v" or "strace -f"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapper: Option<String>,
+    /// Octal umask the dev command is started with on Unix, e.g. "0077"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub umask: Option<String>,
+    /// User ID the dev command runs as on Unix; dev must run as root to switch to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_as_uid: Option<u32>,
+    /// Group ID the dev command runs as on Unix; dev must run as root to switch to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_as_gid: Option<u32>,
+    /// Directory the dev command runs in, e.g. "frontend"; relative to the config file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    /// Copy this directory (e.g. ".") to a temporary directory before every start and run there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fresh_workdir: Option<String>,
+    /// Commands run before the dev server first starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_commands: Option<HookGroup>,
+    /// One-time setup command (migrations, code generation) run before `pre_commands`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup: Option<String>,
+    /// Command run periodically while the server runs, for custom liveness checks or heartbeats
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_tick: Option<String>,
+    /// Minimum seconds between `on_tick` runs (default: 5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_tick_interval_secs: Option<u64>,
+    /// Reload dev-cli.json when it changes while the server is running
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub watch_config: bool,
+    /// Milliseconds `dev-cli.json` must stay unchanged before a burst of writes is reloaded (default: 300)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watch_debounce_ms: Option<u64>,
+    /// Milliseconds after a `watch_config` restart during which error patterns don't restart the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rebuild_grace_ms: Option<u64>,
+    /// File that receives a plain copy of the server's stdout and stderr
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<String>,
+    /// Where dev's own status lines go instead of stdout: a file path, or `stderr`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor_log: Option<String>,
+    /// Named sets of fields that override the base settings, selected with `--profile` or `DEV_PROFILE`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+}
+
+/// Fields that only take effect when the server process is restarted
+const RESTART_FIELDS: [&str; 15] = [
+    "run_command",
+    "env",
+    "env_clear",
+    "env_inherit",
+    "secret_command",
+    "wrapper",
+    "umask",
+    "remote",
+    "run_as_uid",
+    "run_as_gid",
+    "nohup_mode",
+    "capture_stdout",
+    "capture_stderr",
+    "working_dir",
+    "fresh_workdir",
+];
+
+/// One changed field between two configurations (`None` when the field is absent)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+/// `error_pattern: "[Error" → "ERROR:"`, values as they'd appear in dev-cli.json. `env`
+/// may hold secrets, so only the names of the variables that changed are shown.
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field == "env" {
+            return write!(f, "env: {}", env_changes(&self.old, &self.new));
+        }
+        let show = |value: &Option<serde_json::Value>| match value {
+            Some(value) => value.to_string(),
+            None => "(unset)".to_string(),
+        };
+        write!(
+            f,
+            "{}: {} → {}",
+            self.field,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+/// `DB_PASSWORD changed, DEBUG added` for two values of `env`, without the values
+fn env_changes(old: &Option<serde_json::Value>, new: &Option<serde_json::Value>) -> String {
+    let vars = |value: &Option<serde_json::Value>| {
+        value
+            .as_ref()
+            .and_then(serde_json::Value::as_object)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let (old, new) = (vars(old), vars(new));
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| match (old.get(name), new.get(name)) {
+            (None, Some(_)) => Some(format!("{} added", name)),
+            (Some(_), None) => Some(format!("{} removed", name)),
+            (Some(old), Some(new)) if old != new => Some(format!("{} changed", name)),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Result of comparing two configurations with [`CliConfig::diff`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigDiff {
+    pub changes: Vec<FieldChange>,
+}
+
+impl ConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether a changed field only applies after restarting the server
+    pub fn requires_restart(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|change| RESTART_FIELDS.contains(&change.field.as_str()))
+    }
+
+    pub fn field_names(&self) -> Vec<&str> {
+        self.changes.iter().map(|c| c.field.as_str()).collect()
+    }
+
+    /// The combined change of applying this diff and then `later`;
+    /// a field changed and then changed back drops out
+    pub fn followed_by(&self, later: &ConfigDiff) -> ConfigDiff {
+        let mut changes = self.changes.clone();
+        for change in &later.changes {
+            match changes.iter_mut().find(|c| c.field == change.field) {
+                Some(existing) => existing.new = change.new.clone(),
+                None => changes.push(change.clone()),
+            }
+        }
+        changes.retain(|c| c.old != c.new);
+        changes.sort_by(|a, b| a.field.cmp(&b.field));
+        ConfigDiff { changes }
+    }
+}
+
+/// Fields of a config file with the file it `extends` (and so on) merged underneath, the
+/// file's own fields winning. `chain` holds the files already read, to catch cycles.
+fn merge_extends(
+    path: &Path,
+    mut fields: serde_json::Map<String, serde_json::Value>,
+    mut chain: Vec<PathBuf>,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let Some(extends) = fields.remove("extends") else {
+        return Ok(fields);
+    };
+    let serde_json::Value::String(base) = extends else {
+        return Err(ServerError::ConfigError(format!(
+            "extends in {} must be a path",
+            path.display()
+        )));
+    };
+
+    // Relative to the extending file, not to where dev runs
+    let base_path = path.parent().unwrap_or(Path::new("")).join(&base);
+    let canonical = base_path.canonicalize().map_err(|e| {
+        ServerError::ConfigError(format!(
+            "{} extends {}, which can't be read: {}",
+            path.display(),
+            base_path.display(),
+            e
+        ))
+    })?;
+    if chain.contains(&canonical) {
+        let cycle: Vec<String> = chain
+            .iter()
+            .chain([&canonical])
+            .map(|file| file.display().to_string())
+            .collect();
+        return Err(ServerError::ConfigError(format!(
+            "extends cycle: {}",
+            cycle.join(" → ")
+        )));
+    }
+    if chain.len() >= MAX_EXTENDS_DEPTH {
+        return Err(ServerError::ConfigError(format!(
+            "extends chain from {} is longer than {} files",
+            chain[0].display(),
+            MAX_EXTENDS_DEPTH
+        )));
+    }
+    chain.push(canonical);
+
+    let content = fs::read_to_string(&base_path).map_err(|e| {
+        ServerError::IoError(format!("Failed to read {}: {}", base_path.display(), e))
+    })?;
+    let base_fields = serde_json::from_str(&content).map_err(|e| {
+        ServerError::IoError(format!("Failed to parse {}: {}", base_path.display(), e))
+    })?;
+    let mut merged = merge_extends(&base_path, base_fields, chain)?;
+    merged.extend(fields);
+    Ok(merged)
+}
+
+/// Serialized fields of a configuration, keyed by field name
+fn config_fields(config: &CliConfig) -> serde_json::Map<String, serde_json::Value> {
+    match serde_json::to_value(config) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    }
 }
 
 impl Default for CliConfig {
     fn default() -> Self {
         Self {
             run_command: "pnpm dev".to_string(),
-            error_pattern: "[Error".to_string(),
+            error_pattern: "[Error".into(),
+            mode: RunMode::Server,
+            watch_paths: Vec::new(),
+            first_output_timeout_secs: None,
+            initial_delay_secs: None,
+            ready_timeout_secs: None,
+            ready_warn_after_secs: None,
+            restart_on_eof: false,
+            restart_on_nonzero_exit: None,
+            exit_code_rules: Vec::new(),
+            restart_on_error: None,
+            confirm_restart: false,
+            confirm_restart_timeout_secs: None,
+            max_spawn_retries: None,
+            max_restarts: None,
+            max_restarts_message: None,
+            restart_burst: None,
+            restart_refill: None,
+            restart_budget_strict: false,
+            backoff_multiplier: None,
+            max_restart_delay_secs: None,
+            stability_window_secs: None,
+            spawn_backoff_cap_secs: None,
+            ready_pattern: None,
+            ready_command: None,
+            ready_command_interval_ms: None,
+            ready_command_timeout_secs: None,
+            ready_marker: None,
+            ready_file: None,
+            on_ready: None,
+            on_restart: None,
+            on_clean_exit: None,
+            parse_levels: false,
+            level_patterns: None,
+            highlight_match: false,
+            attempt_prefix: false,
+            stderr_is_error: false,
+            capture_stdout: None,
+            capture_stderr: None,
+            no_force_kill: false,
+            nohup_mode: false,
+            shutdown_timeout_secs: None,
+            shutdown_poll_interval_ms: None,
+            shutdown_warn_after_secs: None,
+            graceful_shutdown_warn_secs: None,
+            pattern_rules: Vec::new(),
+            restart_when: Vec::new(),
+            error_threshold: None,
+            error_window_secs: None,
+            count_per_line: CountPerLine::Once,
+            report_matches: false,
+            stall_patterns: Vec::new(),
+            block_start: None,
+            block_end: None,
+            max_block_lines: None,
+            mute_patterns: Vec::new(),
+            dedupe_errors: false,
+            read_buffer_size: None,
+            delimiter: None,
+            patterns_file: None,
+            regex_patterns_file: None,
+            pattern_set: None,
+            state_file: None,
+            fallback_after_failures: None,
+            event_command: None,
+            event_log: None,
+            max_memory_mb: None,
+            max_cpu_percent: None,
+            resource_sample_interval_secs: None,
+            resource_limit_window_secs: None,
+            crash_signals: Vec::new(),
+            stop_signals: Vec::new(),
+            remote: None,
+            theme: None,
+            echo_command: false,
+            env: BTreeMap::new(),
+            env_clear: false,
+            env_inherit: Vec::new(),
+            secret_command: None,
+            wrapper: None,
+            umask: None,
+            run_as_uid: None,
+            run_as_gid: None,
+            working_dir: None,
+            fresh_workdir: None,
+            pre_commands: None,
+            warmup: None,
+            on_tick: None,
+            on_tick_interval_secs: None,
+            watch_config: false,
+            watch_debounce_ms: None,
+            rebuild_grace_ms: None,
+            log_file: None,
+            monitor_log: None,
+            profiles: HashMap::new(),
         }
     }
 }
 
-impl CliConfig {
-    /// Load configuration from dev-cli.json or create it if it doesn't exist
-    pub fn load_or_create() -> Result<Self> {
-        let config_path = Path::new(CONFIG_FILE);
+/// Run the command briefly during setup; returns whether it looks usable
+fn try_command(run_command: &str) -> Result<bool> {
+    println!(
+        "{} Running '{}' for up to {}s...",
+        theme::icon(Icon::Start),
+        run_command,
+        trial::TRIAL_DURATION.as_secs()
+    );
+    let cli_config = CliConfig {
+        run_command: run_command.to_string(),
+        ..CliConfig::default()
+    };
+    let outcome = trial::run(&cli_config, trial::TRIAL_DURATION)?;
+    match &outcome {
+        TrialOutcome::StillRunning => println!(
+            "{} Still running after {}s; stopped it",
+            theme::icon(Icon::Success),
+            trial::TRIAL_DURATION.as_secs()
+        ),
+        TrialOutcome::Exited(status) if status.success() => println!(
+            "{} The command finished successfully",
+            theme::icon(Icon::Success)
+        ),
+        TrialOutcome::Exited(status) => println!(
+            "{} The command {}; enter it again",
+            theme::icon(Icon::Failure),
+            exit_status::format(status)
+        ),
+        TrialOutcome::Unknown => println!(
+            "{} The command exited, but its exit status is unknown",
+            theme::icon(Icon::Warning)
+        ),
+    }
+    Ok(!outcome.failed())
+}
 
-        if config_path.exists() {
-            println!("📄 Loading configuration from {}", CONFIG_FILE);
-            Self::load_from_file(config_path)
+impl CliConfig {
+    /// Load configuration from `config_path` (dev-cli.json unless `--config-path` says
+    /// otherwise) or create it if it doesn't exist and merge the selected profile over the
+    /// base settings
+    pub fn load_or_create(config_path: &Path, profile: Option<&str>) -> Result<Self> {
+        let config = if config_path.exists() {
+            status!(
+                "{} Loading configuration from {}",
+                theme::icon(Icon::Config),
+                config_path.display()
+            );
+            Self::load_from_file(config_path, profile)?
         } else {
-            println!("📄 Configuration file {} not found", CONFIG_FILE);
-            Self::create_interactive()
+            println!(
+                "{} Configuration file {} not found",
+                theme::icon(Icon::Config),
+                config_path.display()
+            );
+            Self::create_interactive(config_path)?.with_profile(profile, config_path)?
+        };
+        let config = config.resolve_working_dir(config_path)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Merge the named profile over these settings, read from `config_path`. Every field the
+    /// profile sets replaces the base field as a whole; `null` clears an optional field.
+    /// `None` keeps the base config.
+    pub fn with_profile(self, profile: Option<&str>, config_path: &Path) -> Result<Self> {
+        let Some(name) = profile else {
+            return Ok(self);
+        };
+        let Some(overrides) = self.profiles.get(name) else {
+            let mut available: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            available.sort_unstable();
+            return Err(ServerError::ConfigError(if available.is_empty() {
+                format!(
+                    "profile '{}' not found: {} has no profiles",
+                    name,
+                    config_path.display()
+                )
+            } else {
+                format!(
+                    "profile '{}' not found (available: {})",
+                    name,
+                    available.join(", ")
+                )
+            }));
+        };
+
+        let mut fields = config_fields(&self);
+        for (field, value) in overrides {
+            if field != "profiles" {
+                fields.insert(field.clone(), value.clone());
+            }
+        }
+        serde_json::from_value(serde_json::Value::Object(fields))
+            .map_err(|e| ServerError::ConfigError(format!("profile '{}': {}", name, e)))
+    }
+
+    /// Make a relative `working_dir` relative to the directory of `config_path`, the file
+    /// these settings were read from, rather than to wherever dev was started, and check
+    /// that the directory exists
+    pub fn resolve_working_dir(mut self, config_path: &Path) -> Result<Self> {
+        let Some(dir) = &self.working_dir else {
+            return Ok(self);
+        };
+        let dir = config_path.parent().unwrap_or(Path::new("")).join(dir);
+        if !dir.is_dir() {
+            return Err(ServerError::ConfigError(format!(
+                "working_dir {} doesn't exist or isn't a directory",
+                dir.display()
+            )));
+        }
+        self.working_dir = Some(dir.to_string_lossy().into_owned());
+        Ok(self)
+    }
+
+    /// Replace `run_command` and `error_pattern` with `DEV_CLI_RUN_COMMAND` and
+    /// `DEV_CLI_ERROR_PATTERN`, looked up through `var`. Unset or blank variables keep the file's
+    /// value. Returns the variables that were applied, with the field each replaced.
+    pub fn apply_env_overrides(
+        &mut self,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Vec<(&'static str, &'static str)> {
+        let mut applied = Vec::new();
+        if let Some(run_command) = var(RUN_COMMAND_ENV).filter(|value| !value.trim().is_empty()) {
+            self.run_command = run_command;
+            applied.push((RUN_COMMAND_ENV, "run_command"));
+        }
+        if let Some(patterns) = var(ERROR_PATTERN_ENV)
+            .map(|value| ErrorPatterns::parse_list(&value))
+            .filter(|patterns| !patterns.is_empty())
+        {
+            self.error_pattern = patterns;
+            applied.push((ERROR_PATTERN_ENV, "error_pattern"));
+        }
+        applied
+    }
+
+    /// Check settings that can't be expressed through the JSON types alone
+    pub fn validate(&self) -> Result<()> {
+        if let Some(patterns) = &self.level_patterns {
+            LevelColorizer::from_patterns(patterns)
+                .map_err(|e| ServerError::ConfigError(format!("level_patterns: {}", e)))?;
+        }
+        self.error_patterns()?;
+        conditions::validate(&self.restart_when)?;
+        exit_rules::validate(&self.exit_code_rules)?;
+        for pattern in self.regex_patterns()? {
+            patterns::compile_regex(&pattern)?;
+        }
+        if let Some(size) = self.read_buffer_size
+            && !(1..=MAX_READ_BUFFER_SIZE).contains(&size)
+        {
+            return Err(ServerError::ConfigError(format!(
+                "read_buffer_size must be between 1 and {} bytes",
+                MAX_READ_BUFFER_SIZE
+            )));
+        }
+        if self.shutdown_poll_interval_ms == Some(0) {
+            return Err(ServerError::ConfigError(
+                "shutdown_poll_interval_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.error_threshold == Some(0) {
+            return Err(ServerError::ConfigError(
+                "error_threshold must be at least 1".to_string(),
+            ));
+        }
+        if self.first_output_timeout_secs == Some(0) {
+            // Every start would fail before the server could print anything
+            return Err(ServerError::ConfigError(
+                "first_output_timeout_secs must be at least 1".to_string(),
+            ));
+        }
+        #[cfg(unix)]
+        {
+            signals::parse_signals("crash_signals", &self.crash_signals)?;
+            signals::parse_signals("stop_signals", &self.stop_signals)?;
+        }
+        self.umask()?;
+        #[cfg(unix)]
+        self.check_run_as()?;
+        self.theme()?;
+        self.blocks()?;
+        let checks_readiness = self.ready_pattern.is_some() || self.ready_command.is_some();
+        if self.ready_timeout_secs.is_some() && !checks_readiness {
+            return Err(ServerError::ConfigError(
+                "ready_timeout_secs requires ready_pattern or ready_command to tell when the server is ready"
+                    .to_string(),
+            ));
+        }
+        if self.ready_warn_after_secs.is_some() && !checks_readiness {
+            return Err(ServerError::ConfigError(
+                "ready_warn_after_secs requires ready_pattern or ready_command to tell when the server is ready"
+                    .to_string(),
+            ));
+        }
+        if self.ready_command.is_none()
+            && (self.ready_command_interval_ms.is_some()
+                || self.ready_command_timeout_secs.is_some())
+        {
+            return Err(ServerError::ConfigError(
+                "ready_command_interval_ms and ready_command_timeout_secs require ready_command"
+                    .to_string(),
+            ));
+        }
+        if self.ready_command_interval_ms == Some(0) || self.ready_command_timeout_secs == Some(0) {
+            return Err(ServerError::ConfigError(
+                "ready_command_interval_ms and ready_command_timeout_secs must be at least 1"
+                    .to_string(),
+            ));
+        }
+        if let (Some(warn_after), Some(timeout)) =
+            (self.ready_warn_after_secs, self.ready_timeout_secs)
+            && warn_after >= timeout
+        {
+            return Err(ServerError::ConfigError(format!(
+                "ready_warn_after_secs ({}) must be less than ready_timeout_secs ({}), or the warning never shows",
+                warn_after, timeout
+            )));
+        }
+        match (self.restart_burst, self.restart_refill) {
+            (None, None) if self.restart_budget_strict => {
+                return Err(ServerError::ConfigError(
+                    "restart_budget_strict requires restart_burst and restart_refill".to_string(),
+                ));
+            }
+            (None, None) => {}
+            (Some(burst), Some(refill)) => {
+                if burst == 0 || !(refill > 0.0 && refill.is_finite()) {
+                    return Err(ServerError::ConfigError(
+                        "restart_burst must be at least 1 and restart_refill more than 0"
+                            .to_string(),
+                    ));
+                }
+            }
+            _ => {
+                return Err(ServerError::ConfigError(
+                    "restart_burst and restart_refill must be set together".to_string(),
+                ));
+            }
+        }
+        match self.backoff_multiplier {
+            Some(multiplier) if !(multiplier >= 1.0 && multiplier.is_finite()) => {
+                return Err(ServerError::ConfigError(format!(
+                    "backoff_multiplier ({}) must be at least 1",
+                    multiplier
+                )));
+            }
+            None if self.max_restart_delay_secs.is_some()
+                || self.stability_window_secs.is_some() =>
+            {
+                return Err(ServerError::ConfigError(
+                    "max_restart_delay_secs and stability_window_secs require backoff_multiplier"
+                        .to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if self.capture_stdout == Some(false) && self.capture_stderr == Some(false) {
+            return Err(ServerError::ConfigError(
+                "capture_stdout and capture_stderr can't both be false: error_pattern needs a stream to match"
+                    .to_string(),
+            ));
+        }
+        if self.fresh_workdir.is_some() && self.remote.is_some() {
+            return Err(ServerError::ConfigError(
+                "fresh_workdir copies a local directory; it can't be combined with remote"
+                    .to_string(),
+            ));
+        }
+        if self.working_dir.is_some() && (self.remote.is_some() || self.fresh_workdir.is_some()) {
+            return Err(ServerError::ConfigError(
+                "working_dir can't be combined with remote or fresh_workdir, which pick the directory the server runs in"
+                    .to_string(),
+            ));
+        }
+        if !self.env_inherit.is_empty() && !self.env_clear {
+            return Err(ServerError::ConfigError(
+                "env_inherit requires env_clear; without it every variable is inherited"
+                    .to_string(),
+            ));
+        }
+        if self.fallback_after_failures.is_some() && self.state_file.is_none() {
+            return Err(ServerError::ConfigError(
+                "fallback_after_failures requires state_file to remember the last known good command"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The `wrapper` setting, unless it's blank
+    pub fn wrapper(&self) -> Option<&str> {
+        self.wrapper
+            .as_deref()
+            .map(str::trim)
+            .filter(|wrapper| !wrapper.is_empty())
+    }
+
+    /// The `theme` setting, defaulting to emoji
+    pub fn theme(&self) -> Result<Theme> {
+        self.theme
+            .as_deref()
+            .map_or(Ok(Theme::default()), Theme::from_name)
+    }
+
+    /// Switching to another user or group takes root; caught here, since a failed switch
+    /// would otherwise only show up as the server failing to start, over and over
+    #[cfg(unix)]
+    fn check_run_as(&self) -> Result<()> {
+        // SAFETY: these only read the process's own IDs
+        let (euid, egid) = unsafe { (libc::geteuid(), libc::getegid()) };
+        if euid == 0 {
+            return Ok(());
+        }
+        for (field, id, current) in [
+            ("run_as_uid", self.run_as_uid, euid),
+            ("run_as_gid", self.run_as_gid, egid),
+        ] {
+            if let Some(id) = id
+                && id != current
+            {
+                return Err(ServerError::ConfigError(format!(
+                    "{} {} needs dev to run as root (it runs as uid {})",
+                    field, id, euid
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The `umask` setting as mode bits
+    pub fn umask(&self) -> Result<Option<u32>> {
+        self.umask.as_deref().map(parse_umask).transpose()
+    }
+
+    /// The `block_start`, `block_end` and `max_block_lines` settings, if blocks are on
+    pub fn blocks(&self) -> Result<Option<BlockDelimiters>> {
+        let Some(start) = &self.block_start else {
+            if self.block_end.is_some() || self.max_block_lines.is_some() {
+                return Err(ServerError::ConfigError(
+                    "block_end and max_block_lines require block_start".to_string(),
+                ));
+            }
+            return Ok(None);
+        };
+        if start.is_empty() || self.block_end.as_deref() == Some("") {
+            return Err(ServerError::ConfigError(
+                "block_start and block_end can't be empty".to_string(),
+            ));
+        }
+        if self.max_block_lines == Some(0) {
+            return Err(ServerError::ConfigError(
+                "max_block_lines must be at least 1".to_string(),
+            ));
+        }
+        Ok(Some(BlockDelimiters {
+            start: start.clone(),
+            end: self.block_end.clone(),
+            max_lines: self.max_block_lines.unwrap_or(DEFAULT_MAX_BLOCK_LINES),
+        }))
+    }
+
+    /// The inline error patterns merged with any patterns from `pattern_rules` and
+    /// `patterns_file`, deduplicated
+    pub fn error_patterns(&self) -> Result<Vec<String>> {
+        let mut all: Vec<String> = Vec::new();
+        let mut candidates: Vec<String> = self.error_pattern.iter().map(str::to_string).collect();
+        candidates.extend(
+            self.pattern_rules
+                .iter()
+                .filter(|rule| !rule.regex)
+                .map(|rule| rule.pattern.clone()),
+        );
+        if let Some(path) = &self.patterns_file {
+            candidates.extend(patterns::load_patterns_file(Path::new(path))?);
+        }
+        for pattern in candidates {
+            if !all.contains(&pattern) {
+                all.push(pattern);
+            }
+        }
+        Ok(all)
+    }
+
+    /// Regex error patterns from `pattern_rules`, `regex_patterns_file` and the pattern set,
+    /// deduplicated
+    pub fn regex_patterns(&self) -> Result<Vec<String>> {
+        let mut all: Vec<String> = Vec::new();
+        let mut candidates: Vec<String> = self
+            .pattern_rules
+            .iter()
+            .filter(|rule| rule.regex)
+            .map(|rule| rule.pattern.clone())
+            .collect();
+        if let Some(path) = &self.regex_patterns_file {
+            candidates.extend(patterns::load_patterns_file(Path::new(path))?);
+        }
+        if let Some(name) = self.active_pattern_set() {
+            candidates.extend(patterns::pattern_set(name)?.iter().map(|p| p.to_string()));
+        }
+        for pattern in candidates {
+            if !all.contains(&pattern) {
+                all.push(pattern);
+            }
+        }
+        Ok(all)
+    }
+
+    /// The built-in pattern set in use: the configured one, or `universal` when no other
+    /// pattern is configured at all
+    pub fn active_pattern_set(&self) -> Option<&str> {
+        let no_patterns = self.error_pattern.is_empty()
+            && self.pattern_rules.is_empty()
+            && self.patterns_file.is_none()
+            && self.regex_patterns_file.is_none();
+        match &self.pattern_set {
+            Some(name) => Some(name),
+            None if no_patterns => Some("universal"),
+            None => None,
         }
     }
 
     /// Load configuration from existing file
-    fn load_from_file(path: &Path) -> Result<Self> {
-        let content = fs::read_to_string(path)
-            .map_err(|e| ServerError::IoError(format!("Failed to read {}: {}", CONFIG_FILE, e)))?;
+    fn load_from_file(path: &Path, profile: Option<&str>) -> Result<Self> {
+        let config = Self::read_from_file(path)?.with_profile(profile, path)?;
 
-        let config: CliConfig = serde_json::from_str(&content)
-            .map_err(|e| ServerError::IoError(format!("Failed to parse {}: {}", CONFIG_FILE, e)))?;
+        status!("{} Loaded configuration:", theme::icon(Icon::Success));
+        if let Some(profile) = profile {
+            status!("   Profile: {}", profile);
+        }
+        status!("   Run command: {}", config.run_command);
+        status!("   Error pattern: {}", config.error_pattern);
+        if let Some(name) = config.active_pattern_set() {
+            status!("   Pattern set: {}", name);
+        }
 
-        println!("✅ Loaded configuration:");
-        println!("   Run command: {}", config.run_command);
-        println!("   Error pattern: {}", config.error_pattern);
+        Ok(config)
+    }
 
+    /// The configuration a session with `profile` runs with, `DEV_CLI_*` variables included,
+    /// for the commands that look at a session instead of starting one; prints nothing
+    pub fn read_for_session(path: &Path, profile: Option<&str>) -> Result<Self> {
+        let mut config = Self::read_from_file(path)?.with_profile(profile, path)?;
+        config.apply_env_overrides(|name| env::var(name).ok());
         Ok(config)
     }
 
+    /// Read and parse a configuration file without printing anything
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            ServerError::IoError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+
+        let parse_error = |e: serde_json::Error| {
+            ServerError::IoError(format!("Failed to parse {}: {}", path.display(), e))
+        };
+        let fields: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&content).map_err(parse_error)?;
+        if !fields.contains_key("extends") {
+            // Parsed from the text again so type errors keep their line and column
+            return serde_json::from_str(&content).map_err(parse_error);
+        }
+
+        let chain = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+        let fields = merge_extends(path, fields, chain)?;
+        serde_json::from_value(serde_json::Value::Object(fields)).map_err(parse_error)
+    }
+
+    /// Field-level changes between this configuration and a newer one
+    pub fn diff(&self, new: &CliConfig) -> ConfigDiff {
+        let old = config_fields(self);
+        let new = config_fields(new);
+
+        let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        let changes = names
+            .into_iter()
+            .filter(|name| old.get(*name) != new.get(*name))
+            .map(|name| FieldChange {
+                field: name.clone(),
+                old: old.get(name).cloned(),
+                new: new.get(name).cloned(),
+            })
+            .collect();
+        ConfigDiff { changes }
+    }
+
     /// Create configuration interactively and save to file
-    fn create_interactive() -> Result<Self> {
-        println!("🔧 Let's set up your dev server configuration!");
+    fn create_interactive(config_path: &Path) -> Result<Self> {
+        println!(
+            "{} Let's set up your dev server configuration!",
+            theme::icon(Icon::Setup)
+        );
         println!();
 
-        // Get run command
-        print!("Enter the command to run your dev server [default: pnpm dev]: ");
-        io::stdout().flush().unwrap();
-        let mut run_command = String::new();
-        io::stdin()
-            .read_line(&mut run_command)
-            .map_err(|e| ServerError::IoError(format!("Failed to read input: {}", e)))?;
-        let run_command = run_command.trim();
-        let run_command = if run_command.is_empty() {
-            "pnpm dev".to_string()
-        } else {
-            run_command.to_string()
+        let mut input = prompt::LineReader::stdin(prompt::input_timeout_from_env());
+
+        // Get run command, trying it out first if asked so a typo is caught before saving
+        let run_command = loop {
+            print!("Enter the command to run your dev server [default: pnpm dev]: ");
+            io::stdout().flush().unwrap();
+            let run_command = input.read_line()?.unwrap_or_default();
+            let run_command = run_command.trim();
+            let run_command = if run_command.is_empty() {
+                "pnpm dev".to_string()
+            } else {
+                run_command.to_string()
+            };
+            if !input.confirm("Test this command now?", false)? || try_command(&run_command)? {
+                break run_command;
+            }
         };
 
-        // Get error pattern
-        print!("Enter the error pattern to watch for [default: [Error]: ");
+        // Get error patterns
+        print!("Enter the error pattern(s) to watch for, separated by commas [default: [Error]: ");
         io::stdout().flush().unwrap();
-        let mut error_pattern = String::new();
-        io::stdin()
-            .read_line(&mut error_pattern)
-            .map_err(|e| ServerError::IoError(format!("Failed to read input: {}", e)))?;
-        let error_pattern = error_pattern.trim();
+        let error_pattern = ErrorPatterns::parse_list(&input.read_line()?.unwrap_or_default());
         let error_pattern = if error_pattern.is_empty() {
-            "[Error".to_string()
+            ErrorPatterns::from("[Error")
         } else {
-            error_pattern.to_string()
+            error_pattern
         };
 
         let config = CliConfig {
             run_command,
             error_pattern,
+            ..CliConfig::default()
         };
 
         // Save to file
-        config.save_to_file()?;
+        config.save_to_file(config_path)?;
 
         println!();
-        println!("✅ Configuration saved to {}", CONFIG_FILE);
+        println!(
+            "{} Configuration saved to {}",
+            theme::icon(Icon::Success),
+            config_path.display()
+        );
         println!("   Run command: {}", config.run_command);
         println!("   Error pattern: {}", config.error_pattern);
         println!();
@@ -102,26 +1178,35 @@ impl CliConfig {
         Ok(config)
     }
 
-    /// Save configuration to dev-cli.json
-    fn save_to_file(&self) -> Result<()> {
+    /// Save configuration to `path`
+    fn save_to_file(&self, path: &Path) -> Result<()> {
         let json = serde_json::to_string_pretty(self)
             .map_err(|e| ServerError::IoError(format!("Failed to serialize config: {}", e)))?;
 
-        fs::write(CONFIG_FILE, json)
-            .map_err(|e| ServerError::IoError(format!("Failed to write {}: {}", CONFIG_FILE, e)))?;
+        fs::write(path, json).map_err(|e| {
+            ServerError::IoError(format!("Failed to write {}: {}", path.display(), e))
+        })?;
 
         Ok(())
     }
 
-    /// Get the command parts for execution
-    pub fn get_command_parts(&self) -> Vec<&str> {
-        self.run_command.split_whitespace().collect()
+    /// Get the command parts for execution without a shell. A multi-line command is a
+    /// shell script, and splitting it into words would run something else entirely.
+    #[allow(dead_code)]
+    pub fn get_command_parts(&self) -> Result<Vec<&str>> {
+        if self.run_command.trim().contains('\n') {
+            return Err(ServerError::ConfigError(
+                "run_command spans several lines, so it can only run through the shell".to_string(),
+            ));
+        }
+        Ok(self.run_command.split_whitespace().collect())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Config;
     use std::fs;
     use std::path::Path;
 
@@ -129,24 +1214,35 @@ mod tests {
     fn test_default_config() {
         let config = CliConfig::default();
         assert_eq!(config.run_command, "pnpm dev");
-        assert_eq!(config.error_pattern, "[Error");
+        assert_eq!(config.error_pattern, "[Error".into());
     }
 
     #[test]
     fn test_get_command_parts() {
         let config = CliConfig {
             run_command: "npm run dev".to_string(),
-            error_pattern: "[Error".to_string(),
+            error_pattern: "[Error".into(),
+            ..CliConfig::default()
         };
-        let parts = config.get_command_parts();
+        let parts = config.get_command_parts().unwrap();
         assert_eq!(parts, vec!["npm", "run", "dev"]);
+
+        let multi_line = CliConfig {
+            run_command: "npm install\nnpm run dev\n".to_string(),
+            ..CliConfig::default()
+        };
+        assert!(matches!(
+            multi_line.get_command_parts(),
+            Err(ServerError::ConfigError(message)) if message.contains("several lines")
+        ));
     }
 
     #[test]
     fn test_json_serialization() {
         let config = CliConfig {
             run_command: "yarn dev".to_string(),
-            error_pattern: "ERROR:".to_string(),
+            error_pattern: "ERROR:".into(),
+            ..CliConfig::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -156,6 +1252,26 @@ mod tests {
         assert_eq!(config.error_pattern, deserialized.error_pattern);
     }
 
+    #[test]
+    fn test_error_pattern_list_is_merged_with_the_other_patterns() {
+        let config: CliConfig = serde_json::from_str(
+            r#"{"run_command": "cargo run", "error_pattern": ["[Error", "panic:"],
+                "pattern_rules": [{"pattern": "panic:"}, {"pattern": "FATAL"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.error_patterns().unwrap(),
+            ["[Error", "panic:", "FATAL"]
+        );
+        assert_eq!(config.active_pattern_set(), None);
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            json["error_pattern"],
+            serde_json::json!(["[Error", "panic:"])
+        );
+    }
+
     #[test]
     fn test_save_and_load_config() {
         let test_file = "test-cli-config.json";
@@ -167,7 +1283,8 @@ mod tests {
 
         let original_config = CliConfig {
             run_command: "bun dev".to_string(),
-            error_pattern: "Error:".to_string(),
+            error_pattern: "Error:".into(),
+            ..CliConfig::default()
         };
 
         // Save config
@@ -175,7 +1292,7 @@ mod tests {
         fs::write(test_file, json).unwrap();
 
         // Load config
-        let loaded_config = CliConfig::load_from_file(Path::new(test_file)).unwrap();
+        let loaded_config = CliConfig::load_from_file(Path::new(test_file), None).unwrap();
 
         assert_eq!(original_config.run_command, loaded_config.run_command);
         assert_eq!(original_config.error_pattern, loaded_config.error_pattern);
@@ -183,4 +1300,542 @@ mod tests {
         // Clean up
         fs::remove_file(test_file).unwrap();
     }
+
+    #[test]
+    fn test_diff_identical_configs() {
+        let config = CliConfig::default();
+        assert!(config.diff(&config.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_pattern_change_applies_live() {
+        let old = CliConfig::default();
+        let new = CliConfig {
+            error_pattern: "ERROR:".into(),
+            ready_pattern: Some("Local:".to_string()),
+            ..CliConfig::default()
+        };
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.field_names(), vec!["error_pattern", "ready_pattern"]);
+        assert_eq!(diff.changes[1].old, None);
+        assert_eq!(diff.changes[1].new, Some(serde_json::json!("Local:")));
+        assert!(!diff.requires_restart());
+        assert_eq!(
+            diff.changes[0].to_string(),
+            r#"error_pattern: "[Error" → "ERROR:""#
+        );
+        assert_eq!(
+            diff.changes[1].to_string(),
+            r#"ready_pattern: (unset) → "Local:""#
+        );
+    }
+
+    #[test]
+    fn test_diff_run_command_change_requires_restart() {
+        let old = CliConfig::default();
+        let new = CliConfig {
+            run_command: "npm run dev".to_string(),
+            ..CliConfig::default()
+        };
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.field_names(), vec!["run_command"]);
+        assert!(diff.requires_restart());
+    }
+
+    #[test]
+    fn test_diff_never_shows_env_values() {
+        let old = CliConfig {
+            env: BTreeMap::from([
+                ("DB_PASSWORD".to_string(), "hunter2".to_string()),
+                ("PORT".to_string(), "3000".to_string()),
+                ("TRACE".to_string(), "1".to_string()),
+            ]),
+            ..CliConfig::default()
+        };
+        let new = CliConfig {
+            env: BTreeMap::from([
+                ("DB_PASSWORD".to_string(), "correct-horse".to_string()),
+                ("DEBUG".to_string(), "1".to_string()),
+                ("PORT".to_string(), "3000".to_string()),
+            ]),
+            ..CliConfig::default()
+        };
+
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff.changes[0].to_string(),
+            "env: DB_PASSWORD changed, DEBUG added, TRACE removed"
+        );
+        // Set from nothing
+        assert_eq!(
+            CliConfig::default().diff(&new).changes[0].to_string(),
+            "env: DB_PASSWORD added, DEBUG added, PORT added"
+        );
+    }
+
+    #[test]
+    fn test_diff_spawn_settings_require_restart() {
+        let old = CliConfig::default();
+        let changed = [
+            (
+                "wrapper",
+                CliConfig {
+                    wrapper: Some("time -v".to_string()),
+                    ..CliConfig::default()
+                },
+            ),
+            (
+                "umask",
+                CliConfig {
+                    umask: Some("0077".to_string()),
+                    ..CliConfig::default()
+                },
+            ),
+            (
+                "remote",
+                CliConfig {
+                    remote: Some(RemoteConfig {
+                        host: "devbox".to_string(),
+                        user: None,
+                        key: None,
+                        port: None,
+                    }),
+                    ..CliConfig::default()
+                },
+            ),
+            (
+                "run_as_uid",
+                CliConfig {
+                    run_as_uid: Some(33),
+                    ..CliConfig::default()
+                },
+            ),
+            (
+                "run_as_gid",
+                CliConfig {
+                    run_as_gid: Some(33),
+                    ..CliConfig::default()
+                },
+            ),
+            (
+                "nohup_mode",
+                CliConfig {
+                    nohup_mode: true,
+                    ..CliConfig::default()
+                },
+            ),
+            (
+                "capture_stdout",
+                CliConfig {
+                    capture_stdout: Some(false),
+                    ..CliConfig::default()
+                },
+            ),
+            (
+                "capture_stderr",
+                CliConfig {
+                    capture_stderr: Some(false),
+                    ..CliConfig::default()
+                },
+            ),
+        ];
+        for (field, new) in changed {
+            let diff = old.diff(&new);
+            assert_eq!(diff.field_names(), vec![field]);
+            assert!(diff.requires_restart(), "{} applied live", field);
+        }
+    }
+
+    #[test]
+    fn test_validate_level_patterns() {
+        let mut config = CliConfig {
+            parse_levels: true,
+            level_patterns: Some(HashMap::from([("WARN".to_string(), "yellow".to_string())])),
+            ..CliConfig::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.level_patterns = Some(HashMap::from([("WARN".to_string(), "beige".to_string())]));
+        assert!(matches!(
+            config.validate(),
+            Err(ServerError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_spawn_retry_settings_are_optional() {
+        let config: CliConfig =
+            serde_json::from_str(r#"{"run_command": "npm run dev", "error_pattern": "[Error"}"#)
+                .unwrap();
+        assert_eq!(config.max_spawn_retries, None);
+        assert_eq!(config.spawn_backoff_cap_secs, None);
+
+        let config: CliConfig = serde_json::from_str(
+            r#"{"run_command": "npm run dev", "error_pattern": "[Error", "max_spawn_retries": 3, "spawn_backoff_cap_secs": 20}"#,
+        )
+        .unwrap();
+        assert_eq!(config.max_spawn_retries, Some(3));
+        assert_eq!(config.spawn_backoff_cap_secs, Some(20));
+    }
+
+    #[test]
+    fn test_umask_is_parsed_as_octal() {
+        let mut config = CliConfig {
+            umask: Some("0077".to_string()),
+            ..CliConfig::default()
+        };
+        assert_eq!(config.umask().unwrap(), Some(0o077));
+        assert!(config.validate().is_ok());
+
+        config.umask = Some("0999".to_string());
+        assert!(matches!(
+            config.validate(),
+            Err(ServerError::ConfigError(_))
+        ));
+        config.umask = Some("1777".to_string());
+        assert!(config.umask().is_err());
+    }
+
+    #[test]
+    fn test_ready_warning_comes_before_the_ready_timeout() {
+        let mut config = CliConfig {
+            ready_pattern: Some("listening".to_string()),
+            ready_warn_after_secs: Some(30),
+            ready_timeout_secs: Some(120),
+            ..CliConfig::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.ready_warn_after_secs = Some(120);
+        assert!(config.validate().is_err());
+        config.ready_timeout_secs = None;
+        assert!(config.validate().is_ok());
+        config.ready_pattern = None;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_env_inherit_requires_env_clear() {
+        let mut config = CliConfig {
+            env_inherit: vec!["PATH".to_string()],
+            ..CliConfig::default()
+        };
+        assert!(config.validate().is_err());
+        config.env_clear = true;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_restart_budget_needs_both_settings() {
+        let mut config = CliConfig {
+            restart_burst: Some(3),
+            ..CliConfig::default()
+        };
+        assert!(config.validate().is_err());
+        config.restart_refill = Some(0.0);
+        assert!(config.validate().is_err());
+        config.restart_refill = Some(0.5);
+        assert!(config.validate().is_ok());
+        config.restart_burst = None;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_restart_backoff_settings_need_a_multiplier() {
+        let mut config = CliConfig {
+            max_restart_delay_secs: Some(120),
+            ..CliConfig::default()
+        };
+        assert!(config.validate().is_err());
+        config.backoff_multiplier = Some(0.5);
+        assert!(config.validate().is_err());
+        config.backoff_multiplier = Some(f64::INFINITY);
+        assert!(config.validate().is_err());
+        config.backoff_multiplier = Some(2.0);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_first_output_timeout_must_be_positive() {
+        let mut config = CliConfig {
+            first_output_timeout_secs: Some(0),
+            ..CliConfig::default()
+        };
+        assert!(config.validate().is_err());
+        config.first_output_timeout_secs = Some(1);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_at_least_one_stream_is_captured() {
+        let mut config = CliConfig {
+            capture_stdout: Some(false),
+            capture_stderr: Some(false),
+            ..CliConfig::default()
+        };
+        assert!(config.validate().is_err());
+        config.capture_stderr = None;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_universal_set_is_the_fallback_without_patterns() {
+        let mut config = CliConfig {
+            error_pattern: "".into(),
+            ..CliConfig::default()
+        };
+        assert_eq!(config.active_pattern_set(), Some("universal"));
+        assert!(config.error_patterns().unwrap().is_empty());
+        assert_eq!(
+            config.regex_patterns().unwrap().len(),
+            patterns::UNIVERSAL_PATTERNS.len()
+        );
+
+        config.error_pattern = "[Error".into();
+        assert_eq!(config.active_pattern_set(), None);
+        assert!(config.regex_patterns().unwrap().is_empty());
+
+        config.pattern_set = Some("universal".to_string());
+        assert_eq!(config.active_pattern_set(), Some("universal"));
+        config.pattern_set = Some("rails".to_string());
+        assert!(config.regex_patterns().is_err());
+    }
+
+    #[test]
+    fn test_block_settings_need_block_start() {
+        let mut config = CliConfig {
+            block_start: Some("Error:".to_string()),
+            ..CliConfig::default()
+        };
+        assert_eq!(
+            config.blocks().unwrap(),
+            Some(BlockDelimiters {
+                start: "Error:".to_string(),
+                end: None,
+                max_lines: DEFAULT_MAX_BLOCK_LINES,
+            })
+        );
+
+        config.max_block_lines = Some(0);
+        assert!(config.validate().is_err());
+        config.block_start = None;
+        config.max_block_lines = Some(50);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_read_buffer_size_bounds() {
+        let mut config = CliConfig {
+            read_buffer_size: Some(1024 * 1024),
+            ..CliConfig::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.read_buffer_size = Some(0);
+        assert!(config.validate().is_err());
+        config.read_buffer_size = Some(MAX_READ_BUFFER_SIZE + 1);
+        assert!(config.validate().is_err());
+    }
+
+    fn config_with_profiles() -> CliConfig {
+        serde_json::from_str(
+            r#"{
+                "run_command": "npm run dev",
+                "error_pattern": "[Error",
+                "ready_pattern": "listening",
+                "profiles": {
+                    "ci": {"run_command": "npm run dev -- --ci", "ready_pattern": null, "max_spawn_retries": 2},
+                    "local": {"error_pattern": "FATAL"}
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_profile_overrides_only_its_fields() {
+        let config = config_with_profiles()
+            .with_profile(Some("ci"), Path::new(CONFIG_FILE))
+            .unwrap();
+        assert_eq!(config.run_command, "npm run dev -- --ci");
+        assert_eq!(config.error_pattern, "[Error".into());
+        assert_eq!(config.ready_pattern, None);
+        assert_eq!(config.max_spawn_retries, Some(2));
+
+        let config = config_with_profiles()
+            .with_profile(Some("local"), Path::new(CONFIG_FILE))
+            .unwrap();
+        assert_eq!(config.run_command, "npm run dev");
+        assert_eq!(config.error_pattern, "FATAL".into());
+        assert_eq!(config.ready_pattern.as_deref(), Some("listening"));
+    }
+
+    #[test]
+    fn test_env_overrides_replace_the_file_values() {
+        let vars = |name: &str| match name {
+            RUN_COMMAND_ENV => Some("npm run dev -- --ci".to_string()),
+            ERROR_PATTERN_ENV => Some("FATAL, panic:".to_string()),
+            _ => None,
+        };
+        let mut config = config_with_profiles();
+        assert_eq!(
+            config.apply_env_overrides(vars),
+            [
+                (RUN_COMMAND_ENV, "run_command"),
+                (ERROR_PATTERN_ENV, "error_pattern")
+            ]
+        );
+        assert_eq!(config.run_command, "npm run dev -- --ci");
+        let config = Config::new().with_cli_config(&config).unwrap();
+        assert_eq!(config.error_patterns, ["FATAL", "panic:"]);
+
+        // Unset or blank variables leave the file alone
+        let mut config = config_with_profiles();
+        let blank = |name: &str| (name == ERROR_PATTERN_ENV).then(|| " ".to_string());
+        assert!(config.apply_env_overrides(blank).is_empty());
+        assert_eq!(config.run_command, "npm run dev");
+        assert_eq!(config.error_pattern, "[Error".into());
+    }
+
+    #[test]
+    fn test_working_dir_is_relative_to_the_config_file() {
+        let dir = std::env::temp_dir().join("dev-cli-test-working-dir");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("frontend")).unwrap();
+        let config_path = dir.join("dev-cli.staging.json");
+        let config = |working_dir: &str| CliConfig {
+            working_dir: Some(working_dir.to_string()),
+            ..CliConfig::default()
+        };
+
+        let resolved = config("frontend")
+            .resolve_working_dir(&config_path)
+            .unwrap();
+        assert_eq!(
+            resolved.working_dir.map(PathBuf::from),
+            Some(dir.join("frontend"))
+        );
+        let absolute = dir.join("frontend").to_string_lossy().into_owned();
+        let resolved = config(&absolute)
+            .resolve_working_dir(Path::new("dev-cli.json"))
+            .unwrap();
+        assert_eq!(resolved.working_dir, Some(absolute));
+        assert!(config("backend").resolve_working_dir(&config_path).is_err());
+        assert!(
+            CliConfig::default()
+                .resolve_working_dir(&config_path)
+                .unwrap()
+                .working_dir
+                .is_none()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A fresh directory for config files that extend each other
+    fn extends_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dev-cli-test-extends-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_extends_merges_base_under_local_fields() {
+        let dir = extends_dir("single");
+        fs::write(
+            dir.join("shared/dev-base.json"),
+            r#"{"run_command": "npm run dev", "error_pattern": "[Error", "ready_pattern": "listening"}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join(CONFIG_FILE),
+            r#"{"extends": "shared/dev-base.json", "error_pattern": "FATAL"}"#,
+        )
+        .unwrap();
+
+        let config = CliConfig::read_from_file(&dir.join(CONFIG_FILE)).unwrap();
+        assert_eq!(config.run_command, "npm run dev");
+        assert_eq!(config.error_pattern, "FATAL".into());
+        assert_eq!(config.ready_pattern.as_deref(), Some("listening"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extends_chains_resolve_relative_to_each_file() {
+        let dir = extends_dir("multi");
+        fs::write(
+            dir.join("shared/root.json"),
+            r#"{"run_command": "npm run dev", "error_pattern": "[Error", "shutdown_timeout_secs": 5}"#,
+        )
+        .unwrap();
+        // Relative to shared/, not to the directory dev runs in
+        fs::write(
+            dir.join("shared/team.json"),
+            r#"{"extends": "root.json", "shutdown_timeout_secs": 2, "ready_pattern": "listening"}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join(CONFIG_FILE),
+            r#"{"extends": "./shared/team.json", "ready_pattern": "ready in"}"#,
+        )
+        .unwrap();
+
+        let config = CliConfig::read_from_file(&dir.join(CONFIG_FILE)).unwrap();
+        assert_eq!(config.run_command, "npm run dev");
+        assert_eq!(config.shutdown_timeout_secs, Some(2));
+        assert_eq!(config.ready_pattern.as_deref(), Some("ready in"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extends_cycles_and_missing_bases_are_errors() {
+        let dir = extends_dir("cycle");
+        fs::write(dir.join("shared/a.json"), r#"{"extends": "b.json"}"#).unwrap();
+        fs::write(dir.join("shared/b.json"), r#"{"extends": "a.json"}"#).unwrap();
+        fs::write(dir.join(CONFIG_FILE), r#"{"extends": "shared/a.json"}"#).unwrap();
+        match CliConfig::read_from_file(&dir.join(CONFIG_FILE)) {
+            Err(ServerError::ConfigError(msg)) => {
+                assert!(msg.starts_with("extends cycle:"), "{}", msg);
+                assert!(msg.ends_with("a.json"), "{}", msg);
+            }
+            other => panic!("expected a cycle error, got {:?}", other.map(|_| ())),
+        }
+
+        fs::write(
+            dir.join(CONFIG_FILE),
+            r#"{"extends": "shared/missing.json"}"#,
+        )
+        .unwrap();
+        match CliConfig::read_from_file(&dir.join(CONFIG_FILE)) {
+            Err(ServerError::ConfigError(msg)) => assert!(msg.contains("missing.json"), "{}", msg),
+            other => panic!("expected a config error, got {:?}", other.map(|_| ())),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_profile_uses_base_and_unknown_profile_fails() {
+        let config = config_with_profiles()
+            .with_profile(None, Path::new(CONFIG_FILE))
+            .unwrap();
+        assert_eq!(config.run_command, "npm run dev");
+        assert_eq!(config.ready_pattern.as_deref(), Some("listening"));
+
+        match config_with_profiles().with_profile(Some("staging"), Path::new(CONFIG_FILE)) {
+            Err(ServerError::ConfigError(msg)) => assert!(msg.contains("available: ci, local")),
+            other => panic!("expected a config error, got {:?}", other.map(|_| ())),
+        }
+        let path = Path::new("frontend/dev-cli.json");
+        match CliConfig::default().with_profile(Some("ci"), path) {
+            Err(ServerError::ConfigError(msg)) => {
+                assert_eq!(
+                    msg,
+                    "profile 'ci' not found: frontend/dev-cli.json has no profiles"
+                )
+            }
+            other => panic!("expected a config error, got {:?}", other.map(|_| ())),
+        }
+    }
 }
@@ -0,0 +1,83 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+/// ANSI colors available for decorating output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Gray,
+}
+
+impl Color {
+    /// Parse a color name as written in dev-cli.json
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "red" => Some(Color::Red),
+            "yellow" => Some(Color::Yellow),
+            "green" => Some(Color::Green),
+            "blue" => Some(Color::Blue),
+            "magenta" => Some(Color::Magenta),
+            "cyan" => Some(Color::Cyan),
+            "white" => Some(Color::White),
+            "gray" | "grey" => Some(Color::Gray),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Color::Red => "31",
+            Color::Yellow => "33",
+            Color::Green => "32",
+            Color::Blue => "34",
+            Color::Magenta => "35",
+            Color::Cyan => "36",
+            Color::White => "37",
+            Color::Gray => "90",
+        }
+    }
+}
+
+/// Wrap text in the escape codes for the given color
+pub fn paint(text: &str, color: Color) -> String {
+    format!("\x1b[{}m{}\x1b[0m", color.code(), text)
+}
+
+/// Wrap text in bold red so it stands out within a line
+pub fn highlight(text: &str) -> String {
+    format!("\x1b[1;31m{}\x1b[0m", text)
+}
+
+/// Whether colored output should be written to stdout
+pub fn stdout_enabled() -> bool {
+    env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal()
+}
+
+/// Whether colored output should be written to stderr
+pub fn stderr_enabled() -> bool {
+    env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_from_name() {
+        assert_eq!(Color::from_name("red"), Some(Color::Red));
+        assert_eq!(Color::from_name("Yellow"), Some(Color::Yellow));
+        assert_eq!(Color::from_name("grey"), Some(Color::Gray));
+        assert_eq!(Color::from_name("chartreuse"), None);
+    }
+
+    #[test]
+    fn test_paint() {
+        assert_eq!(paint("boom", Color::Red), "\x1b[31mboom\x1b[0m");
+    }
+}
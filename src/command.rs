@@ -1,12 +1,52 @@
 use std::env;
+use std::path::PathBuf;
 use std::process::Command;
 
 use crate::cli_config::CliConfig;
+use crate::remote::RemoteConfig;
+#[cfg(windows)]
+use crate::theme::{self, Icon};
 
 /// Represents different types of commands that can be executed
 pub enum CommandType {
     Test,
-    Dev(CliConfig),
+    Dev(Box<CliConfig>),
+    /// A one-off shell command such as a lifecycle hook
+    Hook(String),
+}
+
+/// Shell-style rendering of a command as it will be spawned: environment overrides,
+/// then the program and its arguments, quoted where the shell would need it. The values
+/// of the `masked` variables are shown as `***`.
+pub fn describe(command: &Command, masked: &[String]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for (key, value) in command.get_envs() {
+        let key = key.to_string_lossy();
+        match value {
+            Some(_) if masked.iter().any(|name| *name == key) => parts.push(format!("{}=***", key)),
+            Some(value) => parts.push(format!("{}={}", key, quote(&value.to_string_lossy()))),
+            None => parts.push(format!("-u {}", key)),
+        }
+    }
+    if parts.iter().any(|part| part.starts_with("-u ")) {
+        parts.insert(0, "env".to_string());
+    }
+    parts.push(quote(&command.get_program().to_string_lossy()));
+    parts.extend(command.get_args().map(|arg| quote(&arg.to_string_lossy())));
+    parts.join(" ")
+}
+
+/// Single-quote `arg` unless it only has characters the shell leaves alone
+pub fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
 }
 
 /// Command builder for creating process commands
@@ -16,16 +56,28 @@ impl CommandBuilder {
     pub fn build(command_type: CommandType) -> Command {
         match command_type {
             CommandType::Test => Self::create_test_command(),
-            CommandType::Dev(config) => Self::create_dev_command(config),
+            CommandType::Dev(config) => match &config.remote {
+                Some(remote) => Self::create_remote_command(remote, &config.run_command),
+                None => Self::create_dev_command(config),
+            },
+            CommandType::Hook(command) => Self::create_hook_command(&command),
         }
     }
 
+    /// Directory the spawned commands run in, unless the dev command has a `working_dir`
+    fn resolve_working_dir() -> Option<PathBuf> {
+        env::current_dir().ok()
+    }
+
     #[cfg(windows)]
     fn create_test_command() -> Command {
         let mut command = Command::new("cmd");
         command.arg("/C").arg(
             "echo Test server starting... && timeout /t 2 && echo Server ready && timeout /t 3 && echo [Error]: Simulated test error && timeout /t 1 && echo This should not appear"
         );
+        if let Some(dir) = Self::resolve_working_dir() {
+            command.current_dir(dir);
+        }
         command
     }
 
@@ -35,30 +87,385 @@ impl CommandBuilder {
         command.arg("-c").arg(
             "echo 'Test server starting...'; sleep 2; echo 'Server ready'; sleep 3; echo '[Error]: Simulated test error'; sleep 1; echo 'This should not appear'"
         );
+        if let Some(dir) = Self::resolve_working_dir() {
+            command.current_dir(dir);
+        }
         command
     }
 
     #[cfg(windows)]
-    fn create_dev_command(config: CliConfig) -> Command {
+    fn create_dev_command(config: Box<CliConfig>) -> Command {
         let mut command = Command::new("cmd");
         // Use /C to run command and return, but we need to handle process tree killing
-        command.arg("/C").arg(&config.run_command);
-        if let Ok(cd) = env::current_dir() {
-            command.current_dir(cd);
+        match config.wrapper() {
+            Some(wrapper) => command
+                .arg("/C")
+                .arg(format!("{} {}", wrapper, config.run_command)),
+            None => command.arg("/C").arg(&config.run_command),
+        };
+        if let Some(dir) = config
+            .working_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(Self::resolve_working_dir)
+        {
+            command.current_dir(dir);
         }
+        Self::filter_env(&mut command, &config);
         // Set up process group for proper cleanup
         use std::os::windows::process::CommandExt;
         command.creation_flags(0x00000200); // CREATE_NEW_PROCESS_GROUP
+        if config.umask.is_some() {
+            eprintln!(
+                "{} umask is only supported on Unix; ignoring it",
+                theme::icon(Icon::Warning)
+            );
+        }
+        if config.run_as_uid.is_some() || config.run_as_gid.is_some() {
+            eprintln!(
+                "{} run_as_uid and run_as_gid are only supported on Unix; ignoring them",
+                theme::icon(Icon::Warning)
+            );
+        }
         command
     }
 
     #[cfg(not(windows))]
-    fn create_dev_command(config: CliConfig) -> Command {
+    fn create_dev_command(config: Box<CliConfig>) -> Command {
         let mut command = Command::new("sh");
-        command.arg("-c").arg(&config.run_command);
-        if let Ok(cd) = env::current_dir() {
-            command.current_dir(cd);
+        match config.wrapper() {
+            // The wrapper is shell syntax; the dev command reaches it as separate arguments
+            // through "$@", so neither side's quoting leaks into the other
+            Some(wrapper) => command.arg("-c").arg(format!("{} \"$@\"", wrapper)).args([
+                "sh",
+                "sh",
+                "-c",
+                &config.run_command,
+            ]),
+            None => command.arg("-c").arg(&config.run_command),
+        };
+        if let Some(dir) = config
+            .working_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(Self::resolve_working_dir)
+        {
+            command.current_dir(dir);
+        }
+        Self::filter_env(&mut command, &config);
+        // Already validated with the rest of the config
+        if let Ok(Some(mask)) = config.umask() {
+            use std::os::unix::process::CommandExt;
+            // SAFETY: umask is async-signal-safe and touches no memory of the parent
+            unsafe {
+                command.pre_exec(move || {
+                    libc::umask(mask as libc::mode_t);
+                    Ok(())
+                });
+            }
+        }
+        // The group is switched first, while there's still the privilege to do so, and
+        // root's supplementary groups are dropped along with root
+        {
+            use std::os::unix::process::CommandExt;
+            if let Some(gid) = config.run_as_gid {
+                command.gid(gid);
+            }
+            if let Some(uid) = config.run_as_uid {
+                command.uid(uid);
+            }
+        }
+        // The hangup from a closed terminal reaches the server too; an ignored signal stays
+        // ignored across exec, so the server and what it starts survive it
+        if config.nohup_mode {
+            use std::os::unix::process::CommandExt;
+            // SAFETY: signal is async-signal-safe and touches no memory of the parent
+            unsafe {
+                command.pre_exec(|| {
+                    libc::signal(libc::SIGHUP, libc::SIG_IGN);
+                    Ok(())
+                });
+            }
+        }
+        command
+    }
+
+    /// With `env_clear`, start from an empty environment and copy back only the `env_inherit`
+    /// variables dev itself has. The config's `env` is set on top when the command is spawned.
+    fn filter_env(command: &mut Command, config: &CliConfig) {
+        if !config.env_clear {
+            return;
+        }
+        command.env_clear();
+        for name in &config.env_inherit {
+            if let Some(value) = env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+    }
+
+    /// Run the dev command on a remote host; the working directory is the remote login directory
+    fn create_remote_command(remote: &RemoteConfig, run_command: &str) -> Command {
+        let mut command = Command::new("ssh");
+        command.args(remote.ssh_args(run_command));
+        command
+    }
+
+    #[cfg(windows)]
+    fn create_hook_command(hook: &str) -> Command {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(hook);
+        if let Some(dir) = Self::resolve_working_dir() {
+            command.current_dir(dir);
+        }
+        command
+    }
+
+    #[cfg(not(windows))]
+    fn create_hook_command(hook: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(hook);
+        if let Some(dir) = Self::resolve_working_dir() {
+            command.current_dir(dir);
+        }
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_dev_command_uses_ssh() {
+        let config = CliConfig {
+            run_command: "npm run dev".to_string(),
+            remote: Some(RemoteConfig {
+                host: "devbox".to_string(),
+                user: None,
+                key: None,
+                port: None,
+            }),
+            ..CliConfig::default()
+        };
+        let command = CommandBuilder::build(CommandType::Dev(Box::new(config)));
+        assert_eq!(command.get_program(), "ssh");
+        assert_eq!(command.get_args().last().unwrap(), "npm run dev");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_dev_command_runs_under_wrapper() {
+        let config = CliConfig {
+            run_command: "echo \"$GREETING\" 'a  b'; echo done".to_string(),
+            wrapper: Some("env GREETING='hi there'".to_string()),
+            ..CliConfig::default()
+        };
+        let mut command = CommandBuilder::build(CommandType::Dev(Box::new(config.clone())));
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "-c",
+                "env GREETING='hi there' \"$@\"",
+                "sh",
+                "sh",
+                "-c",
+                config.run_command.as_str()
+            ]
+        );
+
+        let output = command.output().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hi there a  b\ndone\n"
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_multi_line_run_command_runs_as_one_script() {
+        let config = CliConfig {
+            run_command: "greeting='hi there'\necho \"$greeting\"\necho done".to_string(),
+            ..CliConfig::default()
+        };
+        let mut command = CommandBuilder::build(CommandType::Dev(Box::new(config.clone())));
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["-c", config.run_command.as_str()]
+        );
+
+        let output = command.output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hi there\ndone\n");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_blank_wrapper_is_ignored() {
+        let config = CliConfig {
+            run_command: "npm run dev".to_string(),
+            wrapper: Some("  ".to_string()),
+            ..CliConfig::default()
+        };
+        let command = CommandBuilder::build(CommandType::Dev(Box::new(config)));
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["-c", "npm run dev"]);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_dev_command_applies_umask() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = env::temp_dir().join("dev-cli-test-umask");
+        let _ = std::fs::remove_file(&path);
+        let config = CliConfig {
+            run_command: format!("touch '{}'", path.display()),
+            umask: Some("0077".to_string()),
+            ..CliConfig::default()
+        };
+        let status = CommandBuilder::build(CommandType::Dev(Box::new(config)))
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_dev_command_runs_as_the_configured_user() {
+        // SAFETY: only reads the test process's own user ID
+        if unsafe { libc::geteuid() } != 0 {
+            eprintln!("skipping: switching users needs root");
+            return;
         }
+        let config = CliConfig {
+            run_command: "id -u; id -g; id -G".to_string(),
+            run_as_uid: Some(65534),
+            run_as_gid: Some(65534),
+            ..CliConfig::default()
+        };
+        let mut command = CommandBuilder::build(CommandType::Dev(Box::new(config)));
+        // The target user may not be allowed into the test's working directory
+        command.current_dir("/");
+        let output = command.output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "65534\n65534\n65534\n"
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_nohup_mode_dev_command_survives_a_hangup() {
+        let output = |nohup_mode| {
+            let config = CliConfig {
+                run_command: "kill -HUP $$; echo survived".to_string(),
+                nohup_mode,
+                ..CliConfig::default()
+            };
+            CommandBuilder::build(CommandType::Dev(Box::new(config)))
+                .output()
+                .unwrap()
+        };
+
+        let output_with_nohup = output(true);
+        assert!(output_with_nohup.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output_with_nohup.stdout),
+            "survived\n"
+        );
+
+        let hung_up = output(false);
+        assert!(!hung_up.status.success());
+        assert!(hung_up.stdout.is_empty());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_env_clear_keeps_only_the_inherited_variables() {
+        let child_env = |env_clear, env_inherit: &[&str]| {
+            let config = CliConfig {
+                run_command: "env".to_string(),
+                env_clear,
+                env_inherit: env_inherit.iter().map(|name| name.to_string()).collect(),
+                ..CliConfig::default()
+            };
+            let mut command = CommandBuilder::build(CommandType::Dev(Box::new(config)));
+            // Set after the build, as the server does with `env`
+            command.env("NODE_ENV", "development");
+            let output = command.output().unwrap();
+            assert!(output.status.success());
+            let mut names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.split_once('=').map(|(name, _)| name.to_string()))
+                // Exported by sh itself
+                .filter(|name| !["PWD", "SHLVL", "_", "OLDPWD"].contains(&name.as_str()))
+                .collect();
+            names.sort();
+            names
+        };
+
+        // By default everything is inherited; PATH is set wherever the tests run
+        let inherited = child_env(false, &[]);
+        assert!(inherited.contains(&"PATH".to_string()));
+        assert!(inherited.contains(&"NODE_ENV".to_string()));
+
+        assert_eq!(child_env(true, &[]), ["NODE_ENV"]);
+        // A listed variable dev doesn't have is simply left out
+        assert_eq!(
+            child_env(true, &["PATH", "DEV_CLI_TEST_UNSET_VAR"]),
+            ["NODE_ENV", "PATH"]
+        );
+    }
+
+    #[test]
+    fn test_describe_quotes_args_and_shows_env() {
+        let mut command = Command::new("sh");
         command
+            .arg("-c")
+            .arg("echo it's $HOME")
+            .env("NODE_ENV", "development");
+        assert_eq!(
+            describe(&command, &[]),
+            r#"NODE_ENV=development sh -c 'echo it'\''s $HOME'"#
+        );
+
+        command.env("DB_PASSWORD", "hunter2");
+        assert_eq!(
+            describe(&command, &["DB_PASSWORD".to_string()]),
+            r#"DB_PASSWORD=*** NODE_ENV=development sh -c 'echo it'\''s $HOME'"#
+        );
+
+        let mut plain = Command::new("npm");
+        plain.args(["run", "dev", ""]).env_remove("CI");
+        assert_eq!(describe(&plain, &[]), "env -u CI npm run dev ''");
+    }
+
+    #[test]
+    fn test_test_command_runs_in_working_dir() {
+        let command = CommandBuilder::build(CommandType::Test);
+        assert_eq!(
+            command.get_current_dir(),
+            env::current_dir().ok().as_deref()
+        );
+    }
+
+    #[test]
+    fn test_dev_command_runs_in_its_working_dir() {
+        let config = CliConfig {
+            run_command: "npm run dev".to_string(),
+            working_dir: Some("frontend".to_string()),
+            ..CliConfig::default()
+        };
+        let command = CommandBuilder::build(CommandType::Dev(Box::new(config)));
+        assert_eq!(
+            command.get_current_dir(),
+            Some(std::path::Path::new("frontend"))
+        );
     }
 }
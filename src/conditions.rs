@@ -0,0 +1,345 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, ServerError};
+
+/// One entry of `restart_when`: the server restarts as soon as its condition is met
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestartRule {
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// For a `sequence`: the seconds its last step may come after its first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub within_secs: Option<u64>,
+}
+
+/// A condition in `restart_when`, written as a single-key object such as `{"pattern": "[Error"}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// An output line contains this text
+    Pattern(String),
+    /// The server has been running for at least this many seconds
+    UptimeAtLeastSecs(u64),
+    /// The server exited with this code
+    ExitCode(i32),
+    /// Every condition holds for the same line or exit
+    All(Vec<Condition>),
+    /// Each step holds for a later line (or the exit) than the one before; only allowed
+    /// as a whole rule, not inside another condition
+    Sequence(Vec<Condition>),
+}
+
+/// What the monitor just saw from the server
+#[derive(Debug, Clone, Copy)]
+pub enum Observation<'a> {
+    Line(&'a str),
+    Exit(Option<i32>),
+}
+
+impl Condition {
+    fn holds(&self, seen: Observation, uptime: Duration) -> bool {
+        match self {
+            Condition::Pattern(text) => {
+                matches!(seen, Observation::Line(line) if line.contains(text.as_str()))
+            }
+            Condition::UptimeAtLeastSecs(secs) => uptime >= Duration::from_secs(*secs),
+            Condition::ExitCode(code) => {
+                matches!(seen, Observation::Exit(Some(exit)) if exit == *code)
+            }
+            Condition::All(conditions) => conditions.iter().all(|c| c.holds(seen, uptime)),
+            // Rejected by `validate` below the top level
+            Condition::Sequence(_) => false,
+        }
+    }
+
+    /// Whether the condition waits for something the server does. Uptime alone would be
+    /// met by whatever line happens to come next.
+    fn observes(&self) -> bool {
+        match self {
+            Condition::Pattern(_) | Condition::ExitCode(_) => true,
+            Condition::UptimeAtLeastSecs(_) => false,
+            Condition::All(conditions) => conditions.iter().any(Condition::observes),
+            Condition::Sequence(steps) => steps.iter().all(Condition::observes),
+        }
+    }
+
+    fn collect_patterns<'a>(&'a self, patterns: &mut Vec<&'a str>) {
+        match self {
+            Condition::Pattern(text) => patterns.push(text),
+            Condition::All(conditions) | Condition::Sequence(conditions) => {
+                for condition in conditions {
+                    condition.collect_patterns(patterns);
+                }
+            }
+            Condition::UptimeAtLeastSecs(_) | Condition::ExitCode(_) => {}
+        }
+    }
+
+    fn check_nesting(&self) -> std::result::Result<(), String> {
+        match self {
+            Condition::Pattern(text) if text.is_empty() => Err("pattern can't be empty".into()),
+            Condition::All(conditions) if conditions.is_empty() => {
+                Err("all needs at least one condition".into())
+            }
+            Condition::All(conditions) | Condition::Sequence(conditions) => {
+                conditions.iter().try_for_each(|condition| match condition {
+                    Condition::Sequence(_) => {
+                        Err("sequence is only allowed as a whole rule".into())
+                    }
+                    condition => condition.check_nesting(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Pattern(text) => write!(f, "'{}'", text),
+            Condition::UptimeAtLeastSecs(secs) => write!(f, "uptime ≥ {}s", secs),
+            Condition::ExitCode(code) => write!(f, "exit code {}", code),
+            Condition::All(conditions) => write!(f, "({})", join(conditions, " and ")),
+            Condition::Sequence(steps) => write!(f, "{}", join(steps, " then ")),
+        }
+    }
+}
+
+impl fmt::Display for RestartRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.condition {
+            // Top-level parentheses add nothing
+            Condition::All(conditions) => write!(f, "{}", join(conditions, " and "))?,
+            condition => write!(f, "{}", condition)?,
+        }
+        if let Some(secs) = self.within_secs {
+            write!(f, " within {}s", secs)?;
+        }
+        Ok(())
+    }
+}
+
+fn join(conditions: &[Condition], separator: &str) -> String {
+    conditions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Check `restart_when` when the config is loaded
+pub fn validate(rules: &[RestartRule]) -> Result<()> {
+    for (index, rule) in rules.iter().enumerate() {
+        let invalid = |reason: String| {
+            ServerError::ConfigError(format!("restart_when[{}]: {}", index, reason))
+        };
+        rule.condition.check_nesting().map_err(invalid)?;
+        if let Condition::Sequence(steps) = &rule.condition
+            && steps.len() < 2
+        {
+            return Err(invalid("sequence needs at least two steps".into()));
+        }
+        if rule.within_secs.is_some() && !matches!(rule.condition, Condition::Sequence(_)) {
+            return Err(invalid("within_secs only applies to a sequence".into()));
+        }
+        if !rule.condition.observes() {
+            return Err(invalid(
+                "every rule and sequence step needs a pattern or exit_code".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Every `pattern` text in the rules, so the readers know which lines the monitor needs
+pub fn rule_patterns(rules: &[RestartRule]) -> Vec<String> {
+    let mut patterns = Vec::new();
+    for rule in rules {
+        rule.condition.collect_patterns(&mut patterns);
+    }
+    patterns.sort_unstable();
+    patterns.dedup();
+    patterns.into_iter().map(str::to_string).collect()
+}
+
+/// Follows the `restart_when` rules through one run of the server
+pub struct RuleMatcher {
+    rules: Vec<RestartRule>,
+    started: Instant,
+    /// Per rule: the next sequence step and when the first one was seen
+    progress: Vec<(usize, Option<Instant>)>,
+}
+
+impl RuleMatcher {
+    pub fn new(rules: &[RestartRule], started: Instant) -> Self {
+        Self {
+            rules: rules.to_vec(),
+            started,
+            progress: vec![(0, None); rules.len()],
+        }
+    }
+
+    /// The first rule this line or exit completes, if any
+    pub fn observe(&mut self, seen: Observation, now: Instant) -> Option<&RestartRule> {
+        let uptime = now.saturating_duration_since(self.started);
+        let index = (0..self.rules.len()).find(|&index| self.advance(index, seen, now, uptime))?;
+        Some(&self.rules[index])
+    }
+
+    fn advance(&mut self, index: usize, seen: Observation, now: Instant, uptime: Duration) -> bool {
+        let rule = &self.rules[index];
+        let Condition::Sequence(steps) = &rule.condition else {
+            return rule.condition.holds(seen, uptime);
+        };
+        let (next, first_at) = &mut self.progress[index];
+        // A sequence that took too long starts over, possibly with this very line
+        if let (Some(within), Some(at)) = (rule.within_secs, *first_at)
+            && now.saturating_duration_since(at) > Duration::from_secs(within)
+        {
+            (*next, *first_at) = (0, None);
+        }
+        if !steps[*next].holds(seen, uptime) {
+            return false;
+        }
+        first_at.get_or_insert(now);
+        *next += 1;
+        if *next < steps.len() {
+            return false;
+        }
+        (*next, *first_at) = (0, None);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(json: &str) -> Vec<RestartRule> {
+        let rules: Vec<RestartRule> = serde_json::from_str(json).unwrap();
+        validate(&rules).unwrap();
+        rules
+    }
+
+    #[test]
+    fn test_all_combines_a_pattern_with_uptime() {
+        let start = Instant::now();
+        let rules = rules(r#"[{"all": [{"pattern": "[Error"}, {"uptime_at_least_secs": 10}]}]"#);
+        let mut matcher = RuleMatcher::new(&rules, start);
+
+        // A boot hiccup
+        let early = start + Duration::from_secs(3);
+        assert!(
+            matcher
+                .observe(Observation::Line("[Error] boot"), early)
+                .is_none()
+        );
+
+        let late = start + Duration::from_secs(12);
+        assert!(
+            matcher
+                .observe(Observation::Line("listening"), late)
+                .is_none()
+        );
+        let rule = matcher
+            .observe(Observation::Line("[Error] crash"), late)
+            .unwrap();
+        assert_eq!(rule.to_string(), "'[Error' and uptime ≥ 10s");
+    }
+
+    #[test]
+    fn test_sequence_needs_its_steps_in_order_and_in_time() {
+        let start = Instant::now();
+        let rules = rules(
+            r#"[{"sequence": [{"pattern": "pool exhausted"}, {"pattern": "timeout"}], "within_secs": 30}]"#,
+        );
+        let mut matcher = RuleMatcher::new(&rules, start);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // Wrong order
+        assert!(
+            matcher
+                .observe(Observation::Line("timeout"), at(1))
+                .is_none()
+        );
+        assert!(
+            matcher
+                .observe(Observation::Line("pool exhausted"), at(2))
+                .is_none()
+        );
+        // Too late: the window starts over at the next first step
+        assert!(
+            matcher
+                .observe(Observation::Line("timeout"), at(40))
+                .is_none()
+        );
+        assert!(
+            matcher
+                .observe(Observation::Line("pool exhausted"), at(41))
+                .is_none()
+        );
+        let rule = matcher
+            .observe(Observation::Line("timeout"), at(50))
+            .unwrap();
+        assert_eq!(
+            rule.to_string(),
+            "'pool exhausted' then 'timeout' within 30s"
+        );
+
+        // Completing a sequence resets it
+        assert!(
+            matcher
+                .observe(Observation::Line("timeout"), at(51))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_exit_code_conditions_only_match_exits() {
+        let start = Instant::now();
+        let rules = rules(
+            r#"[{"pattern": "OOM"}, {"all": [{"exit_code": 0}, {"uptime_at_least_secs": 60}]}]"#,
+        );
+        let mut matcher = RuleMatcher::new(&rules, start);
+
+        assert!(matcher.observe(Observation::Exit(Some(0)), start).is_none());
+        let later = start + Duration::from_secs(90);
+        assert!(
+            matcher
+                .observe(Observation::Line("exit code 0"), later)
+                .is_none()
+        );
+        assert!(matcher.observe(Observation::Exit(None), later).is_none());
+        assert_eq!(
+            matcher.observe(Observation::Exit(Some(0)), later),
+            Some(&rules[1])
+        );
+        assert_eq!(
+            matcher.observe(Observation::Line("OOM killer"), start),
+            Some(&rules[0])
+        );
+        assert_eq!(rule_patterns(&rules), vec!["OOM"]);
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        let error = |json: &str| {
+            let rules: Vec<RestartRule> = serde_json::from_str(json).unwrap();
+            match validate(&rules) {
+                Err(ServerError::ConfigError(msg)) => msg,
+                other => panic!("expected a config error for {}, got {:?}", json, other),
+            }
+        };
+        assert!(error(r#"[{"uptime_at_least_secs": 10}]"#).contains("pattern or exit_code"));
+        assert!(error(r#"[{"pattern": "a", "within_secs": 5}]"#).contains("within_secs"));
+        assert!(error(r#"[{"sequence": [{"pattern": "a"}]}]"#).contains("two steps"));
+        assert!(
+            error(r#"[{"pattern": "a"}, {"all": [{"sequence": [{"pattern": "a"}, {"pattern": "b"}]}]}]"#)
+                .starts_with("restart_when[1]: sequence")
+        );
+        assert!(serde_json::from_str::<Vec<RestartRule>>(r#"[{"uptime": 3}]"#).is_err());
+    }
+}
@@ -1,13 +1,133 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::blocks::BlockDelimiters;
+use crate::budget::RestartBudget;
+use crate::cli_config::CliConfig;
+use crate::conditions::RestartRule;
+use crate::error::Result;
+use crate::exit_rules::{self, ExitAction, ExitCodeRule};
+use crate::hooks::HookGroup;
+use crate::levels::{self, LevelColorizer};
+use crate::monitor::{CountPerLine, DEFAULT_READ_BUFFER_SIZE};
+use crate::patterns::PatternRule;
+use crate::process::{Capture, ShutdownWait};
+use crate::ready::{DEFAULT_READY_COMMAND_INTERVAL, DEFAULT_READY_COMMAND_TIMEOUT, ReadyCommand};
+use crate::remote::RemoteConfig;
+use crate::rerun::RunMode;
+use crate::resources::ResourceLimits;
+#[cfg(unix)]
+use crate::signals;
+use crate::theme::Theme;
+
+/// Printed when `max_restarts` is reached and `max_restarts_message` isn't set
+pub const DEFAULT_MAX_RESTARTS_MESSAGE: &str =
+    "Giving up after {count} crash restart(s). Last error: {last_error}";
+
+/// How long `confirm_restart` waits for an answer before restarting
+pub const DEFAULT_CONFIRM_RESTART_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Configuration constants for the dev server monitor
 #[derive(Debug, Clone)]
 pub struct Config {
     pub restart_delay: Duration,
     pub error_delay: Duration,
     pub process_check_interval: Duration,
-    pub shutdown_timeout: Duration,
-    pub error_pattern: String,
+    /// Timeout, poll interval and progress threshold for waiting on a killed server
+    pub shutdown: ShutdownWait,
+    pub error_patterns: Vec<String>,
+    /// Error patterns matched as regular expressions (validated when the config is loaded)
+    pub regex_patterns: Vec<String>,
+    pub pattern_rules: Vec<PatternRule>,
+    pub restart_when: Vec<RestartRule>,
+    /// Matches needed before restarting (1 = restart on the first match)
+    pub error_threshold: u32,
+    /// Only matches within this window count toward `error_threshold`
+    pub error_window: Option<Duration>,
+    pub count_per_line: CountPerLine,
+    pub report_matches: bool,
+    pub dedupe_errors: bool,
+    pub stall_patterns: Vec<String>,
+    pub mute_patterns: Vec<String>,
+    /// Multi-line blocks matched as one error
+    pub blocks: Option<BlockDelimiters>,
+    pub spawn_backoff_cap: Duration,
+    pub max_spawn_retries: Option<u32>,
+    /// Crash restarts allowed before the session ends as a failure
+    pub max_restarts: Option<u32>,
+    pub max_restarts_message: String,
+    pub restart_budget: Option<RestartBudget>,
+    /// Growth of the restart delay per crash in a row; `None` keeps it fixed
+    pub backoff_multiplier: Option<f64>,
+    pub max_restart_delay: Duration,
+    /// Uptime after which the next crash starts the backoff over
+    pub stability_window: Duration,
+    pub ready_pattern: Option<String>,
+    pub ready_command: Option<ReadyCommand>,
+    pub ready_marker: String,
+    pub ready_file: Option<PathBuf>,
+    pub on_ready: Option<String>,
+    pub on_restart: Option<String>,
+    pub on_clean_exit: Option<String>,
+    pub level_colorizer: Option<LevelColorizer>,
+    pub highlight_match: bool,
+    pub attempt_prefix: bool,
+    pub stderr_is_error: bool,
+    pub no_force_kill: bool,
+    pub nohup_mode: bool,
+    pub graceful_shutdown_warn_interval: Duration,
+    pub state_file: Option<PathBuf>,
+    pub fallback_after_failures: Option<u32>,
+    pub event_command: Option<String>,
+    /// Lifecycle events appended as JSON lines
+    pub event_log: Option<String>,
+    pub on_tick: Option<String>,
+    pub on_tick_interval: Duration,
+    pub pre_commands: Option<HookGroup>,
+    pub warmup: Option<String>,
+    pub resource_limits: ResourceLimits,
+    pub remote: Option<RemoteConfig>,
+    pub theme: Theme,
+    pub mode: RunMode,
+    /// Watched in "command" mode; the working directory when empty
+    pub watch_paths: Vec<PathBuf>,
+    /// Print each spawned command line (`$ sh -c '...'`) before starting it
+    pub echo_command: bool,
+    /// Capacity of each output reader's buffer in bytes
+    pub read_buffer_size: usize,
+    /// Byte that separates output records (default: newline)
+    pub delimiter: u8,
+    /// Treat the attempt as a failed start when no output arrives within this time
+    pub first_output_timeout: Option<Duration>,
+    pub ready_timeout: Option<Duration>,
+    /// Warn, but keep waiting, if the server isn't ready this long after the first start
+    pub ready_warn_after: Option<Duration>,
+    /// Wait before the first spawn of the session
+    pub initial_delay: Option<Duration>,
+    /// Directory copied afresh for every start of the server
+    pub fresh_workdir: Option<PathBuf>,
+    /// Plain copy of the server's output
+    pub log_file: Option<String>,
+    /// Destination of dev's own status lines (file path or `stderr`); stdout when unset
+    pub monitor_log: Option<String>,
+    /// Quiet period before a burst of config file writes is reloaded
+    pub watch_debounce: Duration,
+    /// After a restart for a config change, error patterns are ignored for this long
+    pub rebuild_grace: Duration,
+    /// Restart when the server closes its output while the process stays alive
+    pub restart_on_eof: bool,
+    pub restart_on_nonzero_exit: bool,
+    pub exit_code_rules: Vec<ExitCodeRule>,
+    pub restart_on_error: bool,
+    /// The server's output streams dev reads and matches; the others go straight to the terminal
+    pub capture: Capture,
+    pub confirm_restart: bool,
+    /// How long the restart prompt waits before restarting; `None` waits forever
+    pub confirm_restart_timeout: Option<Duration>,
+    /// Parsed `crash_signals` (Unix only)
+    pub crash_signals: Vec<i32>,
+    /// Parsed `stop_signals` (Unix only)
+    pub stop_signals: Vec<i32>,
 }
 
 impl Default for Config {
@@ -16,8 +136,75 @@ impl Default for Config {
             restart_delay: Duration::from_secs(2),
             error_delay: Duration::from_secs(5),
             process_check_interval: Duration::from_millis(100),
-            shutdown_timeout: Duration::from_secs(5), // Increased from 2 to 5 seconds
-            error_pattern: "[Error".to_string(),
+            shutdown: ShutdownWait::default(),
+            error_patterns: vec!["[Error".to_string()],
+            regex_patterns: Vec::new(),
+            pattern_rules: Vec::new(),
+            restart_when: Vec::new(),
+            error_threshold: 1,
+            error_window: None,
+            count_per_line: CountPerLine::Once,
+            report_matches: false,
+            dedupe_errors: false,
+            stall_patterns: Vec::new(),
+            mute_patterns: Vec::new(),
+            blocks: None,
+            spawn_backoff_cap: Duration::from_secs(60),
+            max_spawn_retries: None,
+            max_restarts: None,
+            max_restarts_message: DEFAULT_MAX_RESTARTS_MESSAGE.to_string(),
+            restart_budget: None,
+            backoff_multiplier: None,
+            max_restart_delay: Duration::from_secs(60),
+            stability_window: Duration::from_secs(30),
+            ready_pattern: None,
+            ready_command: None,
+            ready_marker: "DEV_READY".to_string(),
+            ready_file: None,
+            on_ready: None,
+            on_restart: None,
+            on_clean_exit: None,
+            level_colorizer: None,
+            highlight_match: false,
+            attempt_prefix: false,
+            stderr_is_error: false,
+            no_force_kill: false,
+            nohup_mode: false,
+            graceful_shutdown_warn_interval: Duration::from_secs(30),
+            state_file: None,
+            fallback_after_failures: None,
+            event_command: None,
+            event_log: None,
+            on_tick: None,
+            on_tick_interval: Duration::from_secs(5),
+            pre_commands: None,
+            warmup: None,
+            resource_limits: ResourceLimits::default(),
+            remote: None,
+            theme: Theme::default(),
+            mode: RunMode::Server,
+            watch_paths: Vec::new(),
+            echo_command: false,
+            read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+            delimiter: b'\n',
+            first_output_timeout: None,
+            ready_timeout: None,
+            ready_warn_after: None,
+            initial_delay: None,
+            fresh_workdir: None,
+            restart_on_eof: false,
+            restart_on_nonzero_exit: true,
+            exit_code_rules: Vec::new(),
+            restart_on_error: true,
+            capture: Capture::default(),
+            confirm_restart: false,
+            confirm_restart_timeout: Some(DEFAULT_CONFIRM_RESTART_TIMEOUT),
+            watch_debounce: Duration::from_millis(300),
+            rebuild_grace: Duration::ZERO,
+            log_file: None,
+            monitor_log: None,
+            crash_signals: Vec::new(),
+            stop_signals: Vec::new(),
         }
     }
 }
@@ -27,20 +214,271 @@ impl Config {
         Self::default()
     }
 
+    /// Delay before restarting after `pattern` matched: the pattern's own delay, else `restart_delay`
+    pub fn restart_delay_for(&self, pattern: &str) -> Duration {
+        self.pattern_rules
+            .iter()
+            .find(|rule| rule.pattern == pattern)
+            .and_then(|rule| rule.restart_delay_secs)
+            .map(Duration::from_secs)
+            .unwrap_or(self.restart_delay)
+    }
+
+    /// What `restart_on_error` and `restart_on_nonzero_exit` leave of the restarts, for the
+    /// startup banner; `None` when both are on (the usual case)
+    pub fn restart_policy(&self) -> Option<&'static str> {
+        match (self.restart_on_error, self.restart_on_nonzero_exit) {
+            (true, true) => None,
+            (true, false) => {
+                Some("Restarting on error patterns only; a failed exit ends the session")
+            }
+            (false, true) => Some(
+                "Error patterns are only reported; restarting only when the server exits with an error",
+            ),
+            (false, false) => Some(
+                "Restarts are off: the command runs once, errors are reported, and dev stops when it exits",
+            ),
+        }
+    }
+
+    /// Command to run before restarting after `pattern` matched, if its rule has one
+    pub fn recovery_command_for(&self, pattern: &str) -> Option<&str> {
+        self.pattern_rules
+            .iter()
+            .find(|rule| rule.pattern == pattern)
+            .and_then(|rule| rule.recovery_command.as_deref())
+            .filter(|command| !command.trim().is_empty())
+    }
+
+    /// The `exit_code_rules` entry for an exit with `code`, if any
+    pub fn exit_code_rule(&self, code: Option<i32>) -> Option<&ExitCodeRule> {
+        exit_rules::find(&self.exit_code_rules, code)
+    }
+
+    /// Whether an exit with `code` restarts the server, after `exit_code_rules` and the defaults
+    pub fn exit_action(&self, code: Option<i32>) -> ExitAction {
+        exit_rules::action(&self.exit_code_rules, code, self.restart_on_nonzero_exit)
+    }
+
+    /// Delay before restarting after an exit with `code`; the rule's `restart_delay_secs`
+    /// overrides `restart_delay`
+    pub fn exit_restart_delay(&self, code: Option<i32>) -> Duration {
+        self.exit_code_rule(code)
+            .and_then(|rule| rule.restart_delay_secs)
+            .map(Duration::from_secs)
+            .unwrap_or(self.restart_delay)
+    }
+
+    /// How long `pattern` is ignored after it triggered a restart, if it has a cooldown
+    pub fn cooldown_for(&self, pattern: &str) -> Option<Duration> {
+        self.pattern_rules
+            .iter()
+            .find(|rule| rule.pattern == pattern)
+            .and_then(|rule| rule.cooldown_secs)
+            .map(Duration::from_secs)
+    }
+
+    #[allow(dead_code)]
     pub fn with_error_pattern(mut self, pattern: impl Into<String>) -> Self {
-        self.error_pattern = pattern.into();
+        self.error_patterns = vec![pattern.into()];
         self
     }
 
+    pub fn with_error_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.error_patterns = patterns;
+        self
+    }
+
+    #[allow(dead_code)]
     pub fn with_restart_delay(mut self, delay: Duration) -> Self {
         self.restart_delay = delay;
         self
     }
 
+    #[allow(dead_code)]
     pub fn with_error_delay(mut self, delay: Duration) -> Self {
         self.error_delay = delay;
         self
     }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = Some(delay);
+        self
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_warmup(mut self, command: impl Into<String>) -> Self {
+        self.warmup = Some(command.into());
+        self
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_on_clean_exit(mut self, command: impl Into<String>) -> Self {
+        self.on_clean_exit = Some(command.into());
+        self
+    }
+
+    pub fn with_spawn_backoff_cap(mut self, cap: Duration) -> Self {
+        self.spawn_backoff_cap = cap;
+        self
+    }
+
+    pub fn with_max_spawn_retries(mut self, max: Option<u32>) -> Self {
+        self.max_spawn_retries = max;
+        self
+    }
+
+    /// Apply the project settings from dev-cli.json on top of the defaults
+    pub fn with_cli_config(mut self, cli_config: &CliConfig) -> Result<Self> {
+        self = self
+            .with_error_patterns(cli_config.error_patterns()?)
+            .with_max_spawn_retries(cli_config.max_spawn_retries);
+        self.regex_patterns = cli_config.regex_patterns()?;
+        self.max_restarts = cli_config.max_restarts;
+        if let Some(message) = &cli_config.max_restarts_message {
+            self.max_restarts_message = message.clone();
+        }
+        self.restart_budget = cli_config.restart_burst.zip(cli_config.restart_refill).map(
+            |(burst, refill_per_minute)| RestartBudget {
+                burst,
+                refill_per_minute,
+                strict: cli_config.restart_budget_strict,
+            },
+        );
+        self.backoff_multiplier = cli_config.backoff_multiplier;
+        if let Some(secs) = cli_config.max_restart_delay_secs {
+            self.max_restart_delay = Duration::from_secs(secs);
+        }
+        if let Some(secs) = cli_config.stability_window_secs {
+            self.stability_window = Duration::from_secs(secs);
+        }
+        if let Some(secs) = cli_config.spawn_backoff_cap_secs {
+            self = self.with_spawn_backoff_cap(Duration::from_secs(secs));
+        }
+        self.ready_pattern = cli_config
+            .ready_pattern
+            .clone()
+            .filter(|pattern| !pattern.is_empty());
+        self.ready_command = cli_config
+            .ready_command
+            .clone()
+            .map(|command| ReadyCommand {
+                command,
+                interval: cli_config
+                    .ready_command_interval_ms
+                    .map_or(DEFAULT_READY_COMMAND_INTERVAL, Duration::from_millis),
+                timeout: cli_config
+                    .ready_command_timeout_secs
+                    .map_or(DEFAULT_READY_COMMAND_TIMEOUT, Duration::from_secs),
+            });
+        if let Some(marker) = &cli_config.ready_marker {
+            self.ready_marker = marker.clone();
+        }
+        self.ready_file = cli_config.ready_file.as_ref().map(PathBuf::from);
+        self.on_ready = cli_config.on_ready.clone();
+        self.on_restart = cli_config.on_restart.clone();
+        self.on_clean_exit = cli_config.on_clean_exit.clone();
+        if cli_config.parse_levels {
+            let patterns = cli_config
+                .level_patterns
+                .clone()
+                .unwrap_or_else(levels::default_level_patterns);
+            self.level_colorizer = LevelColorizer::from_patterns(&patterns).ok();
+        }
+        self.highlight_match = cli_config.highlight_match;
+        self.attempt_prefix = cli_config.attempt_prefix;
+        self.stderr_is_error = cli_config.stderr_is_error;
+        self.no_force_kill = cli_config.no_force_kill;
+        self.nohup_mode = cli_config.nohup_mode;
+        if let Some(secs) = cli_config.shutdown_timeout_secs {
+            self.shutdown.timeout = Duration::from_secs(secs);
+        }
+        if let Some(ms) = cli_config.shutdown_poll_interval_ms {
+            self.shutdown.poll_interval = Duration::from_millis(ms);
+        }
+        if let Some(secs) = cli_config.shutdown_warn_after_secs {
+            self.shutdown.warn_after = Duration::from_secs(secs);
+        }
+        if let Some(secs) = cli_config.graceful_shutdown_warn_secs {
+            self.graceful_shutdown_warn_interval = Duration::from_secs(secs);
+        }
+        self.state_file = cli_config.state_file.as_ref().map(PathBuf::from);
+        self.fallback_after_failures = cli_config.fallback_after_failures;
+        self.event_command = cli_config.event_command.clone();
+        self.event_log = cli_config.event_log.clone();
+        self.on_tick = cli_config.on_tick.clone();
+        if let Some(secs) = cli_config.on_tick_interval_secs {
+            self.on_tick_interval = Duration::from_secs(secs);
+        }
+        self.pre_commands = cli_config.pre_commands.clone();
+        self.warmup = cli_config.warmup.clone();
+        self.pattern_rules = cli_config.pattern_rules.clone();
+        self.restart_when = cli_config.restart_when.clone();
+        self.remote = cli_config.remote.clone();
+        self.echo_command = cli_config.echo_command;
+        self.theme = cli_config.theme()?;
+        self.mode = cli_config.mode;
+        self.watch_paths = cli_config.watch_paths.iter().map(PathBuf::from).collect();
+        self.first_output_timeout = cli_config
+            .first_output_timeout_secs
+            .map(Duration::from_secs);
+        self.ready_timeout = cli_config.ready_timeout_secs.map(Duration::from_secs);
+        self.ready_warn_after = cli_config.ready_warn_after_secs.map(Duration::from_secs);
+        self.initial_delay = cli_config.initial_delay_secs.map(Duration::from_secs);
+        self.fresh_workdir = cli_config.fresh_workdir.as_ref().map(PathBuf::from);
+        self.restart_on_eof = cli_config.restart_on_eof;
+        self.restart_on_nonzero_exit = cli_config.restart_on_nonzero_exit.unwrap_or(true);
+        self.exit_code_rules = cli_config.exit_code_rules.clone();
+        self.restart_on_error = cli_config.restart_on_error.unwrap_or(true);
+        self.capture = Capture {
+            stdout: cli_config.capture_stdout.unwrap_or(true),
+            stderr: cli_config.capture_stderr.unwrap_or(true),
+        };
+        self.confirm_restart = cli_config.confirm_restart;
+        self.confirm_restart_timeout = match cli_config.confirm_restart_timeout_secs {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => Some(DEFAULT_CONFIRM_RESTART_TIMEOUT),
+        };
+        self.log_file = cli_config.log_file.clone();
+        self.monitor_log = cli_config.monitor_log.clone();
+        if let Some(ms) = cli_config.watch_debounce_ms {
+            self.watch_debounce = Duration::from_millis(ms);
+        }
+        self.rebuild_grace = Duration::from_millis(cli_config.rebuild_grace_ms.unwrap_or(0));
+        if let Some(size) = cli_config.read_buffer_size {
+            self.read_buffer_size = size;
+        }
+        if let Some(delimiter) = cli_config.delimiter {
+            self.delimiter = delimiter;
+        }
+        if let Some(threshold) = cli_config.error_threshold {
+            self.error_threshold = threshold;
+        }
+        self.error_window = cli_config.error_window_secs.map(Duration::from_secs);
+        self.count_per_line = cli_config.count_per_line;
+        self.report_matches = cli_config.report_matches;
+        self.dedupe_errors = cli_config.dedupe_errors;
+        self.stall_patterns = cli_config.stall_patterns.clone();
+        self.mute_patterns = cli_config.mute_patterns.clone();
+        self.blocks = cli_config.blocks()?;
+        self.resource_limits.max_memory_mb = cli_config.max_memory_mb;
+        self.resource_limits.max_cpu_percent = cli_config.max_cpu_percent;
+        if let Some(secs) = cli_config.resource_sample_interval_secs {
+            self.resource_limits.sample_interval = Duration::from_secs(secs);
+        }
+        if let Some(secs) = cli_config.resource_limit_window_secs {
+            self.resource_limits.window = Duration::from_secs(secs);
+        }
+        #[cfg(unix)]
+        {
+            self.crash_signals =
+                signals::parse_signals("crash_signals", &cli_config.crash_signals)?;
+            self.stop_signals = signals::parse_signals("stop_signals", &cli_config.stop_signals)?;
+        }
+        Ok(self)
+    }
 }
 
 #[cfg(test)]
@@ -51,7 +489,7 @@ mod tests {
     #[test]
     fn test_config_default() {
         let config = Config::default();
-        assert_eq!(config.error_pattern, "[Error");
+        assert_eq!(config.error_patterns, vec!["[Error"]);
         assert_eq!(config.restart_delay, Duration::from_secs(2));
         assert_eq!(config.error_delay, Duration::from_secs(5));
     }
@@ -63,7 +501,7 @@ mod tests {
             .with_restart_delay(Duration::from_secs(1))
             .with_error_delay(Duration::from_secs(3));
 
-        assert_eq!(config.error_pattern, "ERROR");
+        assert_eq!(config.error_patterns, vec!["ERROR"]);
         assert_eq!(config.restart_delay, Duration::from_secs(1));
         assert_eq!(config.error_delay, Duration::from_secs(3));
     }
@@ -73,7 +511,128 @@ mod tests {
         let config = Config::new();
         // Should be equivalent to default
         let default_config = Config::default();
-        assert_eq!(config.error_pattern, default_config.error_pattern);
+        assert_eq!(config.error_patterns, default_config.error_patterns);
         assert_eq!(config.restart_delay, default_config.restart_delay);
     }
+
+    #[test]
+    fn test_config_from_cli_config() {
+        let cli_config = CliConfig {
+            error_pattern: "ERROR:".into(),
+            max_spawn_retries: Some(4),
+            spawn_backoff_cap_secs: Some(10),
+            ..CliConfig::default()
+        };
+        let config = Config::new().with_cli_config(&cli_config).unwrap();
+
+        assert_eq!(config.error_patterns, vec!["ERROR:"]);
+        assert_eq!(config.max_spawn_retries, Some(4));
+        assert_eq!(config.spawn_backoff_cap, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_empty_ready_pattern_is_ignored() {
+        let cli_config = CliConfig {
+            ready_pattern: Some(String::new()),
+            ..CliConfig::default()
+        };
+        let config = Config::new().with_cli_config(&cli_config).unwrap();
+        assert_eq!(config.ready_pattern, None);
+        assert_eq!(config.ready_marker, "DEV_READY");
+    }
+
+    #[test]
+    fn test_pattern_restart_delay_overrides_global() {
+        let cli_config = CliConfig {
+            pattern_rules: vec![
+                PatternRule {
+                    pattern: "ECONNRESET".to_string(),
+                    restart_delay_secs: Some(0),
+                    recovery_command: None,
+                    cooldown_secs: None,
+                    regex: false,
+                },
+                PatternRule {
+                    pattern: "TS2304".to_string(),
+                    restart_delay_secs: None,
+                    recovery_command: None,
+                    cooldown_secs: None,
+                    regex: false,
+                },
+            ],
+            ..CliConfig::default()
+        };
+        let config = Config::new().with_cli_config(&cli_config).unwrap();
+
+        assert_eq!(
+            config.error_patterns,
+            vec!["[Error", "ECONNRESET", "TS2304"]
+        );
+        assert_eq!(config.restart_delay_for("ECONNRESET"), Duration::ZERO);
+        assert_eq!(config.restart_delay_for("TS2304"), config.restart_delay);
+        assert_eq!(config.restart_delay_for("[Error"), config.restart_delay);
+    }
+
+    #[test]
+    fn test_restart_trigger_combinations() {
+        let policy = |restart_on_error, restart_on_nonzero_exit| {
+            let cli_config = CliConfig {
+                restart_on_error,
+                restart_on_nonzero_exit,
+                ..CliConfig::default()
+            };
+            let config = Config::new().with_cli_config(&cli_config).unwrap();
+            (
+                config.restart_on_error,
+                config.restart_on_nonzero_exit,
+                config.restart_policy(),
+            )
+        };
+
+        assert_eq!(policy(None, None), (true, true, None));
+        assert_eq!(policy(Some(true), Some(true)), (true, true, None));
+
+        let (on_error, on_exit, message) = policy(None, Some(false));
+        assert!(on_error && !on_exit);
+        assert!(message.unwrap().contains("error patterns only"));
+
+        let (on_error, on_exit, message) = policy(Some(false), None);
+        assert!(!on_error && on_exit);
+        assert!(message.unwrap().contains("only reported"));
+
+        let (on_error, on_exit, message) = policy(Some(false), Some(false));
+        assert!(!on_error && !on_exit);
+        assert!(message.unwrap().contains("runs once"));
+    }
+
+    #[test]
+    fn test_recovery_command_is_per_pattern() {
+        let cli_config = CliConfig {
+            pattern_rules: vec![
+                PatternRule {
+                    pattern: "PrismaClientInitializationError".to_string(),
+                    restart_delay_secs: None,
+                    recovery_command: Some("npx prisma generate".to_string()),
+                    cooldown_secs: None,
+                    regex: false,
+                },
+                PatternRule {
+                    pattern: "EADDRINUSE".to_string(),
+                    restart_delay_secs: None,
+                    recovery_command: Some("  ".to_string()),
+                    cooldown_secs: None,
+                    regex: false,
+                },
+            ],
+            ..CliConfig::default()
+        };
+        let config = Config::new().with_cli_config(&cli_config).unwrap();
+
+        assert_eq!(
+            config.recovery_command_for("PrismaClientInitializationError"),
+            Some("npx prisma generate")
+        );
+        assert_eq!(config.recovery_command_for("EADDRINUSE"), None);
+        assert_eq!(config.recovery_command_for("[Error"), None);
+    }
 }
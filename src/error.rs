@@ -7,6 +7,7 @@ pub enum ServerError {
     IoError(String),
     ChannelError(String),
     ProcessManagement(String),
+    ConfigError(String),
 }
 
 impl fmt::Display for ServerError {
@@ -16,6 +17,7 @@ impl fmt::Display for ServerError {
             ServerError::IoError(msg) => write!(f, "IO error: {}", msg),
             ServerError::ChannelError(msg) => write!(f, "Channel communication error: {}", msg),
             ServerError::ProcessManagement(msg) => write!(f, "Process management error: {}", msg),
+            ServerError::ConfigError(msg) => write!(f, "Configuration error: {}", msg),
         }
     }
 }
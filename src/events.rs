@@ -0,0 +1,325 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::process::Stdio;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::command::{CommandBuilder, CommandType};
+use crate::error;
+use crate::record::Recorder;
+use crate::stats::BootTiming;
+use crate::status;
+use crate::theme::{self, Icon};
+
+/// Version of the JSON event object sent to `event_command`.
+/// Bump when fields are removed or change meaning; adding optional fields keeps the version.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Lifecycle events reported to the event command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A new server process was spawned
+    Start,
+    /// The ready pattern was seen
+    Ready,
+    /// An error pattern was seen
+    Error,
+    /// The monitor is about to restart the server
+    Restart,
+    /// The server process exited on its own
+    Exit,
+}
+
+/// JSON object written to the event command's stdin
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleEvent {
+    pub version: u32,
+    pub event: EventKind,
+    pub timestamp_ms: u128,
+    pub attempt: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Named groups captured by a regex error pattern
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub captures: BTreeMap<String, String>,
+    /// For `ready` events: milliseconds from the spawn to the first output line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_output_ms: Option<u128>,
+    /// For `ready` events: milliseconds from the spawn to readiness
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boot_ms: Option<u128>,
+}
+
+impl LifecycleEvent {
+    pub fn new(event: EventKind, attempt: u32) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        Self {
+            version: EVENT_SCHEMA_VERSION,
+            event,
+            timestamp_ms,
+            attempt,
+            pid: None,
+            exit_code: None,
+            message: None,
+            captures: BTreeMap::new(),
+            first_output_ms: None,
+            boot_ms: None,
+        }
+    }
+
+    pub fn with_pid(mut self, pid: u32) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    pub fn with_exit_code(mut self, exit_code: Option<i32>) -> Self {
+        self.exit_code = exit_code;
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn with_boot_timing(mut self, boot: &BootTiming) -> Self {
+        self.first_output_ms = boot.first_output.map(|d| d.as_millis());
+        self.boot_ms = Some(boot.ready.as_millis());
+        self
+    }
+
+    pub fn with_captures(mut self, captures: BTreeMap<String, String>) -> Self {
+        self.captures = captures;
+        self
+    }
+}
+
+/// File that every event is appended to as one JSON line (`event_log`), for `tail -f | jq`
+#[derive(Debug)]
+pub struct EventLog {
+    file: Mutex<File>,
+}
+
+impl EventLog {
+    pub fn open(path: &str) -> error::Result<Self> {
+        Ok(Self {
+            file: Mutex::new(status::open_append(path)?),
+        })
+    }
+
+    /// Append the event, flushed so a reader sees it right away
+    pub fn write(&self, event: &LifecycleEvent) -> io::Result<()> {
+        let json = serde_json::to_string(event)?;
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{}", json)?;
+        file.flush()
+    }
+}
+
+/// Cheap handle for reporting events; does nothing when no event command or event log is
+/// configured and nothing is being recorded
+#[derive(Debug, Clone, Default)]
+pub struct EventEmitter {
+    tx: Option<Sender<LifecycleEvent>>,
+    recorder: Recorder,
+    log: Option<Arc<EventLog>>,
+}
+
+impl EventEmitter {
+    /// Also add every event to the `--record` file
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    /// Also append every event to `event_log`
+    pub fn with_log(mut self, log: Arc<EventLog>) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    pub fn emit(&self, event: LifecycleEvent) {
+        self.recorder.event(&event);
+        if let Some(log) = &self.log
+            && let Err(e) = log.write(&event)
+        {
+            eprintln!(
+                "{} Failed to write {:?} event to event_log: {}",
+                theme::icon(Icon::Warning),
+                event.event,
+                e
+            );
+        }
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(event);
+        }
+    }
+}
+
+/// Background thread that runs the event command once per event, in order
+pub struct EventWorker {
+    handle: JoinHandle<()>,
+}
+
+impl EventWorker {
+    /// Wait for queued events to be delivered. All emitters must be dropped first.
+    pub fn finish(self) {
+        let _ = self.handle.join();
+    }
+}
+
+/// Start delivering events to `command`, or return a no-op emitter when there is none
+pub fn start(command: Option<String>) -> (EventEmitter, Option<EventWorker>) {
+    let Some(command) = command else {
+        return (EventEmitter::default(), None);
+    };
+
+    let (tx, rx) = mpsc::channel::<LifecycleEvent>();
+    let handle = thread::spawn(move || {
+        for event in rx {
+            if let Err(e) = run_event_command(&command, &event) {
+                eprintln!(
+                    "{} event_command failed for {:?} event: {}",
+                    theme::icon(Icon::Warning),
+                    event.event,
+                    e
+                );
+            }
+        }
+    });
+
+    (
+        EventEmitter {
+            tx: Some(tx),
+            recorder: Recorder::default(),
+            log: None,
+        },
+        Some(EventWorker { handle }),
+    )
+}
+
+fn run_event_command(command: &str, event: &LifecycleEvent) -> Result<(), String> {
+    let json = serde_json::to_string(event).map_err(|e| e.to_string())?;
+    let mut child = CommandBuilder::build(CommandType::Hook(command.to_string()))
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read stdin closes the pipe early; that's not a failure
+        let _ = writeln!(stdin, "{}", json);
+    }
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {}", status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_json_schema() {
+        let mut event = LifecycleEvent::new(EventKind::Exit, 3)
+            .with_pid(42)
+            .with_exit_code(Some(1));
+        event.timestamp_ms = 1_700_000_000_000;
+
+        let json: serde_json::Value = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "version": 1,
+                "event": "exit",
+                "timestamp_ms": 1_700_000_000_000u64,
+                "attempt": 3,
+                "pid": 42,
+                "exit_code": 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_disabled_emitter_is_noop() {
+        let (emitter, worker) = start(None);
+        emitter.emit(LifecycleEvent::new(EventKind::Start, 1));
+        assert!(worker.is_none());
+    }
+
+    #[test]
+    fn test_event_log_appends_one_json_line_per_event() {
+        let path = std::env::temp_dir().join("dev-cli-test-event-log.ndjson");
+        let _ = std::fs::remove_file(&path);
+
+        let log = Arc::new(EventLog::open(path.to_str().unwrap()).unwrap());
+        let emitter = EventEmitter::default().with_log(log);
+        let events = [
+            LifecycleEvent::new(EventKind::Start, 1).with_pid(7),
+            LifecycleEvent::new(EventKind::Error, 1)
+                .with_pid(7)
+                .with_message("error pattern '[Error' detected")
+                .with_captures(BTreeMap::from([("code".to_string(), "E42".to_string())])),
+            LifecycleEvent::new(EventKind::Restart, 1),
+        ];
+        for event in &events {
+            emitter.emit(event.clone());
+        }
+
+        // Readable while the emitter is still alive, as with `tail -f`
+        let content = std::fs::read_to_string(&path).unwrap();
+        let logged: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let expected: Vec<serde_json::Value> = events
+            .iter()
+            .map(|event| serde_json::to_value(event).unwrap())
+            .collect();
+        assert_eq!(logged, expected);
+        assert_eq!(logged[1]["captures"]["code"], "E42");
+
+        drop(emitter);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_event_command_receives_json_on_stdin() {
+        let output = std::env::temp_dir().join("dev-cli-test-events.ndjson");
+        let _ = std::fs::remove_file(&output);
+
+        let (emitter, worker) = start(Some(format!("cat >> '{}'", output.display())));
+        emitter.emit(LifecycleEvent::new(EventKind::Start, 1).with_pid(7));
+        emitter.emit(LifecycleEvent::new(EventKind::Ready, 1));
+        drop(emitter);
+        worker.unwrap().finish();
+
+        let content = std::fs::read_to_string(&output).unwrap();
+        let events: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event"], "start");
+        assert_eq!(events[0]["pid"], 7);
+        assert_eq!(events[1]["event"], "ready");
+
+        std::fs::remove_file(&output).unwrap();
+    }
+}
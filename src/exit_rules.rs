@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::process::ExitStatus;
+
+use crate::error::{Result, ServerError};
+
+/// What happens to the session when the server exits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExitAction {
+    /// Start the server again, like after a crash
+    Restart,
+    /// End the session: cleanly for code 0, as a failure for any other code
+    Stop,
+}
+
+/// One entry of `exit_code_rules`: what to do when the server exits with a matching code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExitCodeRule {
+    /// The exit code this rule is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<i32>,
+    /// Inclusive range of exit codes this rule is for, e.g. `[128, 255]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_range: Option<[i32; 2]>,
+    pub action: ExitAction,
+    /// Warning printed when the server exits with a matching code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<String>,
+    /// Command run to completion before restarting, e.g. to free memory after an OOM kill
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_command: Option<String>,
+    /// Seconds to wait before restarting; overrides `restart_delay`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_delay_secs: Option<u64>,
+}
+
+impl ExitCodeRule {
+    pub fn matches(&self, code: i32) -> bool {
+        match (self.code, self.code_range) {
+            (Some(exact), _) => code == exact,
+            (None, Some([low, high])) => (low..=high).contains(&code),
+            (None, None) => false,
+        }
+    }
+}
+
+impl fmt::Display for ExitCodeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.code, self.code_range) {
+            (Some(code), _) => write!(f, "exit code {}", code),
+            (None, Some([low, high])) => write!(f, "exit codes {}-{}", low, high),
+            (None, None) => write!(f, "no exit code"),
+        }
+    }
+}
+
+/// Check `exit_code_rules` when the config is loaded
+pub fn validate(rules: &[ExitCodeRule]) -> Result<()> {
+    for (index, rule) in rules.iter().enumerate() {
+        let invalid = |reason: &str| {
+            ServerError::ConfigError(format!("exit_code_rules[{}]: {}", index, reason))
+        };
+        match (rule.code, rule.code_range) {
+            (Some(_), Some(_)) => return Err(invalid("set code or code_range, not both")),
+            (None, None) => return Err(invalid("needs a code or code_range")),
+            (None, Some([low, high])) if low > high => {
+                return Err(invalid(
+                    "code_range must go from the lower code to the higher",
+                ));
+            }
+            _ => {}
+        }
+        if rule.action == ExitAction::Stop
+            && (rule.recovery_command.is_some() || rule.restart_delay_secs.is_some())
+        {
+            return Err(invalid(
+                "recovery_command and restart_delay_secs only apply to \"action\": \"restart\"",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The code an exit is matched with: its exit code, or 128 + the signal number for a server
+/// killed by a signal, as a shell reports it, so 137 covers SIGKILL with or without a shell
+/// in between
+pub fn rule_code(status: &ExitStatus) -> Option<i32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            return Some(128 + signal);
+        }
+    }
+    status.code()
+}
+
+/// The first rule for `code`; an exit without a known code matches none
+pub fn find(rules: &[ExitCodeRule], code: Option<i32>) -> Option<&ExitCodeRule> {
+    let code = code?;
+    rules.iter().find(|rule| rule.matches(code))
+}
+
+/// What to do after an exit with `code`: the first matching rule decides, and without one
+/// the defaults apply, which are what dev did before the rules existed: code 0 stops,
+/// anything else restarts unless `restart_on_nonzero_exit` is false
+pub fn action(
+    rules: &[ExitCodeRule],
+    code: Option<i32>,
+    restart_on_nonzero_exit: bool,
+) -> ExitAction {
+    match find(rules, code) {
+        Some(rule) => rule.action,
+        None if code == Some(0) || !restart_on_nonzero_exit => ExitAction::Stop,
+        None => ExitAction::Restart,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(json: &str) -> Vec<ExitCodeRule> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_exact_codes_and_ranges_pick_the_action() {
+        let rules = rules(
+            r#"[{"code": 137, "action": "restart", "notify": "Killed for memory"},
+                {"code": 0, "action": "restart"},
+                {"code_range": [64, 78], "action": "stop"},
+                {"code_range": [128, 255], "action": "stop"}]"#,
+        );
+        assert_eq!(action(&rules, Some(0), true), ExitAction::Restart);
+        // The first matching rule wins over the range after it
+        assert_eq!(action(&rules, Some(137), true), ExitAction::Restart);
+        assert_eq!(
+            find(&rules, Some(137)).unwrap().notify.as_deref(),
+            Some("Killed for memory")
+        );
+        assert_eq!(action(&rules, Some(64), true), ExitAction::Stop);
+        assert_eq!(action(&rules, Some(78), true), ExitAction::Stop);
+        assert_eq!(action(&rules, Some(143), true), ExitAction::Stop);
+        assert_eq!(
+            find(&rules, Some(143)).unwrap().to_string(),
+            "exit codes 128-255"
+        );
+    }
+
+    #[test]
+    fn test_unmatched_exits_fall_through_to_the_defaults() {
+        let rules = rules(r#"[{"code": 3, "action": "stop"}]"#);
+        assert_eq!(action(&rules, Some(0), true), ExitAction::Stop);
+        assert_eq!(action(&rules, Some(1), true), ExitAction::Restart);
+        assert_eq!(action(&rules, None, true), ExitAction::Restart);
+        assert_eq!(action(&rules, Some(1), false), ExitAction::Stop);
+        assert_eq!(action(&rules, None, false), ExitAction::Stop);
+        assert_eq!(action(&[], Some(0), true), ExitAction::Stop);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signalled_exits_match_as_128_plus_the_signal() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let rules = rules(r#"[{"code": 137, "action": "stop"}]"#);
+        let killed = ExitStatus::from_raw(libc::SIGKILL);
+        assert_eq!(rule_code(&killed), Some(137));
+        assert_eq!(action(&rules, rule_code(&killed), true), ExitAction::Stop);
+        // The same kill reported by a shell
+        assert_eq!(rule_code(&ExitStatus::from_raw(137 << 8)), Some(137));
+        assert_eq!(rule_code(&ExitStatus::from_raw(0)), Some(0));
+    }
+
+    #[test]
+    fn test_validate_rejects_unusable_rules() {
+        let error = |json: &str| validate(&rules(json)).unwrap_err().to_string();
+        assert!(
+            validate(&rules(
+                r#"[{"code": 1, "action": "restart", "restart_delay_secs": 5}]"#
+            ))
+            .is_ok()
+        );
+        assert!(error(r#"[{"action": "stop"}]"#).contains("needs a code"));
+        assert!(
+            error(r#"[{"code": 1, "code_range": [1, 2], "action": "stop"}]"#).contains("not both")
+        );
+        assert!(error(r#"[{"code_range": [9, 2], "action": "stop"}]"#).contains("lower code"));
+        assert!(
+            error(r#"[{"code": 2, "action": "stop"}, {"code": 1, "action": "stop", "recovery_command": "x"}]"#)
+                .starts_with("Configuration error: exit_code_rules[1]")
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use std::process::ExitStatus;
+
+use crate::color::{self, Color};
+#[cfg(unix)]
+use crate::signals;
+use crate::status;
+
+/// Human-readable description of how the server ended, with the color for its severity
+pub fn describe(status: &ExitStatus) -> (String, Color) {
+    if status.success() {
+        return ("exited cleanly".to_string(), Color::Green);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            return (format!("killed by {}", signal_label(signal)), Color::Red);
+        }
+        // The dev command runs under a shell, which reports a child killed by signal N as 128 + N
+        if let Some(code) = status.code()
+            && code > 128
+            && let Some(name) = signals::signal_name(code - 128)
+        {
+            return (
+                format!("exited with code {} (child killed by {})", code, name),
+                Color::Red,
+            );
+        }
+    }
+
+    match status.code() {
+        Some(code) => (format!("exited with code {}", code), Color::Yellow),
+        None => (format!("exited with {}", status), Color::Red),
+    }
+}
+
+/// `describe` as a status line, colored when its destination supports it
+pub fn format(status: &ExitStatus) -> String {
+    let (text, severity) = describe(status);
+    if status::color_enabled() {
+        color::paint(&text, severity)
+    } else {
+        text
+    }
+}
+
+#[cfg(unix)]
+fn signal_label(signal: i32) -> String {
+    match signals::signal_name(signal) {
+        Some(name) => format!("{} ({})", name, signal),
+        None => format!("signal {}", signal),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn test_describe_exit_codes() {
+        assert_eq!(
+            describe(&ExitStatus::from_raw(0)),
+            ("exited cleanly".to_string(), Color::Green)
+        );
+        assert_eq!(
+            describe(&ExitStatus::from_raw(1 << 8)),
+            ("exited with code 1".to_string(), Color::Yellow)
+        );
+        assert_eq!(
+            describe(&ExitStatus::from_raw(137 << 8)),
+            (
+                "exited with code 137 (child killed by SIGKILL)".to_string(),
+                Color::Red
+            )
+        );
+    }
+
+    #[test]
+    fn test_describe_signals() {
+        assert_eq!(
+            describe(&ExitStatus::from_raw(libc::SIGKILL)),
+            ("killed by SIGKILL (9)".to_string(), Color::Red)
+        );
+        assert_eq!(describe(&ExitStatus::from_raw(64)).0, "killed by signal 64");
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use crate::cleanup::command_matches;
+use crate::cli_config::CliConfig;
+use crate::error::{Result, ServerError};
+use crate::process;
+use crate::state::{SpawnedProcess, StateStore};
+
+/// Whether the server of a running dev session is up and has been ready (`dev --health`).
+/// Only reads `ready_file` and `state_file`; never touches the server.
+pub fn check(config_path: &Path, profile: Option<&str>) -> Result<bool> {
+    let cli_config = CliConfig::read_for_session(config_path, profile)?;
+    let (Some(ready_file), Some(state_file)) = (&cli_config.ready_file, &cli_config.state_file)
+    else {
+        return Err(ServerError::ConfigError(format!(
+            "--health needs ready_file and state_file in {}",
+            config_path.display()
+        )));
+    };
+
+    let spawned = StateStore::new(state_file).load()?.spawned;
+    Ok(is_healthy(
+        Path::new(ready_file),
+        &spawned,
+        process::command_line,
+    ))
+}
+
+/// Healthy: readiness was announced this session and a server process dev started is
+/// still running the command it was started with
+fn is_healthy(
+    ready_file: &Path,
+    spawned: &[SpawnedProcess],
+    command_line: impl Fn(u32) -> Option<String>,
+) -> bool {
+    ready_file.exists()
+        && spawned.iter().any(|recorded| {
+            command_line(recorded.pid).is_some_and(|live| command_matches(&recorded.command, &live))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_healthy_needs_readiness_and_a_live_server() {
+        let ready_file = std::env::temp_dir().join("dev-cli-test-health-ready");
+        let _ = fs::remove_file(&ready_file);
+        let spawned = [SpawnedProcess {
+            pid: 4242,
+            command: "sh -c npm run dev".to_string(),
+        }];
+        let running = |pid| (pid == 4242).then(|| "sh -c npm run dev".to_string());
+
+        // Alive but never ready
+        assert!(!is_healthy(&ready_file, &spawned, running));
+
+        fs::write(&ready_file, "DEV_READY\n").unwrap();
+        assert!(is_healthy(&ready_file, &spawned, running));
+        // The server exited, or its PID now belongs to something else
+        assert!(!is_healthy(&ready_file, &spawned, |_| None));
+        assert!(!is_healthy(&ready_file, &spawned, |_| Some(
+            "vim notes.txt".to_string()
+        )));
+        assert!(!is_healthy(&ready_file, &[], running));
+
+        fs::remove_file(&ready_file).unwrap();
+    }
+}
@@ -0,0 +1,472 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::command::{CommandBuilder, CommandType};
+use crate::error::{Result, ServerError};
+use crate::exit_status;
+use crate::process;
+use crate::secrets::ResolvedEnv;
+use crate::status::status;
+use crate::theme::{self, Icon};
+
+/// PIDs of hook commands still running in the background, shared with the Ctrl+C handler
+pub type BackgroundPids = Arc<Mutex<Vec<u32>>>;
+
+/// A group of shell commands run together, e.g. `pre_commands`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookGroup {
+    pub commands: Vec<String>,
+    /// Start every command at once instead of one after another
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub parallel: bool,
+    /// With `parallel`, wait for all commands to finish; `false` leaves them running in the
+    /// background until `dev` exits. Sequential groups always wait.
+    #[serde(default = "default_wait", skip_serializing_if = "is_default_wait")]
+    pub wait: bool,
+}
+
+fn default_wait() -> bool {
+    true
+}
+
+fn is_default_wait(wait: &bool) -> bool {
+    *wait
+}
+
+/// Runs hook groups and stops any background commands when dropped
+#[derive(Default)]
+pub struct HookRunner {
+    background: Vec<Child>,
+    background_pids: Option<BackgroundPids>,
+}
+
+impl HookRunner {
+    pub fn new(background_pids: Option<BackgroundPids>) -> Self {
+        Self {
+            background: Vec::new(),
+            background_pids,
+        }
+    }
+
+    /// Run every command of `group`; `name` is used in messages
+    pub fn run(&mut self, name: &str, group: &HookGroup) -> Result<()> {
+        if !group.parallel {
+            for command in &group.commands {
+                status!("{} Running {}: {}", theme::icon(Icon::Hook), name, command);
+                let child = spawn_hook(name, command)?;
+                wait_for_hook(name, command, child)?;
+            }
+            return Ok(());
+        }
+
+        let mut children = Vec::with_capacity(group.commands.len());
+        for command in &group.commands {
+            status!(
+                "{} Starting {} in parallel: {}",
+                theme::icon(Icon::Hook),
+                name,
+                command
+            );
+            children.push((command, spawn_hook(name, command)?));
+        }
+
+        if !group.wait {
+            for (_, child) in children {
+                self.track(child);
+            }
+            return Ok(());
+        }
+
+        // Wait for all of them before reporting the first failure
+        let mut result = Ok(());
+        for (command, child) in children {
+            if let Err(e) = wait_for_hook(name, command, child)
+                && result.is_ok()
+            {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    fn track(&mut self, child: Child) {
+        if let Some(pids) = &self.background_pids {
+            pids.lock().unwrap().push(child.id());
+        }
+        self.background.push(child);
+    }
+
+    /// Stop background commands that are still running
+    pub fn stop_background(&mut self) {
+        for mut child in self.background.drain(..) {
+            if let Ok(None) = child.try_wait() {
+                process::signal_pid(child.id(), true);
+            }
+            let _ = child.wait();
+        }
+        if let Some(pids) = &self.background_pids {
+            pids.lock().unwrap().clear();
+        }
+    }
+}
+
+impl Drop for HookRunner {
+    fn drop(&mut self) {
+        self.stop_background();
+    }
+}
+
+/// Runs the `on_tick` command from the monitor's check loop without ever waiting for it
+pub struct TickHook {
+    command: String,
+    interval: Duration,
+    last_run: Option<Instant>,
+    running: Option<Child>,
+}
+
+impl TickHook {
+    pub fn new(command: impl Into<String>, interval: Duration) -> Self {
+        Self {
+            command: command.into(),
+            interval,
+            last_run: None,
+            running: None,
+        }
+    }
+
+    /// Whether the hook is due; it never overlaps with a previous run that is still going
+    fn is_due(&mut self, now: Instant) -> bool {
+        if let Some(child) = &mut self.running {
+            match child.try_wait() {
+                Ok(None) => return false,
+                _ => self.running = None,
+            }
+        }
+        self.last_run
+            .is_none_or(|last| now.duration_since(last) >= self.interval)
+    }
+
+    /// Start the hook if it's due, passing uptime and counters through the environment
+    pub fn tick(&mut self, uptime: Duration, attempt: u32, restarts: u32) {
+        let now = Instant::now();
+        if !self.is_due(now) {
+            return;
+        }
+        self.last_run = Some(now);
+
+        match CommandBuilder::build(CommandType::Hook(self.command.clone()))
+            .env("DEV_UPTIME_SECS", uptime.as_secs().to_string())
+            .env("DEV_ATTEMPT", attempt.to_string())
+            .env("DEV_RESTARTS", restarts.to_string())
+            .spawn()
+        {
+            Ok(child) => self.running = Some(child),
+            Err(e) => eprintln!(
+                "{} on_tick failed to start: {}",
+                theme::icon(Icon::Warning),
+                e
+            ),
+        }
+    }
+}
+
+impl Drop for TickHook {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.running.take()
+            && let Ok(None) = child.try_wait()
+        {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Run the `warmup` command to completion, printing its output with a `🔥` prefix.
+/// Nothing after it can work if it fails, so a failure is returned as an error.
+pub fn run_warmup(command: &str) -> Result<()> {
+    status!("{} Warming up: {}", theme::icon(Icon::Warmup), command);
+    let mut child = CommandBuilder::build(CommandType::Hook(command.to_string()))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ServerError::ProcessStart(format!("warmup '{}': {}", command, e)))?;
+
+    let stderr = child.stderr.take().map(|stderr| {
+        thread::spawn(move || {
+            print_prefixed(stderr, |line| {
+                eprintln!("{} {}", theme::icon(Icon::Warmup), line)
+            })
+        })
+    });
+    if let Some(stdout) = child.stdout.take() {
+        print_prefixed(stdout, |line| {
+            println!("{} {}", theme::icon(Icon::Warmup), line)
+        });
+    }
+    if let Some(handle) = stderr {
+        let _ = handle.join();
+    }
+
+    wait_for_hook("warmup", command, child)
+}
+
+/// Run a pattern's `recovery_command` to completion before the restart it precedes, in the
+/// server's `working_dir` and with its `env`, since it fixes up what the server runs with.
+/// A failed recovery is only reported: the restart still happens.
+pub fn run_recovery(pattern: &str, command: &str, working_dir: Option<&str>, env: &ResolvedEnv) {
+    status!(
+        "{} Recovering from '{}': {}",
+        theme::icon(Icon::Hook),
+        pattern,
+        command
+    );
+    let mut hook = CommandBuilder::build(CommandType::Hook(command.to_string()));
+    if let Some(dir) = working_dir {
+        hook.current_dir(dir);
+    }
+    env.apply(&mut hook);
+    if let Err(e) = run_to_completion(hook) {
+        eprintln!(
+            "{} recovery_command '{}' {}; restarting anyway",
+            theme::icon(Icon::Warning),
+            command,
+            e
+        );
+    }
+}
+
+/// Run `on_restart` to completion before a restart, telling it why the server is restarting.
+/// Like a recovery, a failure is only reported.
+pub fn run_on_restart(command: &str, reason: &str, detail: &str, attempt: u32, restarts: u32) {
+    status!(
+        "{} Running on_restart command ({}): {}",
+        theme::icon(Icon::Hook),
+        reason,
+        command
+    );
+    let mut hook = CommandBuilder::build(CommandType::Hook(command.to_string()));
+    hook.env("DEV_RESTART_REASON", reason)
+        .env("DEV_RESTART_DETAIL", detail)
+        .env("DEV_ATTEMPT", attempt.to_string())
+        .env("DEV_RESTARTS", restarts.to_string());
+    if let Err(e) = run_to_completion(hook) {
+        eprintln!(
+            "{} on_restart '{}' {}; restarting anyway",
+            theme::icon(Icon::Warning),
+            command,
+            e
+        );
+    }
+}
+
+/// Run `on_clean_exit` to completion after the server exited normally, before `dev` exits.
+/// A failure is only reported.
+pub fn run_on_clean_exit(command: &str, attempt: u32, restarts: u32) {
+    status!(
+        "{} Running on_clean_exit command: {}",
+        theme::icon(Icon::Hook),
+        command
+    );
+    let mut hook = CommandBuilder::build(CommandType::Hook(command.to_string()));
+    hook.env("DEV_ATTEMPT", attempt.to_string())
+        .env("DEV_RESTARTS", restarts.to_string());
+    if let Err(e) = run_to_completion(hook) {
+        eprintln!(
+            "{} on_clean_exit '{}' {}",
+            theme::icon(Icon::Warning),
+            command,
+            e
+        );
+    }
+}
+
+/// Run a hook and wait for it; the error says why it failed, e.g. "exited with code 1"
+fn run_to_completion(mut hook: Command) -> std::result::Result<(), String> {
+    let status = hook
+        .status()
+        .map_err(|e| format!("could not start: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(exit_status::describe(&status).0)
+    }
+}
+
+fn print_prefixed(output: impl Read, print: impl Fn(&str)) {
+    for line in BufReader::new(output).lines().map_while(|line| line.ok()) {
+        print(&line);
+    }
+}
+
+fn spawn_hook(name: &str, command: &str) -> Result<Child> {
+    CommandBuilder::build(CommandType::Hook(command.to_string()))
+        .spawn()
+        .map_err(|e| ServerError::ProcessStart(format!("{} '{}': {}", name, command, e)))
+}
+
+fn wait_for_hook(name: &str, command: &str, mut child: Child) -> Result<()> {
+    let status = child
+        .wait()
+        .map_err(|e| ServerError::ProcessManagement(format!("{} '{}': {}", name, command, e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ServerError::ProcessStart(format!(
+            "{} '{}' failed with {}",
+            name, command, status
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(commands: &[&str], parallel: bool, wait: bool) -> HookGroup {
+        HookGroup {
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            parallel,
+            wait,
+        }
+    }
+
+    #[test]
+    fn test_hook_group_defaults() {
+        let group: HookGroup = serde_json::from_str(r#"{"commands": ["make assets"]}"#).unwrap();
+        assert!(!group.parallel);
+        assert!(group.wait);
+        assert_eq!(
+            serde_json::to_string(&group).unwrap(),
+            r#"{"commands":["make assets"]}"#
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_sequential_group_stops_at_failure() {
+        let marker = std::env::temp_dir().join("dev-cli-test-hooks-sequential");
+        let _ = std::fs::remove_file(&marker);
+
+        let mut runner = HookRunner::default();
+        let result = runner.run(
+            "pre_commands",
+            &group(
+                &["false", &format!("touch '{}'", marker.display())],
+                false,
+                true,
+            ),
+        );
+        assert!(matches!(result, Err(ServerError::ProcessStart(_))));
+        assert!(!marker.exists());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_warmup_runs_to_completion_and_reports_failure() {
+        let marker = std::env::temp_dir().join("dev-cli-test-warmup");
+        let _ = std::fs::remove_file(&marker);
+
+        run_warmup(&format!(
+            "echo generated; echo to stderr >&2; echo run >> '{}'",
+            marker.display()
+        ))
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "run\n");
+        std::fs::remove_file(&marker).unwrap();
+
+        let result = run_warmup("echo migrating; exit 3");
+        assert!(
+            matches!(result, Err(ServerError::ProcessStart(message)) if message.contains("warmup"))
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_tick_hook_respects_interval_and_never_overlaps() {
+        let mut hook = TickHook::new("sleep 30", Duration::ZERO);
+        let now = Instant::now();
+        assert!(hook.is_due(now));
+
+        hook.tick(Duration::from_secs(1), 1, 0);
+        assert!(hook.running.is_some());
+        // Still running, so the next tick is skipped even with a zero interval
+        assert!(!hook.is_due(Instant::now()));
+
+        let mut throttled = TickHook::new("true", Duration::from_secs(60));
+        throttled.last_run = Some(now);
+        assert!(!throttled.is_due(now + Duration::from_secs(59)));
+        assert!(throttled.is_due(now + Duration::from_secs(60)));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_parallel_background_commands_are_stopped() {
+        let pids: BackgroundPids = Arc::new(Mutex::new(Vec::new()));
+        let mut runner = HookRunner::new(Some(Arc::clone(&pids)));
+        runner
+            .run(
+                "pre_commands",
+                &group(&["sleep 30", "sleep 30"], true, false),
+            )
+            .unwrap();
+        assert_eq!(pids.lock().unwrap().len(), 2);
+
+        drop(runner);
+        assert!(pids.lock().unwrap().is_empty());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_on_restart_gets_the_reason_in_its_environment() {
+        let out = std::env::temp_dir().join("dev-cli-test-on-restart");
+        let _ = std::fs::remove_file(&out);
+        let command = format!(
+            "echo \"$DEV_RESTART_REASON|$DEV_RESTART_DETAIL|$DEV_ATTEMPT|$DEV_RESTARTS\" >> '{}'",
+            out.display()
+        );
+
+        run_on_restart(&command, "error", "[Error", 3, 2);
+        run_on_restart(&command, "config_change", "", 4, 3);
+        // A failing hook doesn't stop the restart
+        run_on_restart("exit 4", "exit", "1", 5, 4);
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "error|[Error|3|2\nconfig_change||4|3\n"
+        );
+        std::fs::remove_file(&out).unwrap();
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_recovery_runs_in_the_servers_directory_and_environment() {
+        let dir = std::env::temp_dir().join("dev-cli-test-recovery-dir");
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let out = dir.join("recovered");
+        let _ = std::fs::remove_file(&out);
+        let env = crate::secrets::resolve(
+            &std::collections::BTreeMap::from([(
+                "DATABASE_URL".to_string(),
+                "postgres://localhost/dev".to_string(),
+            )]),
+            None,
+        )
+        .unwrap();
+
+        run_recovery(
+            "PrismaClientInitializationError",
+            "echo \"$(pwd)|$DATABASE_URL\" > recovered",
+            Some(&dir.display().to_string()),
+            &env,
+        );
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            format!("{}|postgres://localhost/dev\n", dir.display())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
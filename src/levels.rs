@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::color::{self, Color};
+
+/// How many leading tokens are searched for a level (e.g. timestamp, module, level)
+const MAX_LEVEL_TOKEN_POSITION: usize = 3;
+
+/// Default level colors used when `level_patterns` is not configured
+pub fn default_level_patterns() -> HashMap<String, String> {
+    [
+        ("ERROR", "red"),
+        ("FATAL", "red"),
+        ("WARN", "yellow"),
+        ("WARNING", "yellow"),
+        ("INFO", "green"),
+        ("DEBUG", "blue"),
+        ("TRACE", "gray"),
+    ]
+    .into_iter()
+    .map(|(level, color)| (level.to_string(), color.to_string()))
+    .collect()
+}
+
+/// Colors whole output lines according to their leading severity level
+#[derive(Debug, Clone)]
+pub struct LevelColorizer {
+    levels: HashMap<String, Color>,
+}
+
+impl LevelColorizer {
+    /// Build from a level name → color name map; unknown color names are returned as errors
+    pub fn from_patterns(patterns: &HashMap<String, String>) -> Result<Self, String> {
+        let mut levels = HashMap::new();
+        for (level, color_name) in patterns {
+            let color = Color::from_name(color_name)
+                .ok_or_else(|| format!("unknown color '{}' for level '{}'", color_name, level))?;
+            levels.insert(level.to_ascii_uppercase(), color);
+        }
+        Ok(Self { levels })
+    }
+
+    /// Find the level token near the start of a line, e.g. `WARN`, `[ERROR]`, `level=info`
+    pub fn extract_level(&self, line: &str) -> Option<String> {
+        line.split_whitespace()
+            .take(MAX_LEVEL_TOKEN_POSITION)
+            .map(|token| {
+                let token = token.strip_prefix("level=").unwrap_or(token);
+                token
+                    .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+                    .to_ascii_uppercase()
+            })
+            .find(|token| self.levels.contains_key(token))
+    }
+
+    /// Color the line for display when it carries a known level and color is enabled
+    pub fn colorize(&self, line: &str, color_enabled: bool) -> String {
+        if !color_enabled {
+            return line.to_string();
+        }
+        match self.extract_level(line) {
+            Some(level) => color::paint(line, self.levels[&level]),
+            None => line.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colorizer() -> LevelColorizer {
+        LevelColorizer::from_patterns(&default_level_patterns()).unwrap()
+    }
+
+    #[test]
+    fn test_extract_plain_prefix() {
+        let levels = colorizer();
+        assert_eq!(
+            levels.extract_level("ERROR failed to bind"),
+            Some("ERROR".into())
+        );
+        assert_eq!(
+            levels.extract_level("WARN: slow query"),
+            Some("WARN".into())
+        );
+    }
+
+    #[test]
+    fn test_extract_bracketed_and_timestamped() {
+        let levels = colorizer();
+        assert_eq!(
+            levels.extract_level("[2024-05-01 12:00:00] [INFO] listening"),
+            Some("INFO".into())
+        );
+        assert_eq!(
+            levels.extract_level("2024-05-01T12:00:00Z DEBUG app::db: connected"),
+            Some("DEBUG".into())
+        );
+    }
+
+    #[test]
+    fn test_extract_logfmt_and_lowercase() {
+        let levels = colorizer();
+        assert_eq!(
+            levels.extract_level("level=warn msg=\"cache miss\""),
+            Some("WARN".into())
+        );
+        assert_eq!(levels.extract_level("info: ready"), Some("INFO".into()));
+    }
+
+    #[test]
+    fn test_no_level_in_message_body() {
+        let levels = colorizer();
+        assert_eq!(
+            levels.extract_level("compiled 3 modules without an ERROR"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_colorize_respects_color_flag() {
+        let levels = colorizer();
+        assert_eq!(levels.colorize("ERROR boom", false), "ERROR boom");
+        assert_eq!(
+            levels.colorize("ERROR boom", true),
+            "\x1b[31mERROR boom\x1b[0m"
+        );
+        assert_eq!(levels.colorize("plain line", true), "plain line");
+    }
+
+    #[test]
+    fn test_unknown_color_rejected() {
+        let patterns = HashMap::from([("ERROR".to_string(), "ultraviolet".to_string())]);
+        assert!(LevelColorizer::from_patterns(&patterns).is_err());
+    }
+}
@@ -1,16 +1,53 @@
 use std::env;
+use std::path::{Path, PathBuf};
 
+mod backoff;
+mod blocks;
+mod budget;
+mod cleanup;
 mod cli_config;
+mod color;
 mod command;
+mod conditions;
 mod config;
 mod error;
+mod events;
+mod exit_rules;
+mod exit_status;
+mod health;
+mod hooks;
+mod levels;
+mod match_test;
 mod monitor;
+mod patterns;
+mod policy;
 mod process;
+mod prompt;
+mod ready;
+mod record;
+mod reload;
+mod remote;
+mod rerun;
+mod resources;
+mod schema;
+mod secrets;
 mod server;
+#[cfg(unix)]
+mod signals;
+mod startup_profile;
+mod state;
+mod stats;
+mod status;
+mod theme;
+mod trial;
+mod workdir;
 
 use config::Config;
-use server::DevServer;
+use server::{DevServer, RunOutcome};
+use status::status;
+use theme::Icon;
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 fn main() {
@@ -18,57 +55,427 @@ fn main() {
     let test_mode = args.iter().any(|arg| arg == "--test");
     let config_mode = args.iter().any(|arg| arg == "--config");
     let help_mode = args.iter().any(|arg| arg == "--help" || arg == "-h");
+    let assume_yes = args.iter().any(|arg| arg == "--yes" || arg == "-y");
+    let cleanup_mode = args.iter().any(|arg| arg == "--cleanup");
+    let health_mode = args.iter().any(|arg| arg == "--health");
+    let fail_fast = args.iter().any(|arg| arg == "--fail-fast");
+    let watch_command = args.iter().any(|arg| arg == "--watch-command");
+    let raw = args.iter().any(|arg| arg == "--raw");
+    let no_delay = args.iter().any(|arg| arg == "--no-delay");
+    let wait_start = args.iter().any(|arg| arg == "--wait-start");
+    let profile_startup = args.iter().any(|arg| arg == "--profile-startup");
+    let json = args.iter().any(|arg| arg == "--json");
+    let schema_mode = args.iter().any(|arg| arg == "--schema");
+    let repeat = match parse_repeat(&args) {
+        Ok(repeat) => repeat,
+        Err(e) => {
+            eprintln!("{} {}", theme::icon(Icon::Failure), e);
+            std::process::exit(2);
+        }
+    };
+    if fail_fast && repeat.is_some() {
+        eprintln!(
+            "{} --fail-fast and --repeat can't be combined",
+            theme::icon(Icon::Failure)
+        );
+        std::process::exit(2);
+    }
+    let profile = match parse_profile(&args) {
+        Ok(profile) => profile.or_else(cli_config::profile_from_env),
+        Err(e) => {
+            eprintln!("{} {}", theme::icon(Icon::Failure), e);
+            std::process::exit(2);
+        }
+    };
+
+    let config_path = match parse_config_path(&args) {
+        Ok(config_path) => config_path,
+        Err(e) => {
+            eprintln!("{} {}", theme::icon(Icon::Failure), e);
+            std::process::exit(2);
+        }
+    };
+
+    let wrapper = match parse_wrapper(&args) {
+        Ok(wrapper) => wrapper,
+        Err(e) => {
+            eprintln!("{} {}", theme::icon(Icon::Failure), e);
+            std::process::exit(2);
+        }
+    };
+
+    let pattern_set = match parse_pattern_set(&args, "--patterns") {
+        Ok(pattern_set) => pattern_set,
+        Err(e) => {
+            eprintln!("{} {}", theme::icon(Icon::Failure), e);
+            std::process::exit(2);
+        }
+    };
+
+    let record = match parse_record(&args) {
+        Ok(record) => record,
+        Err(e) => {
+            eprintln!("{} {}", theme::icon(Icon::Failure), e);
+            std::process::exit(2);
+        }
+    };
 
     if help_mode {
         print_help();
         return;
     }
 
+    if schema_mode {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema::schema()).expect("the schema is plain JSON")
+        );
+        return;
+    }
+
+    match parse_pattern_set(&args, "--print-patterns") {
+        Ok(Some(name)) => {
+            // In the regex_patterns_file format, to copy and adjust
+            println!("# dev pattern set '{}'", name);
+            for pattern in patterns::pattern_set(&name).expect("the name was checked") {
+                println!("{}", pattern);
+            }
+            return;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{} {}", theme::icon(Icon::Failure), e);
+            std::process::exit(2);
+        }
+    }
+
+    match parse_match_test(&args) {
+        // Exit status like grep: 0 when something matched, 1 when nothing did
+        Ok(Some((pattern, log_file))) => {
+            match match_test::run(
+                pattern.as_deref(),
+                Path::new(&log_file),
+                json,
+                &config_path,
+                profile.as_deref(),
+            ) {
+                Ok(true) => return,
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("{} Match test error: {}", theme::icon(Icon::Failure), e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{} {}", theme::icon(Icon::Failure), e);
+            std::process::exit(2);
+        }
+    }
+
     if config_mode {
-        println!("🔧 Reconfiguring dev-cli.json...");
-        if let Err(e) = create_config_interactive() {
-            eprintln!("❌ Configuration error: {}", e);
+        println!(
+            "{} Reconfiguring {}...",
+            theme::icon(Icon::Setup),
+            config_path.display()
+        );
+        if let Err(e) = create_config_interactive(&config_path) {
+            eprintln!("{} Configuration error: {}", theme::icon(Icon::Failure), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if cleanup_mode {
+        if let Err(e) = cleanup::run(&config_path, profile.as_deref()) {
+            eprintln!("{} Cleanup error: {}", theme::icon(Icon::Failure), e);
             std::process::exit(1);
         }
         return;
     }
 
+    // Silent on purpose: container healthchecks only look at the exit code
+    if health_mode {
+        match health::check(&config_path, profile.as_deref()) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("{} Health check error: {}", theme::icon(Icon::Failure), e);
+                std::process::exit(2);
+            }
+        }
+    }
+
     // Shared PID for child process
     let child_pid: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
+    // Background pre-commands to stop on Ctrl+C
+    let background_pids: hooks::BackgroundPids = Arc::new(Mutex::new(Vec::new()));
+    // Session counters, so Ctrl+C can still print the summary
+    let stats = stats::SharedStats::default();
+    // Cleared by the server when no_force_kill is configured
+    let force_kill = Arc::new(AtomicBool::new(true));
+
+    // Set by the server when nohup_mode is configured
+    let nohup = Arc::new(AtomicBool::new(false));
+    // The fresh_workdir copy the server runs in, removed on Ctrl+C
+    let fresh_workdir = workdir::CurrentCopy::default();
+    let teardown = Teardown {
+        child_pid: Arc::clone(&child_pid),
+        force_kill: Arc::clone(&force_kill),
+        background_pids: Arc::clone(&background_pids),
+        stats: Arc::clone(&stats),
+        fresh_workdir: Arc::clone(&fresh_workdir),
+    };
+    #[cfg(unix)]
+    watch_hangup(teardown.clone(), Arc::clone(&nohup));
 
     // Register Ctrl+C handler
     {
-        let child_pid = Arc::clone(&child_pid);
-        ctrlc::set_handler(move || {
-            let pid = *child_pid.lock().unwrap();
-            #[cfg(windows)]
-            if let Some(pid) = pid {
-                println!("🛑 Ctrl+C pressed! Killing process tree (PID {})...", pid);
-                let _ = std::process::Command::new("taskkill")
-                    .args(["/F", "/T", "/PID", &pid.to_string()])
-                    .output();
-            }
-            #[cfg(not(windows))]
-            if let Some(pid) = pid {
-                println!("🛑 Ctrl+C pressed! Killing process (PID {})...", pid);
-                let _ = std::process::Command::new("kill")
-                    .arg("-9")
-                    .arg(pid.to_string())
-                    .output();
-            }
+        let registered = ctrlc::set_handler(move || {
+            teardown.stop_all(Some("Ctrl+C pressed"));
             std::process::exit(130);
-        })
-        .expect("Failed to set Ctrl+C handler");
+        });
+        // Without the handler Ctrl+C still reaches the child through the terminal's
+        // process group; only the explicit tree kill is lost
+        if let Err(e) = registered {
+            eprintln!(
+                "{} Could not register Ctrl+C handler ({}); the dev server won't be cleaned up on Ctrl+C",
+                theme::icon(Icon::Warning),
+                e
+            );
+            eprintln!("   Stop it with `kill <PID>` if it keeps running after dev exits");
+        }
     }
 
     let config = Config::new();
     let mut server = DevServer::new(config, test_mode);
     // Pass the child_pid Arc to the server so it can update the PID
     server.set_child_pid_handle(child_pid);
+    server.set_force_kill_handle(force_kill);
+    server.set_nohup_handle(nohup);
+    server.set_fresh_workdir_handle(Arc::clone(&fresh_workdir));
+    server.set_background_pids_handle(background_pids);
+    server.set_stats_handle(stats);
+    server.set_assume_yes(assume_yes);
+    server.set_repeat(repeat);
+    server.set_fail_fast(fail_fast);
+    server.set_config_path(config_path);
+    server.set_profile(profile);
+    server.set_wrapper(wrapper);
+    server.set_watch_command(watch_command);
+    server.set_record(record);
+    server.set_raw(raw);
+    server.set_skip_initial_delay(no_delay);
+    server.set_wait_start(wait_start);
+    server.set_profile_startup(profile_startup);
+    server.set_pattern_set(pattern_set);
 
-    if let Err(e) = server.run() {
-        eprintln!("❌ Server error: {}", e);
-        std::process::exit(1);
+    let outcome = server.run();
+    // Removed here, since exit() skips the server's destructors
+    workdir::discard(&fresh_workdir);
+    match outcome {
+        Ok(RunOutcome::Completed) => {}
+        Ok(RunOutcome::ErrorExit) => std::process::exit(1),
+        // Same as timeout(1), so CI logs tell a slow boot from a failure
+        Ok(RunOutcome::ReadyTimeout) => std::process::exit(124),
+        Err(e) => {
+            eprintln!("{} Server error: {}", theme::icon(Icon::Failure), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// What the signal handlers share to stop everything dev started
+#[derive(Clone)]
+struct Teardown {
+    child_pid: Arc<Mutex<Option<u32>>>,
+    force_kill: Arc<AtomicBool>,
+    background_pids: hooks::BackgroundPids,
+    stats: stats::SharedStats,
+    fresh_workdir: workdir::CurrentCopy,
+}
+
+impl Teardown {
+    /// Stop the server's process tree and the background pre-commands, and remove the
+    /// `fresh_workdir` copy. With a `trigger`, say so and print the session summary;
+    /// without one, stay silent.
+    fn stop_all(&self, trigger: Option<&str>) {
+        let pid = *self.child_pid.lock().unwrap();
+        if let Some(pid) = pid {
+            let force = self.force_kill.load(Ordering::SeqCst);
+            if let Some(trigger) = trigger {
+                if force {
+                    status!(
+                        "{} {}! Killing process tree (PID {})...",
+                        theme::icon(Icon::Stop),
+                        trigger,
+                        pid
+                    );
+                } else {
+                    status!(
+                        "{} {}! Asking process (PID {}) to exit...",
+                        theme::icon(Icon::Stop),
+                        trigger,
+                        pid
+                    );
+                }
+            }
+            // `sh -c` doesn't pass the signal on, so the tree is collected while the shell
+            // still holds it and signalled deepest first, leaving nothing to be reparented
+            #[cfg(not(windows))]
+            for descendant in process::descendant_pids(pid).into_iter().rev() {
+                process::signal_pid(descendant, force);
+            }
+            process::signal_pid(pid, force);
+        }
+        for pid in self.background_pids.lock().unwrap().iter() {
+            process::signal_pid(*pid, true);
+        }
+        workdir::discard(&self.fresh_workdir);
+        // try_lock: never hang the exit on a lock held by the interrupted thread
+        if trigger.is_some()
+            && let Ok(stats) = self.stats.try_lock()
+            && stats.attempts > 0
+        {
+            status!("{}", stats.summary());
+        }
+    }
+}
+
+/// Handle SIGHUP from a closed terminal or dropped SSH session: stop everything like
+/// Ctrl+C does, or with `nohup_mode`, keep running without the terminal
+#[cfg(unix)]
+fn watch_hangup(teardown: Teardown, nohup: Arc<AtomicBool>) {
+    let mut hangups = match signal_hook::iterator::Signals::new([signal_hook::consts::SIGHUP]) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            eprintln!(
+                "{} Could not register SIGHUP handler ({}); closing the terminal may leave the dev server running",
+                theme::icon(Icon::Warning),
+                e
+            );
+            return;
+        }
+    };
+    std::thread::spawn(move || {
+        for _ in hangups.forever() {
+            if hang_up(&teardown, &nohup) {
+                std::process::exit(129);
+            }
+        }
+    });
+}
+
+/// Handle one SIGHUP; returns whether dev should exit
+#[cfg(unix)]
+fn hang_up(teardown: &Teardown, nohup: &AtomicBool) -> bool {
+    if nohup.load(Ordering::SeqCst) {
+        // Writes to a hung-up terminal fail; `log_file` and `monitor_log` keep working
+        signals::detach_from_terminal();
+        return false;
+    }
+    // Nobody is left to read a message
+    teardown.stop_all(None);
+    true
+}
+
+/// Value of `--repeat <K>`; K must be a positive number
+fn parse_repeat(args: &[String]) -> Result<Option<u32>, String> {
+    let Some(pos) = args.iter().position(|arg| arg == "--repeat") else {
+        return Ok(None);
+    };
+    match args.get(pos + 1).map(|value| value.parse::<u32>()) {
+        Some(Ok(count)) if count > 0 => Ok(Some(count)),
+        _ => Err("--repeat expects a positive number of runs, e.g. --repeat 10".to_string()),
+    }
+}
+
+/// Value of `--profile <name>`
+fn parse_profile(args: &[String]) -> Result<Option<String>, String> {
+    let Some(pos) = args.iter().position(|arg| arg == "--profile") else {
+        return Ok(None);
+    };
+    match args.get(pos + 1) {
+        Some(name) if !name.starts_with('-') => Ok(Some(name.clone())),
+        _ => {
+            Err("--profile expects a profile name from dev-cli.json, e.g. --profile ci".to_string())
+        }
+    }
+}
+
+/// Value of `--config-path <path>`, or dev-cli.json in the current directory
+fn parse_config_path(args: &[String]) -> Result<PathBuf, String> {
+    let Some(pos) = args.iter().position(|arg| arg == "--config-path") else {
+        return Ok(PathBuf::from(cli_config::CONFIG_FILE));
+    };
+    match args.get(pos + 1) {
+        Some(path) if !path.starts_with('-') && !path.trim().is_empty() => Ok(PathBuf::from(path)),
+        _ => Err(
+            "--config-path expects a config file, e.g. --config-path dev-cli.staging.json"
+                .to_string(),
+        ),
+    }
+}
+
+/// Value of `--wrapper <command>`
+fn parse_wrapper(args: &[String]) -> Result<Option<String>, String> {
+    let Some(pos) = args.iter().position(|arg| arg == "--wrapper") else {
+        return Ok(None);
+    };
+    match args.get(pos + 1) {
+        Some(wrapper) if !wrapper.starts_with('-') && !wrapper.trim().is_empty() => {
+            Ok(Some(wrapper.clone()))
+        }
+        _ => Err("--wrapper expects a command, e.g. --wrapper \"time -v\"".to_string()),
+    }
+}
+
+/// Value of `--patterns <set>` or `--print-patterns <set>`, a built-in pattern set name
+fn parse_pattern_set(args: &[String], flag: &str) -> Result<Option<String>, String> {
+    let Some(pos) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    match args.get(pos + 1) {
+        Some(name) if patterns::PATTERN_SETS.contains(&name.as_str()) => Ok(Some(name.clone())),
+        _ => Err(format!(
+            "{} expects a pattern set: {}",
+            flag,
+            patterns::PATTERN_SETS.join(", ")
+        )),
+    }
+}
+
+/// Values of `--match-test [pattern] <logfile>`
+fn parse_match_test(args: &[String]) -> Result<Option<(Option<String>, String)>, String> {
+    let Some(pos) = args.iter().position(|arg| arg == "--match-test") else {
+        return Ok(None);
+    };
+    let values: Vec<&String> = args[pos + 1..]
+        .iter()
+        .take_while(|value| !value.starts_with("--"))
+        .take(2)
+        .collect();
+    match values.as_slice() {
+        [log_file] => Ok(Some((None, log_file.to_string()))),
+        [pattern, log_file] if !pattern.is_empty() => {
+            Ok(Some((Some(pattern.to_string()), log_file.to_string())))
+        }
+        _ => Err(
+            "--match-test expects a log file, optionally after a pattern, e.g. --match-test \"[Error\" crash.log"
+                .to_string(),
+        ),
+    }
+}
+
+/// Value of `--record <file>`
+fn parse_record(args: &[String]) -> Result<Option<String>, String> {
+    let Some(pos) = args.iter().position(|arg| arg == "--record") else {
+        return Ok(None);
+    };
+    match args.get(pos + 1) {
+        Some(path) if !path.starts_with('-') && !path.trim().is_empty() => Ok(Some(path.clone())),
+        _ => Err("--record expects a file, e.g. --record session.jsonl".to_string()),
     }
 }
 
@@ -81,6 +488,41 @@ fn print_help() {
     println!("OPTIONS:");
     println!("    --test      Run in test mode (simulates errors for testing)");
     println!("    --config    Create or update dev-cli.json configuration");
+    println!(
+        "    --yes, -y   Answer yes to prompts (e.g. falling back to the last known good command)"
+    );
+    println!("    --repeat K  Run the server exactly K times, then print how many runs succeeded");
+    println!(
+        "    --profile NAME  Merge the named profile from dev-cli.json over the base settings"
+    );
+    println!("    --config-path FILE  Use FILE instead of dev-cli.json, e.g. dev-cli.staging.json");
+    println!("    --wrapper CMD  Run the dev command under CMD, e.g. \"time -v\" or \"strace -f\"");
+    println!("    --record FILE  Write the session's output and events to FILE (JSON lines)");
+    println!(
+        "    --watch-command  Rerun the command to completion on every file change (mode \"command\")"
+    );
+    println!(
+        "    --raw       Print the server's output byte for byte; dev's own lines go to stderr"
+    );
+    println!("    --fail-fast Stop at the first error pattern or crash and exit non-zero");
+    println!("    --no-delay  Start right away, skipping initial_delay_secs");
+    println!("    --wait-start  Show the startup info, then wait for Enter before the first start");
+    println!(
+        "    --profile-startup  Once ready, list the longest pauses in the boot output with the lines around them"
+    );
+    println!(
+        "    --match-test [PATTERN] FILE  List the lines of a saved log that PATTERN (or the config) matches"
+    );
+    println!("    --json      With --match-test, print the matches as JSON");
+    println!(
+        "    --patterns SET  Add a built-in set of regex error patterns (universal), like pattern_set"
+    );
+    println!("    --print-patterns SET  Print a built-in pattern set as a regex_patterns_file");
+    println!("    --schema    Print a JSON Schema of dev-cli.json for editor autocomplete");
+    println!("    --cleanup   Kill server processes left running by a dev session that died");
+    println!(
+        "    --health    Exit 0 if the running session's server is up and was ready, 1 if not"
+    );
     println!("    --help, -h  Show this help message");
     println!();
     println!("DESCRIPTION:");
@@ -100,29 +542,211 @@ fn print_help() {
     println!("    dev                    # Start monitoring (creates config if needed)");
     println!("    dev --test             # Test the error detection in test mode");
     println!("    dev --config           # Reconfigure the run command and error pattern");
+    println!("    dev --test --repeat 5  # Run test mode five times and tally the results");
+    println!(
+        "    dev --fail-fast        # CI smoke test: fail on the first error instead of restarting"
+    );
+    println!(
+        "    dev --wrapper \"time -v\" # Report the server's time and memory use when it exits"
+    );
+    println!("    dev --record bug.jsonl # Capture a session to attach to a bug report");
+    println!(
+        "    dev --match-test \"[Error\" crash.log  # Tune a pattern against a saved crash log"
+    );
+    println!("    dev --raw | jq .       # Pipe the server's JSON logs into another tool");
+    println!("    dev --patterns universal  # Catch common error markers of any framework");
+    println!("    dev --no-delay         # The tunnel is already up; don't wait before starting");
+    println!("    dev --wait-start       # Demo: explain the command, then press Enter to launch");
+    println!("    dev --profile-startup  # Find the step a slow boot waits on");
+    println!("    dev --watch-command    # Rerun run_command (e.g. cargo test) on every save");
+    println!("    dev --profile ci       # Use the 'ci' profile (or set DEV_PROFILE=ci)");
+    println!("    dev --config-path dev-cli.staging.json  # Keep a config file per environment");
+    println!("    dev --schema > dev-cli.schema.json  # Autocomplete dev-cli.json in your editor");
+    println!(
+        "    dev --cleanup          # Kill servers orphaned by a crashed dev (needs state_file)"
+    );
+    println!(
+        "    dev --health           # Container HEALTHCHECK (needs ready_file and state_file)"
+    );
 }
 
-fn create_config_interactive() -> std::result::Result<(), Box<dyn std::error::Error>> {
+fn create_config_interactive(
+    config_path: &Path,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
     use crate::cli_config::CliConfig;
     use std::fs;
 
     // Remove existing config if it exists
-    if std::path::Path::new("dev-cli.json").exists() {
-        fs::remove_file("dev-cli.json")?;
-        println!("📄 Removed existing dev-cli.json");
+    if config_path.exists() {
+        fs::remove_file(config_path)?;
+        println!(
+            "{} Removed existing {}",
+            theme::icon(Icon::Config),
+            config_path.display()
+        );
     }
 
     // Create new config
-    let _config = CliConfig::load_or_create()?;
-    println!("✅ Configuration complete! You can now run 'dev' to start monitoring.");
+    let _config = CliConfig::load_or_create(config_path, None)?;
+    println!(
+        "{} Configuration complete! You can now run 'dev' to start monitoring.",
+        theme::icon(Icon::Success)
+    );
 
     Ok(())
 }
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_placeholder() {
         // Placeholder: integration tests can be added here
-        assert!(true);
+    }
+
+    #[test]
+    fn test_parse_repeat() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_repeat(&args(&["dev"])), Ok(None));
+        assert_eq!(
+            parse_repeat(&args(&["dev", "--repeat", "10"])),
+            Ok(Some(10))
+        );
+        assert!(parse_repeat(&args(&["dev", "--repeat"])).is_err());
+        assert!(parse_repeat(&args(&["dev", "--repeat", "0"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_profile(&args(&["dev"])), Ok(None));
+        assert_eq!(
+            parse_profile(&args(&["dev", "--profile", "ci", "--yes"])),
+            Ok(Some("ci".to_string()))
+        );
+        assert!(parse_profile(&args(&["dev", "--profile"])).is_err());
+        assert!(parse_profile(&args(&["dev", "--profile", "--yes"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_config_path() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_config_path(&args(&["dev", "--config"])),
+            Ok(PathBuf::from("dev-cli.json"))
+        );
+        assert_eq!(
+            parse_config_path(&args(&["dev", "--config-path", "dev-cli.staging.json"])),
+            Ok(PathBuf::from("dev-cli.staging.json"))
+        );
+        assert!(parse_config_path(&args(&["dev", "--config-path"])).is_err());
+        assert!(parse_config_path(&args(&["dev", "--config-path", "--yes"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_pattern_set() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_pattern_set(&args(&["dev"]), "--patterns"), Ok(None));
+        assert_eq!(
+            parse_pattern_set(&args(&["dev", "--patterns", "universal"]), "--patterns"),
+            Ok(Some("universal".to_string()))
+        );
+        assert!(parse_pattern_set(&args(&["dev", "--patterns", "rails"]), "--patterns").is_err());
+        assert!(
+            parse_pattern_set(&args(&["dev", "--print-patterns"]), "--print-patterns").is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_wrapper() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_wrapper(&args(&["dev"])), Ok(None));
+        assert_eq!(
+            parse_wrapper(&args(&["dev", "--wrapper", "strace -f", "--yes"])),
+            Ok(Some("strace -f".to_string()))
+        );
+        assert!(parse_wrapper(&args(&["dev", "--wrapper"])).is_err());
+        assert!(parse_wrapper(&args(&["dev", "--wrapper", "--yes"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_record(&args(&["dev"])), Ok(None));
+        assert_eq!(
+            parse_record(&args(&["dev", "--record", "bug.jsonl"])),
+            Ok(Some("bug.jsonl".to_string()))
+        );
+        assert!(parse_record(&args(&["dev", "--record"])).is_err());
+        assert!(parse_record(&args(&["dev", "--record", "--yes"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_match_test() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_match_test(&args(&["dev"])), Ok(None));
+        assert_eq!(
+            parse_match_test(&args(&["dev", "--match-test", "crash.log", "--json"])),
+            Ok(Some((None, "crash.log".to_string())))
+        );
+        assert_eq!(
+            parse_match_test(&args(&["dev", "--match-test", "[Error", "crash.log"])),
+            Ok(Some((Some("[Error".to_string()), "crash.log".to_string())))
+        );
+        assert!(parse_match_test(&args(&["dev", "--match-test"])).is_err());
+        assert!(parse_match_test(&args(&["dev", "--match-test", "--json"])).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hangup_kills_the_whole_server_tree() {
+        // A zombie waiting for its new parent to reap it is gone as far as the server goes
+        let running = |pid: u32| {
+            std::process::Command::new("ps")
+                .args(["-o", "stat=", "-p", &pid.to_string()])
+                .output()
+                .map(|output| {
+                    let stat = String::from_utf8_lossy(&output.stdout);
+                    !stat.trim().is_empty() && !stat.trim().starts_with('Z')
+                })
+                .unwrap_or(false)
+        };
+
+        let mut server = std::process::Command::new("sh")
+            .args(["-c", "sleep 60 & wait"])
+            .spawn()
+            .unwrap();
+        let mut grandchildren = Vec::new();
+        for _ in 0..50 {
+            grandchildren = process::descendant_pids(server.id());
+            if !grandchildren.is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let grandchild = grandchildren[0];
+
+        let teardown = Teardown {
+            child_pid: Arc::new(Mutex::new(Some(server.id()))),
+            force_kill: Arc::new(AtomicBool::new(true)),
+            background_pids: Arc::new(Mutex::new(Vec::new())),
+            stats: stats::SharedStats::default(),
+            fresh_workdir: workdir::CurrentCopy::default(),
+        };
+        // What the SIGHUP handler does without nohup_mode
+        assert!(hang_up(&teardown, &AtomicBool::new(false)));
+
+        server.wait().unwrap();
+        for _ in 0..50 {
+            if !running(grandchild) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert!(
+            !running(grandchild),
+            "grandchild {} survived SIGHUP",
+            grandchild
+        );
     }
 }
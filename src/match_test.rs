@@ -0,0 +1,142 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde_json::json;
+
+use crate::cli_config::CliConfig;
+use crate::config::Config;
+use crate::error::{Result, ServerError};
+use crate::monitor::{self, OutputMatch};
+use crate::theme::{self, Icon};
+
+/// Report the lines of a saved log that would trigger the monitor (`dev --match-test`).
+/// With a `pattern`, only that plain pattern is tried; without one, the patterns and
+/// matching options from the config file are. Returns whether anything matched.
+pub fn run(
+    pattern: Option<&str>,
+    log_file: &Path,
+    json: bool,
+    config_path: &Path,
+    profile: Option<&str>,
+) -> Result<bool> {
+    let config = match pattern {
+        Some(pattern) => Config::new().with_error_patterns(vec![pattern.to_string()]),
+        None => {
+            let cli_config = CliConfig::read_for_session(config_path, profile)?;
+            cli_config.validate()?;
+            Config::new().with_cli_config(&cli_config)?
+        }
+    };
+    let file = File::open(log_file).map_err(|e| {
+        ServerError::IoError(format!("Failed to read {}: {}", log_file.display(), e))
+    })?;
+    let matches = monitor::scan_output(&config, BufReader::new(file))?;
+
+    if json {
+        println!("{}", to_json(log_file, &matches));
+    } else {
+        for found in &matches {
+            println!("{}", format_match(found));
+        }
+        println!("{}", summary(&matches));
+    }
+    Ok(!matches.is_empty())
+}
+
+/// `  42: <line>  ← '<pattern>'`, with the further lines of a block indented below
+fn format_match(found: &OutputMatch) -> String {
+    let mut lines = found.error.line.lines();
+    let first = lines.next().unwrap_or_default();
+    let kind = if found.stall { "stall " } else { "" };
+    let mut text = format!(
+        "{:>6}: {}  ← {}'{}'",
+        found.line_number, first, kind, found.error.pattern
+    );
+    for line in lines {
+        text.push_str(&format!("\n        {}", line));
+    }
+    text
+}
+
+fn summary(matches: &[OutputMatch]) -> String {
+    let stalls = matches.iter().filter(|found| found.stall).count();
+    let errors = matches.len() - stalls;
+    let mut text = format!("{} {} error match(es)", theme::icon(Icon::Patterns), errors);
+    if stalls > 0 {
+        text.push_str(&format!(", {} stall match(es)", stalls));
+    }
+    text
+}
+
+fn to_json(log_file: &Path, matches: &[OutputMatch]) -> serde_json::Value {
+    let entries: Vec<serde_json::Value> = matches
+        .iter()
+        .map(|found| {
+            json!({
+                "line": found.line_number,
+                "kind": if found.stall { "stall" } else { "error" },
+                "pattern": found.error.pattern,
+                "text": found.error.line,
+                "hits": found.error.hits,
+                "captures": found.error.captures,
+            })
+        })
+        .collect();
+    json!({
+        "file": log_file.display().to_string(),
+        "total": matches.len(),
+        "matches": entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::BlockDelimiters;
+    use std::io::Cursor;
+
+    const CRASH_LOG: &str = "\
+ready on :3000
+GET /users 200
+TypeError: Cannot read properties of undefined (reading 'id')
+    at getUser (/app/src/users.js:14:22)
+
+[Error] pool exhausted
+";
+
+    #[test]
+    fn test_scan_reports_numbered_matches_like_the_monitor() {
+        let config =
+            Config::new().with_error_patterns(vec!["[Error".to_string(), "users.js".to_string()]);
+        let matches = monitor::scan_output(&config, Cursor::new(CRASH_LOG)).unwrap();
+        let found: Vec<(usize, &str)> = matches
+            .iter()
+            .map(|found| (found.line_number, found.error.pattern.as_str()))
+            .collect();
+        assert_eq!(found, [(4, "users.js"), (6, "[Error")]);
+        assert_eq!(
+            format_match(&matches[1]),
+            "     6: [Error] pool exhausted  ← '[Error'"
+        );
+
+        // With blocks, the trace is one match numbered from its first line
+        let config = Config {
+            blocks: Some(BlockDelimiters {
+                start: "Error:".to_string(),
+                end: None,
+                max_lines: 200,
+            }),
+            ..config
+        };
+        let matches = monitor::scan_output(&config, Cursor::new(CRASH_LOG)).unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line_number, 3);
+        assert_eq!(matches[0].error.line.lines().count(), 2);
+
+        let json = to_json(Path::new("crash.log"), &matches);
+        assert_eq!(json["total"], 2);
+        assert_eq!(json["matches"][1]["line"], 6);
+        assert_eq!(json["matches"][1]["kind"], "error");
+    }
+}
@@ -1,91 +1,934 @@
-use std::io::{BufRead, BufReader};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::blocks::BlockCollector;
+use crate::cli_config::{CONFIG_FILE, CliConfig};
+use crate::color::{self, Color};
+use crate::conditions::{self, Observation, RuleMatcher};
 use crate::config::Config;
 use crate::error::{Result, ServerError};
-use crate::process::ProcessManager;
+use crate::events::{EventEmitter, EventKind, LifecycleEvent};
+use crate::exit_rules::{self, ExitAction};
+use crate::exit_status;
+use crate::hooks::TickHook;
+use crate::levels::LevelColorizer;
+use crate::patterns;
+use crate::process::{ChildState, ProcessManager, ShutdownOutcome};
+use crate::ready::ReadyAnnouncer;
+use crate::record::Recorder;
+use crate::reload::{self, ConfigReload};
+use crate::remote::SSH_CONNECTION_LOST;
+use crate::resources;
+#[cfg(unix)]
+use crate::signals;
+use crate::startup_profile::StartupProfile;
+use crate::state::StateStore;
+use crate::stats::{BootTiming, SharedStats};
+use crate::status::{ChildLog, status};
+use crate::theme::{self, Icon};
+
+/// Shell exit codes for "command not executable" and "command not found"
+const SHELL_START_FAILURE_CODES: [i32; 2] = [126, 127];
+
+/// Output reader buffer size when `read_buffer_size` isn't set (same as `BufReader::new`)
+pub const DEFAULT_READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Largest accepted `read_buffer_size`; each output stream gets its own buffer
+pub const MAX_READ_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+/// How long a live server may keep running after closing its output before `restart_on_eof`
+/// acts, so a server that is exiting anyway is reaped and judged by its exit status
+const EOF_GRACE: Duration = Duration::from_secs(1);
+
+/// Output lines kept for the report when `ready_timeout_secs` runs out
+const READY_TIMEOUT_TAIL_LINES: usize = 10;
 
 /// Messages passed between monitoring threads and the main loop
 #[derive(Debug)]
 pub enum WatchMessage {
-    ErrorDetected,
+    /// The first line of output arrived (only sent when `first_output_timeout` is set)
+    FirstOutput,
+    /// A stream reached end of file (only sent when `restart_on_eof` is set)
+    OutputClosed,
+    ErrorDetected(ErrorMatch),
+    /// A `stall_patterns` entry was seen
+    StallDetected(ErrorMatch),
+    ReadyDetected,
+    /// A line containing a `restart_when` pattern, for the monitor to evaluate the rules
+    RuleLine(String),
     IoError(String),
+    /// A memory/CPU limit stayed exceeded for the configured window
+    #[cfg_attr(not(feature = "resource-limits"), allow(dead_code))]
+    ResourceLimitExceeded(String),
+}
+
+/// The error pattern that matched and the line it matched in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorMatch {
+    pub pattern: String,
+    pub line: String,
+    /// Named groups captured by a regex pattern (empty for plain patterns)
+    pub captures: BTreeMap<String, String>,
+    /// How much the line adds toward `error_threshold` (see `count_per_line`)
+    pub hits: u32,
+}
+
+/// How much a line with several error pattern matches adds toward `error_threshold`
+/// (`count_per_line` in dev-cli.json)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountPerLine {
+    /// One, however many times the patterns occur in it
+    #[default]
+    Once,
+    /// One per pattern occurrence; overlapping occurrences count once
+    All,
+}
+
+impl CountPerLine {
+    pub fn is_once(&self) -> bool {
+        *self == CountPerLine::Once
+    }
+}
+
+/// Why an attempt ended in a restart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestartReason {
+    /// An error pattern was seen in the output
+    ErrorDetected(ErrorMatch),
+    /// A stall pattern was seen in the output
+    Stalled(ErrorMatch),
+    /// Reading the server output failed
+    ReaderError,
+    /// The server exited unsuccessfully (exit code when available; 128 + the signal number
+    /// when it was killed by one)
+    ExitFailure(Option<i32>),
+    /// The server exited cleanly, but `exit_code_rules` restarts on its code; not a crash
+    ExitRule(i32),
+    /// A configuration change needs a fresh server process
+    ConfigChanged,
+    /// The server stayed above a memory/CPU limit
+    ResourceLimit,
+    /// The server closed its output but its process kept running
+    OutputClosed,
+    /// `ready_timeout_secs` passed without the server becoming ready; ends the session
+    ReadyTimeout(Duration),
+    /// A `restart_when` rule was met (its description)
+    ConditionMet(String),
+}
+
+impl fmt::Display for RestartReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestartReason::ErrorDetected(error) => {
+                write!(f, "error pattern '{}' detected", error.pattern)
+            }
+            RestartReason::Stalled(stall) => {
+                write!(f, "stall pattern '{}' detected", stall.pattern)
+            }
+            RestartReason::ReaderError => write!(f, "failed to read server output"),
+            RestartReason::ExitFailure(Some(code)) => write!(f, "exited with code {}", code),
+            RestartReason::ExitFailure(None) => write!(f, "exited unsuccessfully"),
+            RestartReason::ExitRule(code) => {
+                write!(
+                    f,
+                    "exited with code {}, which exit_code_rules restarts on",
+                    code
+                )
+            }
+            RestartReason::ConfigChanged => write!(f, "configuration changed"),
+            RestartReason::ResourceLimit => write!(f, "resource limit exceeded"),
+            RestartReason::OutputClosed => write!(f, "output closed while the process was alive"),
+            RestartReason::ReadyTimeout(timeout) => {
+                write!(f, "not ready within {}s", timeout.as_secs())
+            }
+            RestartReason::ConditionMet(rule) => write!(f, "restart_when rule met: {}", rule),
+        }
+    }
+}
+
+impl RestartReason {
+    /// `DEV_RESTART_REASON` for `on_restart`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RestartReason::ErrorDetected(_) => "error",
+            RestartReason::Stalled(_) => "stall",
+            RestartReason::ReaderError => "reader_error",
+            RestartReason::ExitFailure(_) => "exit",
+            RestartReason::ExitRule(_) => "exit_rule",
+            RestartReason::ConfigChanged => "config_change",
+            RestartReason::ResourceLimit => "resource_limit",
+            RestartReason::OutputClosed => "output_closed",
+            RestartReason::ReadyTimeout(_) => "ready_timeout",
+            RestartReason::ConditionMet(_) => "restart_when",
+        }
+    }
+
+    /// `DEV_RESTART_DETAIL` for `on_restart`: the pattern, exit code or rule, if any
+    pub fn detail(&self) -> String {
+        match self {
+            RestartReason::ErrorDetected(error) | RestartReason::Stalled(error) => {
+                error.pattern.clone()
+            }
+            RestartReason::ExitFailure(Some(code)) | RestartReason::ExitRule(code) => {
+                code.to_string()
+            }
+            RestartReason::ConditionMet(rule) => rule.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Patterns the reader threads match against; swapped in place when the config reloads
+#[derive(Debug, Clone, Default)]
+struct LivePatterns {
+    error_patterns: Vec<String>,
+    regex_patterns: Vec<Regex>,
+    stall_patterns: Vec<String>,
+    ready_pattern: Option<String>,
+    rule_patterns: Vec<String>,
+    mute_patterns: Vec<String>,
+}
+
+impl LivePatterns {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            error_patterns: config.error_patterns.clone(),
+            regex_patterns: compile_regexes(&config.regex_patterns),
+            stall_patterns: config.stall_patterns.clone(),
+            ready_pattern: config.ready_pattern.clone(),
+            rule_patterns: conditions::rule_patterns(&config.restart_when),
+            mute_patterns: config.mute_patterns.clone(),
+        }
+    }
+
+    /// Like `find_error`, with every match counted when `count_per_line` is "all". Only
+    /// scanning for every occurrence when asked keeps long lines cheap.
+    fn find_error_counted(&self, line: &str, count_all: bool) -> Option<ErrorMatch> {
+        let mut error = self.find_error(line)?;
+        if count_all {
+            let spans = match_spans(line, &self.error_patterns, &self.regex_patterns);
+            error.hits = spans.len().max(1) as u32;
+        }
+        Some(error)
+    }
+
+    /// The error, else the stall, in a line or block
+    fn detect(&self, text: &str, count_all: bool) -> Option<WatchMessage> {
+        match self.find_error_counted(text, count_all) {
+            Some(error) => Some(WatchMessage::ErrorDetected(error)),
+            None => self.find_stall(text).map(WatchMessage::StallDetected),
+        }
+    }
+
+    /// The first plain pattern found in the line, else the first matching regex with its captures
+    fn find_error(&self, line: &str) -> Option<ErrorMatch> {
+        if let Some(pattern) = matching_pattern(line, &self.error_patterns) {
+            return Some(ErrorMatch {
+                pattern: pattern.to_string(),
+                line: line.to_string(),
+                captures: BTreeMap::new(),
+                hits: 1,
+            });
+        }
+        self.regex_patterns.iter().find_map(|regex| {
+            patterns::named_captures(regex, line).map(|captures| ErrorMatch {
+                pattern: regex.as_str().to_string(),
+                line: line.to_string(),
+                captures,
+                hits: 1,
+            })
+        })
+    }
+}
+
+impl LivePatterns {
+    /// Whether the line is left out of the displayed output (`mute_patterns`)
+    fn is_muted(&self, line: &str) -> bool {
+        matching_pattern(line, &self.mute_patterns).is_some()
+    }
+
+    fn find_stall(&self, line: &str) -> Option<ErrorMatch> {
+        matching_pattern(line, &self.stall_patterns).map(|pattern| ErrorMatch {
+            pattern: pattern.to_string(),
+            line: line.to_string(),
+            captures: BTreeMap::new(),
+            hits: 1,
+        })
+    }
+}
+
+/// Compile regex patterns that were already validated with the config; a pattern that
+/// still fails is reported and skipped
+fn compile_regexes(sources: &[String]) -> Vec<Regex> {
+    sources
+        .iter()
+        .filter_map(|source| match patterns::compile_regex(source) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                eprintln!("{} Skipping pattern: {}", theme::icon(Icon::Warning), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Which child output stream a reader thread is watching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    fn name(self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+
+    fn color_enabled(self) -> bool {
+        match self {
+            OutputStream::Stdout => color::stdout_enabled(),
+            OutputStream::Stderr => color::stderr_enabled(),
+        }
+    }
+
+    fn print(self, line: &str) {
+        match self {
+            OutputStream::Stdout => println!("{} {}", theme::icon(Icon::Stdout), line),
+            OutputStream::Stderr => eprintln!("{} {}", theme::icon(Icon::Stderr), line),
+        }
+    }
+
+    /// Pass a chunk through exactly as the server wrote it (`--raw`)
+    fn write_raw(self, chunk: &[u8]) {
+        // Nothing useful to do when dev's own output is gone; the reader keeps matching
+        let _ = match self {
+            OutputStream::Stdout => write_flushed(&mut io::stdout().lock(), chunk),
+            OutputStream::Stderr => write_flushed(&mut io::stderr().lock(), chunk),
+        };
+    }
+}
+
+/// How a reader shows the lines of one stream: colors, match highlighting and the tag of
+/// the attempt that printed them
+#[derive(Debug, Clone)]
+struct LineFormat {
+    level_colorizer: Option<LevelColorizer>,
+    highlight_match: bool,
+    paint_red: bool,
+    color_enabled: bool,
+    attempt_tag: Option<u32>,
+}
+
+impl LineFormat {
+    /// `line` as printed; `matched` when it matched an error pattern
+    fn display(&self, line: &str, matched: bool, patterns: &LivePatterns) -> String {
+        let display = if matched && self.highlight_match && self.color_enabled {
+            highlight_matches(line, &patterns.error_patterns, &patterns.regex_patterns)
+        } else {
+            match &self.level_colorizer {
+                Some(levels) => levels.colorize(line, self.color_enabled),
+                None if self.paint_red && self.color_enabled => color::paint(line, Color::Red),
+                None => line.to_string(),
+            }
+        };
+        tag_line(self.attempt_tag, display)
+    }
+}
+
+/// `#3 | <line>` for a line printed by attempt 3
+fn tag_line(attempt: Option<u32>, line: String) -> String {
+    match attempt {
+        Some(attempt) => format!("#{} | {}", attempt, line),
+        None => line,
+    }
+}
+
+fn write_flushed(out: &mut impl Write, chunk: &[u8]) -> io::Result<()> {
+    out.write_all(chunk)?;
+    out.flush()
+}
+
+/// What the monitor knows about the current run of the server
+#[derive(Debug, Clone, Default)]
+pub struct AttemptInfo {
+    pub number: u32,
+    /// Remembered as last known good if the server reaches readiness
+    pub run_command: Option<String>,
+    /// Crash restarts so far in this session
+    pub restarts: u32,
+}
+
+/// A line, or block of lines, of saved output that would trigger the monitor
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputMatch {
+    /// 1-based number of the (first) line
+    pub line_number: usize,
+    /// Matched a `stall_patterns` entry rather than an error pattern
+    pub stall: bool,
+    pub error: ErrorMatch,
+}
+
+/// Run saved output through the same reading and matching as a live server's output, for
+/// `dev --match-test`. Error thresholds and restarts don't apply; every match is returned.
+pub fn scan_output(config: &Config, mut reader: impl BufRead) -> Result<Vec<OutputMatch>> {
+    let patterns = LivePatterns::from_config(config);
+    let count_all = !config.count_per_line.is_once();
+    let mut blocks = config.blocks.clone().map(BlockCollector::new);
+    let mut block_start = None;
+    let mut matches = Vec::new();
+    let mut found = |line_number, message| match message {
+        Some(WatchMessage::ErrorDetected(error)) => matches.push(OutputMatch {
+            line_number,
+            stall: false,
+            error,
+        }),
+        Some(WatchMessage::StallDetected(error)) => matches.push(OutputMatch {
+            line_number,
+            stall: true,
+            error,
+        }),
+        _ => {}
+    };
+
+    let mut buf = Vec::new();
+    let mut line_number = 0;
+    while let Some(line) = read_chunk(&mut reader, config.delimiter, &mut buf)
+        .map_err(|e| ServerError::IoError(e.to_string()))?
+    {
+        line_number += 1;
+        let Some(collector) = &mut blocks else {
+            found(line_number, patterns.detect(&line, count_all));
+            continue;
+        };
+        let fed = collector.feed(&line);
+        if !fed.in_block {
+            found(line_number, patterns.detect(&line, count_all));
+            continue;
+        }
+        if let Some(block) = fed.completed {
+            let first = block_start.take().unwrap_or(line_number);
+            found(first, patterns.detect(&block, count_all));
+        }
+        if collector.in_progress() {
+            block_start.get_or_insert(line_number);
+        }
+    }
+    if let Some(block) = blocks.as_mut().and_then(BlockCollector::finish) {
+        let first = block_start.unwrap_or(line_number);
+        found(first, patterns.detect(&block, count_all));
+    }
+    Ok(matches)
+}
+
+/// Turn a child pipe that wasn't captured into an error instead of a panic
+fn warn_still_running(process: &ProcessManager) {
+    eprintln!(
+        "{} PID {} did not exit; the next attempt may find its port still in use",
+        theme::icon(Icon::Warning),
+        process.id()
+    );
+}
+
+fn capture_stream<R>(stream: Option<R>, output: OutputStream) -> Result<R> {
+    stream.ok_or_else(|| {
+        ServerError::ProcessManagement(format!("Failed to capture {}", output.name()))
+    })
+}
+
+/// Read the next chunk up to `delimiter`, without the delimiter. With the default `\n`
+/// a trailing `\r` is dropped too, like `BufRead::lines`. Invalid UTF-8 is replaced
+/// rather than treated as a read error, so binary output doesn't stop the reader.
+/// `buf` is left holding the chunk exactly as read, delimiter included.
+fn read_chunk<R: BufRead>(
+    reader: &mut R,
+    delimiter: u8,
+    buf: &mut Vec<u8>,
+) -> io::Result<Option<String>> {
+    buf.clear();
+    if reader.read_until(delimiter, buf)? == 0 {
+        return Ok(None);
+    }
+    let mut line: &[u8] = buf;
+    if let Some(rest) = line.strip_suffix(&[delimiter]) {
+        line = rest;
+        if delimiter == b'\n' {
+            line = line.strip_suffix(b"\r").unwrap_or(line);
+        }
+    }
+    Ok(Some(String::from_utf8_lossy(line).into_owned()))
+}
+
+/// The first error pattern found in the line
+fn matching_pattern<'a>(line: &str, patterns: &'a [String]) -> Option<&'a str> {
+    patterns
+        .iter()
+        .map(String::as_str)
+        .find(|pattern| line.contains(pattern))
+}
+
+/// Byte ranges of every pattern occurrence in the line, sorted and with overlaps merged
+fn match_spans(line: &str, patterns: &[String], regexes: &[Regex]) -> Vec<(usize, usize)> {
+    let mut spans: Vec<(usize, usize)> = patterns
+        .iter()
+        .filter(|pattern| !pattern.is_empty())
+        .flat_map(|pattern| {
+            line.match_indices(pattern.as_str())
+                .map(|(start, matched)| (start, start + matched.len()))
+        })
+        .chain(
+            regexes
+                .iter()
+                .flat_map(|regex| regex.find_iter(line).map(|m| (m.start(), m.end()))),
+        )
+        .filter(|(start, end)| start < end)
+        .collect();
+    spans.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// The line with every error pattern match highlighted
+fn highlight_matches(line: &str, patterns: &[String], regexes: &[Regex]) -> String {
+    let mut highlighted = String::with_capacity(line.len());
+    let mut pos = 0;
+    for (start, end) in match_spans(line, patterns, regexes) {
+        highlighted.push_str(&line[pos..start]);
+        highlighted.push_str(&color::highlight(&line[start..end]));
+        pos = end;
+    }
+    highlighted.push_str(&line[pos..]);
+    highlighted
+}
+
+/// Counts error matches toward `error_threshold`, optionally within a sliding window
+#[derive(Debug)]
+struct ErrorCounter {
+    threshold: u32,
+    window: Option<Duration>,
+    hits: VecDeque<Instant>,
+}
+
+impl ErrorCounter {
+    fn new(threshold: u32, window: Option<Duration>) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            window,
+            hits: VecDeque::new(),
+        }
+    }
+
+    /// Record `hits` matches seen at once; returns how many matches currently count
+    /// toward the threshold
+    fn record(&mut self, now: Instant, hits: u32) -> u32 {
+        self.hits
+            .extend(std::iter::repeat_n(now, hits.max(1) as usize));
+        if let Some(window) = self.window {
+            while let Some(first) = self.hits.front()
+                && now.duration_since(*first) > window
+            {
+                self.hits.pop_front();
+            }
+        }
+        self.hits.len() as u32
+    }
 }
 
 /// Monitors a process for error patterns and manages its lifecycle
 pub struct ProcessMonitor {
     config: Config,
+    patterns: Arc<RwLock<LivePatterns>>,
+    ready: ReadyAnnouncer,
+    state: Option<StateStore>,
+    events: EventEmitter,
+    reloads: Option<Receiver<ConfigReload>>,
+    /// The file the reloads come from, named when they are applied
+    config_path: PathBuf,
+    reloaded_config: RefCell<Option<CliConfig>>,
+    /// The last attempt ended in a restart for a config change, so the next one is a rebuild
+    rebuilding: Cell<bool>,
+    /// When each pattern with a `cooldown_secs` last triggered a restart
+    cooldowns: RefCell<HashMap<String, Instant>>,
+    /// Number of the attempt being watched, for `attempt_prefix`
+    attempt: Cell<u32>,
+    /// When the current attempt printed its first line, set by whichever reader sees it first
+    first_output: Arc<Mutex<Option<Instant>>>,
+    /// When the first attempt started, for `ready_timeout` and `ready_warn_after`
+    ready_clock: Cell<Option<Instant>>,
+    /// The server has been ready since the clock started
+    ready_seen: Cell<bool>,
+    /// When the current attempt became ready, for the restart backoff's stability window
+    attempt_ready_at: Cell<Option<Instant>>,
+    /// The `ready_warn_after` warning was printed since the clock started
+    ready_warned: Cell<bool>,
+    /// The last lines of output, kept only with a `ready_timeout` to show why it ran out
+    recent_output: Arc<Mutex<VecDeque<String>>>,
+    /// Record when every line arrives until the server is ready (`--profile-startup`)
+    profile_startup: bool,
+    /// The current attempt's boot so far; taken when the server becomes ready
+    startup_profile: Arc<Mutex<Option<StartupProfile>>>,
+    stop_when_ready: bool,
+    stats: Option<SharedStats>,
+    child_log: Option<Arc<ChildLog>>,
+    recorder: Recorder,
+    raw_output: bool,
 }
 
 impl ProcessMonitor {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        let patterns = Arc::new(RwLock::new(LivePatterns::from_config(&config)));
+        let ready = ReadyAnnouncer::new(&config);
+        let state = config.state_file.clone().map(StateStore::new);
+        Self {
+            config,
+            patterns,
+            ready,
+            state,
+            events: EventEmitter::default(),
+            reloads: None,
+            config_path: PathBuf::from(CONFIG_FILE),
+            reloaded_config: RefCell::new(None),
+            rebuilding: Cell::new(false),
+            cooldowns: RefCell::new(HashMap::new()),
+            attempt: Cell::new(0),
+            first_output: Arc::new(Mutex::new(None)),
+            ready_clock: Cell::new(None),
+            ready_seen: Cell::new(false),
+            attempt_ready_at: Cell::new(None),
+            ready_warned: Cell::new(false),
+            recent_output: Arc::new(Mutex::new(VecDeque::new())),
+            profile_startup: false,
+            startup_profile: Arc::new(Mutex::new(None)),
+            stop_when_ready: false,
+            stats: None,
+            child_log: None,
+            recorder: Recorder::default(),
+            raw_output: false,
+        }
+    }
+
+    pub fn with_events(mut self, events: EventEmitter) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Apply configuration changes from the config watcher while the server runs
+    pub fn with_config_reloads(
+        mut self,
+        config_path: PathBuf,
+        reloads: Receiver<ConfigReload>,
+    ) -> Self {
+        self.config_path = config_path;
+        self.reloads = Some(reloads);
+        self
+    }
+
+    /// End the attempt successfully as soon as the server reports ready (`--repeat`)
+    pub fn with_stop_when_ready(mut self, stop_when_ready: bool) -> Self {
+        self.stop_when_ready = stop_when_ready;
+        self
+    }
+
+    /// Count every error pattern match in the shared session stats
+    pub fn with_stats(mut self, stats: SharedStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Also write every output line, undecorated, to `log_file`
+    pub fn with_child_log(mut self, child_log: Arc<ChildLog>) -> Self {
+        self.child_log = Some(child_log);
+        self
+    }
+
+    /// Also add every output line to the `--record` file
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    /// Pass the server's output through byte for byte, without icons or colors (`--raw`).
+    /// The ready marker isn't printed, since it would be mixed into the server's stdout.
+    pub fn with_raw_output(mut self, raw_output: bool) -> Self {
+        self.raw_output = raw_output;
+        self.ready.set_print_marker(!raw_output);
+        self
+    }
+
+    /// Print where each boot spent its time once the server is ready (`--profile-startup`)
+    pub fn with_profile_startup(mut self, profile_startup: bool) -> Self {
+        self.profile_startup = profile_startup;
+        self
+    }
+
+    /// Start the `ready_timeout` over, for a fresh launch (each `--repeat` run)
+    pub fn restart_ready_clock(&self) {
+        self.ready_clock.set(None);
+        self.ready_seen.set(false);
+        self.ready_warned.set(false);
+        self.recent_output.lock().unwrap().clear();
+    }
+
+    /// Replace the runtime configuration between attempts
+    pub fn update_config(&mut self, config: Config) {
+        *self.patterns.write().unwrap() = LivePatterns::from_config(&config);
+        self.state = config.state_file.clone().map(StateStore::new);
+        self.config = config;
+    }
+
+    /// The newest configuration picked up by the watcher since the last call
+    pub fn take_reloaded_config(&self) -> Option<CliConfig> {
+        self.reloaded_config.borrow_mut().take()
+    }
+
+    /// When the last watched attempt became ready, if it did
+    pub fn attempt_ready_at(&self) -> Option<Instant> {
+        self.attempt_ready_at.get()
     }
 
-    pub fn monitor(&self, mut process: ProcessManager) -> Result<bool> {
-        let stdout = process.take_stdout().expect("Failed to capture stdout");
-        let stderr = process.take_stderr().expect("Failed to capture stderr");
+    /// Clear readiness left over from a previous session before the first attempt
+    pub fn reset_ready_state(&self) -> Result<()> {
+        self.ready.clear_stale()
+    }
+
+    /// Watch one run of the server; returns why it should restart, or `None` to stop
+    pub fn monitor(
+        &self,
+        mut process: ProcessManager,
+        attempt: &AttemptInfo,
+    ) -> Result<Option<RestartReason>> {
+        // A stream left to the terminal (`capture_stdout`/`capture_stderr`) isn't scanned
+        let capture = self.config.capture;
+        let stdout = capture
+            .stdout
+            .then(|| capture_stream(process.take_stdout(), OutputStream::Stdout))
+            .transpose();
+        let stderr = capture
+            .stderr
+            .then(|| capture_stream(process.take_stderr(), OutputStream::Stderr))
+            .transpose();
+        let (stdout, stderr) = match (stdout, stderr) {
+            (Ok(stdout), Ok(stderr)) => (stdout, stderr),
+            (Err(e), _) | (_, Err(e)) => {
+                // Already running, and nobody would be watching it
+                if let Err(stop_error) = self.stop_process(&mut process) {
+                    eprintln!("Failed to stop process cleanly: {}", stop_error);
+                }
+                return Err(e);
+            }
+        };
+
+        *self.first_output.lock().unwrap() = None;
+        *self.startup_profile.lock().unwrap() = self
+            .profile_startup
+            .then(|| StartupProfile::new(Instant::now()));
+        self.attempt.set(attempt.number);
+        self.attempt_ready_at.set(None);
+        if self.ready_clock.get().is_none() {
+            self.ready_clock.set(Some(Instant::now()));
+        }
 
         // Create channels for communication between threads
         let (tx, rx) = mpsc::channel::<WatchMessage>();
-        let tx_stdout = tx.clone();
-        let tx_stderr = tx.clone();
 
         // Start monitoring threads
-        let stdout_handle = self.spawn_stdout_monitor(stdout, tx_stdout);
-        let stderr_handle = self.spawn_stderr_monitor(stderr, tx_stderr);
+        let stdout_handle =
+            stdout.map(|stdout| self.spawn_reader(stdout, OutputStream::Stdout, tx.clone()));
+        let stderr_handle =
+            stderr.map(|stderr| self.spawn_reader(stderr, OutputStream::Stderr, tx.clone()));
+        let _resource_watch =
+            resources::watch(process.id(), &self.config.resource_limits, tx.clone());
+        let _ready_command_watch = self
+            .config
+            .ready_command
+            .as_ref()
+            .map(|probe| probe.watch(tx.clone()));
+
+        // A rebuild's transient errors are ignored until the grace window has passed
+        let grace_until = self
+            .rebuilding
+            .replace(false)
+            .then(|| Instant::now() + self.config.rebuild_grace);
 
-        // Wait for either an error detection or process completion
-        let should_restart = self.wait_for_completion(&mut process, rx)?;
+        // Wait for either an error detection or process completion. The receiver is dropped
+        // with the first restart reason, so a second detection of the same crash (stdout and
+        // stderr often both carry it) is never counted: its reader just stops.
+        let result = self.wait_for_completion(&mut process, rx, attempt, grace_until);
 
         // Clean up threads
         self.cleanup_threads(stdout_handle, stderr_handle);
 
-        Ok(should_restart)
+        result
     }
 
-    fn spawn_stdout_monitor(
-        &self,
-        stdout: std::process::ChildStdout,
-        tx: Sender<WatchMessage>,
-    ) -> JoinHandle<Result<()>> {
-        let error_pattern = self.config.error_pattern.clone();
-        thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                match line {
-                    Ok(line) => {
-                        println!("📤 {}", line);
-                        if line.contains(&error_pattern) {
-                            tx.send(WatchMessage::ErrorDetected)?;
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        let _ = tx.send(WatchMessage::IoError(e.to_string()));
-                        return Err(ServerError::IoError(e.to_string()));
-                    }
-                }
-            }
-            Ok(())
-        })
+    /// How the current attempt's lines on `stream` are shown
+    fn line_format(&self, stream: OutputStream) -> LineFormat {
+        LineFormat {
+            level_colorizer: self.config.level_colorizer.clone(),
+            highlight_match: self.config.highlight_match,
+            paint_red: self.config.stderr_is_error && stream == OutputStream::Stderr,
+            color_enabled: stream.color_enabled(),
+            attempt_tag: self.config.attempt_prefix.then(|| self.attempt.get()),
+        }
     }
 
-    fn spawn_stderr_monitor(
+    fn spawn_reader<R: Read + Send + 'static>(
         &self,
-        stderr: std::process::ChildStderr,
+        reader: R,
+        stream: OutputStream,
         tx: Sender<WatchMessage>,
     ) -> JoinHandle<Result<()>> {
-        let error_pattern = self.config.error_pattern.clone();
+        let patterns = Arc::clone(&self.patterns);
+        let format = self.line_format(stream);
+        // With a threshold, a rebuild grace window or pattern cooldowns whose errors don't
+        // count, or errors that are only reported, the reader keeps going so later matches
+        // are still seen
+        let stop_at_first_error = self.config.error_threshold <= 1
+            && self.config.rebuild_grace.is_zero()
+            && self.config.restart_on_error
+            && !self
+                .config
+                .pattern_rules
+                .iter()
+                .any(|rule| rule.cooldown_secs.is_some());
+        let delimiter = self.config.delimiter;
+        let count_all = !self.config.count_per_line.is_once();
+        let mut report_first_output = self.config.first_output_timeout.is_some();
+        let report_eof = self.config.restart_on_eof;
+        let mut child_log = self.child_log.clone();
+        let recorder = self.recorder.clone();
+        let first_output = Arc::clone(&self.first_output);
+        let startup_profile = self
+            .profile_startup
+            .then(|| Arc::clone(&self.startup_profile));
+        let recent_output = self
+            .config
+            .ready_timeout
+            .map(|_| Arc::clone(&self.recent_output));
+        let raw_output = self.raw_output;
+        let buffer_size = self.config.read_buffer_size;
+        let mut blocks = self.config.blocks.clone().map(BlockCollector::new);
         thread::spawn(move || {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines() {
-                match line {
-                    Ok(line) => {
-                        eprintln!("📥 {}", line);
-                        if line.contains(&error_pattern) {
-                            tx.send(WatchMessage::ErrorDetected)?;
+            let mut reader = BufReader::with_capacity(buffer_size, reader);
+            let mut buf = Vec::new();
+            let mut ready_sent = false;
+            let mut first_line = true;
+            loop {
+                match read_chunk(&mut reader, delimiter, &mut buf) {
+                    Ok(None) => {
+                        // A trace the server printed just before exiting
+                        if let Some(block) = blocks.as_mut().and_then(BlockCollector::finish)
+                            && let Some(message) =
+                                patterns.read().unwrap().detect(&block, count_all)
+                        {
+                            let _ = tx.send(message);
+                        }
+                        if report_eof {
+                            let _ = tx.send(WatchMessage::OutputClosed);
+                        }
+                        break;
+                    }
+                    Ok(Some(line)) => {
+                        if first_line {
+                            first_line = false;
+                            first_output
+                                .lock()
+                                .unwrap()
+                                .get_or_insert_with(Instant::now);
+                        }
+                        if let Some(profile) = &startup_profile
+                            && let Some(profile) = profile.lock().unwrap().as_mut()
+                        {
+                            profile.record(Instant::now(), &line);
+                        }
+                        if let Some(recent) = &recent_output {
+                            let mut recent = recent.lock().unwrap();
+                            if recent.len() == READY_TIMEOUT_TAIL_LINES {
+                                recent.pop_front();
+                            }
+                            recent.push_back(line.clone());
+                        }
+                        if report_first_output {
+                            report_first_output = false;
+                            let _ = tx.send(WatchMessage::FirstOutput);
+                        }
+                        let patterns = patterns.read().unwrap();
+                        let matched = patterns.find_error_counted(&line, count_all);
+                        if patterns.is_muted(&line) {
+                            // Not shown, but logged, recorded and matched like any other line
+                        } else if raw_output {
+                            stream.write_raw(&buf);
+                        } else {
+                            stream.print(&format.display(&line, matched.is_some(), &patterns));
+                        }
+                        recorder.line(stream.name(), &line);
+                        if let Some(log) = &child_log
+                            && let Err(e) = log.write_line(&line)
+                        {
+                            eprintln!(
+                                "{} Failed to write log_file, no longer logging: {}",
+                                theme::icon(Icon::Warning),
+                                e
+                            );
+                            child_log = None;
+                        }
+                        // A failed send means the monitor stopped listening because a restart
+                        // began, e.g. on the same crash reported by the other stream; what's
+                        // left of this attempt's output no longer matters
+                        if !ready_sent
+                            && let Some(ready_pattern) = &patterns.ready_pattern
+                            && line.contains(ready_pattern.as_str())
+                        {
+                            ready_sent = true;
+                            if tx.send(WatchMessage::ReadyDetected).is_err() {
+                                break;
+                            }
+                        }
+                        if patterns
+                            .rule_patterns
+                            .iter()
+                            .any(|pattern| line.contains(pattern.as_str()))
+                            && tx.send(WatchMessage::RuleLine(line.clone())).is_err()
+                        {
+                            break;
+                        }
+                        let fed = blocks
+                            .as_mut()
+                            .map(|blocks| blocks.feed(&line))
+                            .unwrap_or_default();
+                        // A line in a block is only matched as part of the whole block
+                        let message = if fed.in_block {
+                            fed.completed
+                                .and_then(|block| patterns.detect(&block, count_all))
+                        } else {
+                            match matched {
+                                Some(error) => Some(WatchMessage::ErrorDetected(error)),
+                                None => patterns.find_stall(&line).map(WatchMessage::StallDetected),
+                            }
+                        };
+                        if let Some(message) = message
+                            && (tx.send(message).is_err() || stop_at_first_error)
+                        {
                             break;
                         }
                     }
@@ -103,53 +946,1298 @@ impl ProcessMonitor {
         &self,
         process: &mut ProcessManager,
         rx: Receiver<WatchMessage>,
-    ) -> Result<bool> {
+        attempt: &AttemptInfo,
+        grace_until: Option<Instant>,
+    ) -> Result<Option<RestartReason>> {
+        let mut errors = ErrorCounter::new(self.config.error_threshold, self.config.error_window);
+        let started = Instant::now();
+        let mut first_output_at: Option<Instant> = None;
+        let mut streams_closed = 0;
+        let mut output_closed_at: Option<Instant> = None;
+        let mut tick_hook = self
+            .config
+            .on_tick
+            .as_ref()
+            .map(|command| TickHook::new(command.clone(), self.config.on_tick_interval));
+        let mut rules = RuleMatcher::new(&self.config.restart_when, started);
+        let mut ready = false;
         loop {
             match rx.recv_timeout(self.config.process_check_interval) {
-                Ok(WatchMessage::ErrorDetected) => {
-                    println!("🔍 Error pattern detected!");
-                    if let Err(e) = process.kill_and_wait(self.config.shutdown_timeout) {
+                Ok(WatchMessage::FirstOutput) => {
+                    first_output_at.get_or_insert_with(Instant::now);
+                }
+                Ok(WatchMessage::OutputClosed) => {
+                    streams_closed += 1;
+                    if streams_closed == self.config.capture.streams() {
+                        output_closed_at = Some(Instant::now());
+                    }
+                }
+                Ok(WatchMessage::ErrorDetected(error)) => {
+                    if grace_until.is_some_and(|until| Instant::now() < until) {
+                        status!(
+                            "{} Ignoring '{}' while the server rebuilds (rebuild_grace_ms)",
+                            theme::icon(Icon::Waiting),
+                            error.pattern
+                        );
+                        continue;
+                    }
+                    if let Some(left) = self.cooldown_left(&error.pattern) {
+                        status!(
+                            "{} Ignoring '{}' for another {}s (cooldown_secs)",
+                            theme::icon(Icon::Waiting),
+                            error.pattern,
+                            left.as_secs().max(1)
+                        );
+                        continue;
+                    }
+                    let count = errors.record(Instant::now(), error.hits);
+                    if let Some(stats) = &self.stats {
+                        stats.lock().unwrap().record_pattern_hit(&error.pattern);
+                    }
+                    if self.config.report_matches {
+                        status!(
+                            "{} Match {}/{} for '{}': {}",
+                            theme::icon(Icon::Watch),
+                            count,
+                            errors.threshold,
+                            error.pattern,
+                            error.line
+                        );
+                    }
+                    if count < errors.threshold {
+                        continue;
+                    }
+                    if !self.config.restart_on_error {
+                        // Reported like any error, but the server keeps running
+                        self.report_error(&error, process, attempt);
+                        errors.hits.clear();
+                        continue;
+                    }
+                    let repeats = self.stats.as_ref().map_or(1, |stats| {
+                        stats.lock().unwrap().record_restart_error(&error.line)
+                    });
+                    if self.config.dedupe_errors && repeats > 1 {
+                        status!(
+                            "{} Same error as last restart (×{})",
+                            theme::icon(Icon::ErrorDetected),
+                            repeats
+                        );
+                    } else {
+                        self.report_error(&error, process, attempt);
+                    }
+                    if let Err(e) = self.stop_process(process) {
+                        eprintln!("Failed to stop process cleanly: {}", e);
+                    }
+                    if self.config.cooldown_for(&error.pattern).is_some() {
+                        self.cooldowns
+                            .borrow_mut()
+                            .insert(error.pattern.clone(), Instant::now());
+                    }
+                    return Ok(Some(RestartReason::ErrorDetected(error)));
+                }
+                Ok(WatchMessage::RuleLine(line)) => {
+                    if let Some(rule) = rules.observe(Observation::Line(&line), Instant::now()) {
+                        let rule = rule.to_string();
+                        status!(
+                            "{} restart_when rule met: {}",
+                            theme::icon(Icon::ErrorDetected),
+                            rule
+                        );
+                        status!("   Line: {}", line);
+                        self.events.emit(
+                            LifecycleEvent::new(EventKind::Error, attempt.number)
+                                .with_pid(process.id())
+                                .with_message(format!("restart_when rule met: {}", rule)),
+                        );
+                        if let Err(e) = self.stop_process(process) {
+                            eprintln!("Failed to stop process cleanly: {}", e);
+                        }
+                        return Ok(Some(RestartReason::ConditionMet(rule)));
+                    }
+                }
+                Ok(WatchMessage::StallDetected(stall)) => {
+                    if let Some(stats) = &self.stats {
+                        stats.lock().unwrap().stalls += 1;
+                    }
+                    status!(
+                        "{} Stall detected: '{}'",
+                        theme::icon(Icon::Stall),
+                        stall.pattern
+                    );
+                    self.events.emit(
+                        LifecycleEvent::new(EventKind::Error, attempt.number)
+                            .with_pid(process.id())
+                            .with_message(format!("stall pattern '{}' detected", stall.pattern)),
+                    );
+                    if let Err(e) = self.stop_process(process) {
                         eprintln!("Failed to stop process cleanly: {}", e);
                     }
-                    return Ok(true);
+                    return Ok(Some(RestartReason::Stalled(stall)));
+                }
+                Ok(WatchMessage::ReadyDetected) => {
+                    // Both streams, or ready_pattern and ready_command, may report it
+                    if std::mem::replace(&mut ready, true) {
+                        continue;
+                    }
+                    self.ready_seen.set(true);
+                    self.attempt_ready_at.set(Some(Instant::now()));
+                    let boot = BootTiming {
+                        first_output: self
+                            .first_output
+                            .lock()
+                            .unwrap()
+                            .map(|at| at.saturating_duration_since(started)),
+                        ready: started.elapsed(),
+                    };
+                    status!("{} Server ready", theme::icon(Icon::Success));
+                    status!("{}", boot.line());
+                    if let Some(profile) = self.startup_profile.lock().unwrap().take() {
+                        for line in profile.report(Instant::now()) {
+                            status!("{}", line);
+                        }
+                    }
+                    if let Some(stats) = &self.stats {
+                        stats.lock().unwrap().boots.push(boot);
+                    }
+                    self.events.emit(
+                        LifecycleEvent::new(EventKind::Ready, attempt.number)
+                            .with_pid(process.id())
+                            .with_boot_timing(&boot),
+                    );
+                    if let Err(e) = self.ready.announce() {
+                        eprintln!("Failed to announce readiness: {}", e);
+                    }
+                    if let (Some(state), Some(command)) = (&self.state, &attempt.run_command)
+                        && let Err(e) = state.record_good_command(command)
+                    {
+                        eprintln!("Failed to update state file: {}", e);
+                    }
+                    if self.stop_when_ready {
+                        if let Err(e) = self.stop_process(process) {
+                            eprintln!("Failed to stop process cleanly: {}", e);
+                        }
+                        return Ok(None);
+                    }
                 }
                 Ok(WatchMessage::IoError(msg)) => {
                     eprintln!("Reader IO error: {}", msg);
-                    let _ = process.kill_and_wait(self.config.shutdown_timeout);
-                    return Ok(true); // treat IO errors as reason to restart
+                    let _ = self.stop_process(process);
+                    return Ok(Some(RestartReason::ReaderError)); // treat IO errors as reason to restart
+                }
+                Ok(WatchMessage::ResourceLimitExceeded(reason)) => {
+                    status!(
+                        "{} Resource limit exceeded: {}",
+                        theme::icon(Icon::Resource),
+                        reason
+                    );
+                    self.events.emit(
+                        LifecycleEvent::new(EventKind::Error, attempt.number)
+                            .with_pid(process.id())
+                            .with_message(format!("resource limit exceeded: {}", reason)),
+                    );
+                    if let Err(e) = self.stop_process(process) {
+                        eprintln!("Failed to stop process cleanly: {}", e);
+                    }
+                    return Ok(Some(RestartReason::ResourceLimit));
                 }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
+                    let waiting_for_ready = self
+                        .ready_clock
+                        .get()
+                        .filter(|_| !self.ready_seen.get())
+                        .map(|clock| clock.elapsed());
+                    if let (Some(warn_after), Some(waited)) =
+                        (self.config.ready_warn_after, waiting_for_ready)
+                        && waited >= warn_after
+                        && !self.ready_warned.replace(true)
+                    {
+                        eprintln!(
+                            "{} Server not ready after {}s — still waiting",
+                            theme::icon(Icon::Warning),
+                            warn_after.as_secs()
+                        );
+                    }
+                    if let (Some(timeout), Some(waited)) =
+                        (self.config.ready_timeout, waiting_for_ready)
+                        && waited >= timeout
+                    {
+                        self.report_ready_timeout(timeout);
+                        if let Err(e) = self.stop_process(process) {
+                            eprintln!("Failed to stop process cleanly: {}", e);
+                        }
+                        return Ok(Some(RestartReason::ReadyTimeout(timeout)));
+                    }
+                    if let Some(timeout) = self.config.first_output_timeout
+                        && first_output_at.is_none()
+                        && started.elapsed() >= timeout
+                    {
+                        status!(
+                            "{} No output within {}s of starting; treating this as a failed start, not a crash",
+                            theme::icon(Icon::Timeout),
+                            timeout.as_secs()
+                        );
+                        if let Err(e) = self.stop_process(process) {
+                            eprintln!("Failed to stop process cleanly: {}", e);
+                        }
+                        return Err(ServerError::ProcessStart(format!(
+                            "no output within {}s of spawning",
+                            timeout.as_secs()
+                        )));
+                    }
+                    if let Some(hook) = &mut tick_hook {
+                        hook.tick(started.elapsed(), attempt.number, attempt.restarts);
+                    }
+                    if self.apply_config_reloads() {
+                        if let Err(e) = self.stop_process_gracefully(process) {
+                            eprintln!("Failed to stop process cleanly: {}", e);
+                        }
+                        self.rebuilding.set(true);
+                        return Ok(Some(RestartReason::ConfigChanged));
+                    }
+
                     // Check if process exited
                     match process.try_wait()? {
-                        Some(status) => {
-                            println!("📋 Process exited with status: {}", status);
-                            return Ok(!status.success()); // Restart on non-zero exit
+                        ChildState::ReapedElsewhere => {
+                            eprintln!(
+                                "{} PID {} was reaped by another process (init system or container runtime?); its exit status is unknown, so treating it as a crash",
+                                theme::icon(Icon::Warning),
+                                process.id()
+                            );
+                            self.events.emit(
+                                LifecycleEvent::new(EventKind::Exit, attempt.number)
+                                    .with_pid(process.id())
+                                    .with_message("reaped by another process"),
+                            );
+                            return Ok(Some(RestartReason::ExitFailure(None)));
+                        }
+                        ChildState::Exited(status) => {
+                            status!(
+                                "{} Process {}",
+                                theme::icon(Icon::Exit),
+                                exit_status::format(&status)
+                            );
+                            self.events.emit(
+                                LifecycleEvent::new(EventKind::Exit, attempt.number)
+                                    .with_pid(process.id())
+                                    .with_exit_code(status.code()),
+                            );
+                            if let Some(code) = status.code()
+                                && SHELL_START_FAILURE_CODES.contains(&code)
+                            {
+                                // The shell started but couldn't run the command (typo, missing binary)
+                                return Err(ServerError::ProcessStart(format!(
+                                    "command could not be run (exit code {})",
+                                    code
+                                )));
+                            }
+                            #[cfg(unix)]
+                            if let Some(signal) = signals::exit_signal(&status)
+                                && !signals::is_crash_signal(
+                                    signal,
+                                    &self.config.crash_signals,
+                                    &self.config.stop_signals,
+                                )
+                            {
+                                status!(
+                                    "{} Dev server stopped by signal {}",
+                                    theme::icon(Icon::Stop),
+                                    signal
+                                );
+                                return Ok(None);
+                            }
+                            if let Some(remote) = &self.config.remote
+                                && status.code() == Some(SSH_CONNECTION_LOST)
+                            {
+                                status!(
+                                    "{} SSH connection to {} was lost",
+                                    theme::icon(Icon::Disconnected),
+                                    remote.host
+                                );
+                            }
+                            if let Some(rule) =
+                                rules.observe(Observation::Exit(status.code()), Instant::now())
+                            {
+                                status!(
+                                    "{} restart_when rule met: {}",
+                                    theme::icon(Icon::ErrorDetected),
+                                    rule
+                                );
+                                return Ok(Some(RestartReason::ConditionMet(rule.to_string())));
+                            }
+                            let code = exit_rules::rule_code(&status);
+                            if let Some(rule) = self.config.exit_code_rule(code)
+                                && let Some(message) = &rule.notify
+                            {
+                                eprintln!("{} {} ({})", theme::icon(Icon::Warning), message, rule);
+                            }
+                            // A clean exit ends the session unless `exit_code_rules` restarts on 0;
+                            // the policy decides about every other exit
+                            if status.success() {
+                                if self.config.exit_action(Some(0)) == ExitAction::Stop {
+                                    return Ok(None);
+                                }
+                                return Ok(Some(RestartReason::ExitRule(0)));
+                            }
+                            return Ok(Some(RestartReason::ExitFailure(code)));
+                        }
+                        ChildState::Running => {
+                            if let Some(closed_at) = output_closed_at
+                                && closed_at.elapsed() >= EOF_GRACE
+                            {
+                                status!(
+                                    "{} Dev server closed its output but the process is still running",
+                                    theme::icon(Icon::OutputClosed)
+                                );
+                                if let Err(e) = self.stop_process(process) {
+                                    eprintln!("Failed to stop process cleanly: {}", e);
+                                }
+                                return Ok(Some(RestartReason::OutputClosed));
+                            }
+                            continue; // Still running
                         }
-                        None => continue, // Still running
                     }
                 }
                 Err(mpsc::RecvTimeoutError::Disconnected) => {
-                    println!("📡 Channel disconnected");
-                    return Ok(false);
+                    status!("{} Channel disconnected", theme::icon(Icon::Start));
+                    return Ok(None);
                 }
             }
         }
     }
 
+    /// Apply pending config changes; returns whether the server must restart for them
+    fn apply_config_reloads(&self) -> bool {
+        let Some(reloads) = &self.reloads else {
+            return false;
+        };
+
+        // Everything queued since the last check becomes at most one restart
+        let Some(reload) = reload::coalesce(reloads.try_iter()) else {
+            return false;
+        };
+
+        let fields = reload.diff.field_names().join(", ");
+        let restart = reload.diff.requires_restart();
+        if restart {
+            status!(
+                "{} {} changed ({}); restarting dev server to apply it",
+                theme::icon(Icon::Reload),
+                self.config_path.display(),
+                fields
+            );
+        } else if !reload.diff.is_empty() {
+            status!(
+                "{} {} changed ({}); applied live",
+                theme::icon(Icon::Reload),
+                self.config_path.display(),
+                fields
+            );
+        } else if reload.pattern_files_changed {
+            status!(
+                "{} Pattern file changed; error patterns reloaded",
+                theme::icon(Icon::Reload)
+            );
+        }
+        for change in &reload.diff.changes {
+            status!("   {}", change);
+        }
+
+        match reload
+            .config
+            .error_patterns()
+            .and_then(|plain| Ok((plain, reload.config.regex_patterns()?)))
+        {
+            Ok((error_patterns, regex_patterns)) => {
+                let mut patterns = self.patterns.write().unwrap();
+                patterns.error_patterns = error_patterns;
+                patterns.regex_patterns = compile_regexes(&regex_patterns);
+                patterns.stall_patterns = reload.config.stall_patterns.clone();
+                patterns.ready_pattern = reload
+                    .config
+                    .ready_pattern
+                    .clone()
+                    .filter(|pattern| !pattern.is_empty());
+            }
+            Err(e) => eprintln!(
+                "{} Keeping previous error patterns: {}",
+                theme::icon(Icon::Warning),
+                e
+            ),
+        }
+        *self.reloaded_config.borrow_mut() = Some(reload.config);
+        restart
+    }
+
+    /// What remains of `pattern`'s cooldown since it last triggered a restart, if any
+    fn cooldown_left(&self, pattern: &str) -> Option<Duration> {
+        let cooldown = self.config.cooldown_for(pattern)?;
+        let triggered = *self.cooldowns.borrow().get(pattern)?;
+        cooldown
+            .checked_sub(triggered.elapsed())
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Print a detected error and send the `error` event
+    fn report_error(&self, error: &ErrorMatch, process: &ProcessManager, attempt: &AttemptInfo) {
+        status!(
+            "{} Error pattern detected: '{}'",
+            theme::icon(Icon::ErrorDetected),
+            error.pattern
+        );
+        if self.patterns.read().unwrap().is_muted(&error.line) {
+            // The line itself was never printed
+            status!("   Muted line: {}", error.line);
+        }
+        let mut message = format!("error pattern '{}' detected", error.pattern);
+        if !error.captures.is_empty() {
+            let captures = patterns::format_captures(&error.captures);
+            status!("   Captured: {}", captures);
+            message.push_str(&format!(" ({})", captures));
+        }
+        self.events.emit(
+            LifecycleEvent::new(EventKind::Error, attempt.number)
+                .with_pid(process.id())
+                .with_message(message)
+                .with_captures(error.captures.clone()),
+        );
+    }
+
+    fn report_ready_timeout(&self, timeout: Duration) {
+        eprintln!(
+            "{} Dev server not ready within {}s (ready_timeout_secs)",
+            theme::icon(Icon::Timeout),
+            timeout.as_secs()
+        );
+        let recent = self.recent_output.lock().unwrap();
+        if recent.is_empty() {
+            eprintln!("   It printed nothing");
+            return;
+        }
+        eprintln!("   Last output:");
+        for line in recent.iter() {
+            eprintln!("   | {}", line);
+        }
+    }
+
+    fn stop_process(&self, process: &mut ProcessManager) -> Result<()> {
+        if self.config.no_force_kill {
+            return process.terminate_gracefully(self.config.graceful_shutdown_warn_interval);
+        }
+        if process.kill_and_wait(&self.config.shutdown)? == ShutdownOutcome::StillRunning {
+            warn_still_running(process);
+        }
+        Ok(())
+    }
+
+    /// Stop a server that isn't misbehaving (its configuration changed), giving it
+    /// `shutdown` to exit on its own before it is force-killed
+    fn stop_process_gracefully(&self, process: &mut ProcessManager) -> Result<()> {
+        if self.config.no_force_kill {
+            return process.terminate_gracefully(self.config.graceful_shutdown_warn_interval);
+        }
+        if process.stop_gracefully(&self.config.shutdown)? == ShutdownOutcome::StillRunning {
+            warn_still_running(process);
+        }
+        Ok(())
+    }
+
     fn cleanup_threads(
         &self,
-        stdout_handle: JoinHandle<Result<()>>,
-        stderr_handle: JoinHandle<Result<()>>,
+        stdout_handle: Option<JoinHandle<Result<()>>>,
+        stderr_handle: Option<JoinHandle<Result<()>>>,
     ) {
-        match stdout_handle.join() {
-            Ok(Ok(())) => {}
-            Ok(Err(e)) => eprintln!("stdout thread returned error: {}", e),
-            Err(panic) => eprintln!("stdout thread panicked: {:?}", panic),
-        }
-        match stderr_handle.join() {
-            Ok(Ok(())) => {}
-            Ok(Err(e)) => eprintln!("stderr thread returned error: {}", e),
-            Err(panic) => eprintln!("stderr thread panicked: {:?}", panic),
+        let handles = [
+            (stdout_handle, OutputStream::Stdout),
+            (stderr_handle, OutputStream::Stderr),
+        ];
+        for (handle, stream) in handles {
+            match handle.map(JoinHandle::join) {
+                None | Some(Ok(Ok(()))) => {}
+                Some(Ok(Err(e))) => eprintln!("{} thread returned error: {}", stream.name(), e),
+                Some(Err(panic)) => eprintln!("{} thread panicked: {:?}", stream.name(), panic),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::BlockDelimiters;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_missing_stream_is_an_error() {
+        let result = capture_stream::<Cursor<Vec<u8>>>(None, OutputStream::Stdout);
+        match result {
+            Err(ServerError::ProcessManagement(msg)) => assert_eq!(msg, "Failed to capture stdout"),
+            other => panic!(
+                "expected ProcessManagement error, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+
+    #[test]
+    fn test_captured_stream_is_returned() {
+        let stream = capture_stream(Some(Cursor::new(b"ok".to_vec())), OutputStream::Stderr);
+        assert!(stream.is_ok());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_missing_capture_stream_stops_the_server() {
+        use crate::command::{CommandBuilder, CommandType};
+        use crate::process::Capture;
+
+        let monitor = ProcessMonitor::new(Config::default());
+        // Spawned with stdout left to the terminal, though the monitor expects to read it
+        let process = ProcessManager::spawn_capturing(
+            CommandBuilder::build(CommandType::Hook("sleep 30".to_string())),
+            Capture {
+                stdout: false,
+                stderr: true,
+            },
+        )
+        .unwrap();
+        let pid = process.id();
+        let attempt = AttemptInfo {
+            number: 1,
+            run_command: None,
+            restarts: 0,
+        };
+
+        let result = monitor.monitor(process, &attempt);
+        assert!(matches!(result, Err(ServerError::ProcessManagement(_))));
+        assert_eq!(crate::process::command_line(pid), None);
+    }
+
+    #[test]
+    fn test_matches_any_pattern() {
+        let patterns = vec!["[Error".to_string(), "panic:".to_string()];
+        assert_eq!(
+            matching_pattern("thread 'main' panic: oops", &patterns),
+            Some("panic:")
+        );
+        assert_eq!(matching_pattern("[Error] boom", &patterns), Some("[Error"));
+        assert_eq!(matching_pattern("all good", &patterns), None);
+    }
+
+    #[test]
+    fn test_highlight_every_match() {
+        let patterns = vec!["[Error".to_string(), "Error]".to_string(), "x".to_string()];
+        assert_eq!(
+            match_spans("[Error] x [Error", &patterns, &[]),
+            vec![(0, 7), (8, 9), (10, 16)]
+        );
+        assert_eq!(
+            highlight_matches("a [Error b", &patterns[..1], &[]),
+            "a \x1b[1;31m[Error\x1b[0m b"
+        );
+    }
+
+    #[test]
+    fn test_error_counter_threshold_and_window() {
+        let start = Instant::now();
+        let mut counter = ErrorCounter::new(3, Some(Duration::from_secs(10)));
+        assert_eq!(counter.record(start, 1), 1);
+        assert_eq!(counter.record(start + Duration::from_secs(4), 1), 2);
+        // The first match has left the window by now
+        assert_eq!(counter.record(start + Duration::from_secs(12), 1), 2);
+        assert_eq!(counter.record(start + Duration::from_secs(13), 1), 3);
+
+        let mut unlimited = ErrorCounter::new(2, None);
+        unlimited.record(start, 1);
+        assert_eq!(unlimited.record(start + Duration::from_secs(3600), 1), 2);
+    }
+
+    #[test]
+    fn test_count_per_line_once_or_all() {
+        let line = "[Error] a [Error] b [Error] c";
+        let hits_for = |count_per_line| {
+            let config = Config {
+                error_threshold: 5,
+                count_per_line,
+                ..Config::default()
+            };
+            let (tx, rx) = mpsc::channel();
+            let handle = ProcessMonitor::new(config).spawn_reader(
+                Cursor::new(line.as_bytes().to_vec()),
+                OutputStream::Stdout,
+                tx,
+            );
+            handle.join().unwrap().unwrap();
+            match rx.try_iter().collect::<Vec<_>>().as_slice() {
+                [WatchMessage::ErrorDetected(error)] => error.hits,
+                other => panic!("unexpected messages: {:?}", other),
+            }
+        };
+        assert_eq!(hits_for(CountPerLine::Once), 1);
+        assert_eq!(hits_for(CountPerLine::All), 3);
+
+        let start = Instant::now();
+        let mut counter = ErrorCounter::new(5, None);
+        assert_eq!(counter.record(start, 1), 1);
+        assert_eq!(counter.record(start, 3), 4);
+    }
+
+    #[test]
+    fn test_restart_reason_kind_and_detail_for_each_trigger() {
+        let error = |pattern: &str| ErrorMatch {
+            pattern: pattern.to_string(),
+            line: format!("{} boom", pattern),
+            captures: BTreeMap::new(),
+            hits: 1,
+        };
+        let cases = [
+            (
+                RestartReason::ErrorDetected(error("[Error")),
+                "error",
+                "[Error",
+            ),
+            (
+                RestartReason::Stalled(error("timed out")),
+                "stall",
+                "timed out",
+            ),
+            (RestartReason::ReaderError, "reader_error", ""),
+            (RestartReason::ExitFailure(Some(137)), "exit", "137"),
+            (RestartReason::ExitFailure(None), "exit", ""),
+            (RestartReason::ConfigChanged, "config_change", ""),
+            (RestartReason::ResourceLimit, "resource_limit", ""),
+            (RestartReason::OutputClosed, "output_closed", ""),
+            (
+                RestartReason::ConditionMet("'a' then 'b'".to_string()),
+                "restart_when",
+                "'a' then 'b'",
+            ),
+        ];
+        for (reason, kind, detail) in cases {
+            assert_eq!(reason.kind(), kind, "{:?}", reason);
+            assert_eq!(reason.detail(), detail, "{:?}", reason);
+        }
+    }
+
+    #[test]
+    fn test_read_chunk_with_custom_delimiters() {
+        let mut input = Cursor::new(b"ok\0[Error] boom\0tail".to_vec());
+        let mut buf = Vec::new();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = read_chunk(&mut input, 0, &mut buf).unwrap() {
+            chunks.push(chunk);
+        }
+        assert_eq!(chunks, vec!["ok", "[Error] boom", "tail"]);
+
+        let mut input = Cursor::new(b"a|b\r|".to_vec());
+        assert_eq!(
+            read_chunk(&mut input, b'|', &mut buf).unwrap().as_deref(),
+            Some("a")
+        );
+        assert_eq!(
+            read_chunk(&mut input, b'|', &mut buf).unwrap().as_deref(),
+            Some("b\r")
+        );
+        assert_eq!(read_chunk(&mut input, b'|', &mut buf).unwrap(), None);
+    }
+
+    #[test]
+    fn test_read_chunk_keeps_raw_bytes_for_raw_output() {
+        let input = b"ready\r\n\xffbin\n[Error] boom\n\x1b[31mred\x1b[0m\r\n\ntail".to_vec();
+        let mut reader = Cursor::new(input.clone());
+        let mut buf = Vec::new();
+        let mut output = Vec::new();
+        while read_chunk(&mut reader, b'\n', &mut buf).unwrap().is_some() {
+            write_flushed(&mut output, &buf).unwrap();
+        }
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn test_read_chunk_strips_crlf_and_replaces_invalid_utf8() {
+        let mut input = Cursor::new(b"ready\r\n\xffbin\n".to_vec());
+        let mut buf = Vec::new();
+        assert_eq!(
+            read_chunk(&mut input, b'\n', &mut buf).unwrap().as_deref(),
+            Some("ready")
+        );
+        assert_eq!(
+            read_chunk(&mut input, b'\n', &mut buf).unwrap().as_deref(),
+            Some("\u{fffd}bin")
+        );
+    }
+
+    /// Counts `read` calls, i.e. the syscalls a pipe reader would make
+    struct CountingReader<R> {
+        inner: R,
+        reads: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.read(buf)
+        }
+    }
+
+    fn reads_for(buffer_size: usize, input: &[u8]) -> usize {
+        let config = Config {
+            read_buffer_size: buffer_size,
+            ..Config::default()
+        };
+        let reads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let reader = CountingReader {
+            inner: Cursor::new(input.to_vec()),
+            reads: Arc::clone(&reads),
+        };
+        let (tx, _rx) = mpsc::channel();
+        let handle = ProcessMonitor::new(config).spawn_reader(reader, OutputStream::Stdout, tx);
+        handle.join().unwrap().unwrap();
+        reads.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_larger_read_buffer_needs_fewer_reads_for_long_lines() {
+        // Ten 200 KB JSON-ish lines
+        let line = format!("{{\"blob\":\"{}\"}}\n", "x".repeat(200 * 1024));
+        let input = line.repeat(10);
+
+        let default_reads = reads_for(DEFAULT_READ_BUFFER_SIZE, input.as_bytes());
+        let large_reads = reads_for(1024 * 1024, input.as_bytes());
+        assert!(
+            default_reads > 250,
+            "default buffer: {} reads",
+            default_reads
+        );
+        assert!(large_reads <= 3, "1 MB buffer: {} reads", large_reads);
+    }
+
+    #[test]
+    fn test_reader_matches_null_delimited_output() {
+        let config = Config {
+            delimiter: 0,
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let (tx, rx) = mpsc::channel();
+        let input = Cursor::new(b"heartbeat\0[Error] boom".to_vec());
+
+        let handle = monitor.spawn_reader(input, OutputStream::Stdout, tx);
+        handle.join().unwrap().unwrap();
+
+        let messages: Vec<WatchMessage> = rx.try_iter().collect();
+        match messages.as_slice() {
+            [WatchMessage::ErrorDetected(error)] => assert_eq!(error.line, "[Error] boom"),
+            other => panic!("unexpected messages: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reader_matches_a_stack_trace_as_one_error() {
+        let trace = "TypeError: Cannot read properties of undefined (reading 'id')\n    at getUser (/app/src/users.js:14:22)\n    at Layer.handle (/app/node_modules/express/lib/router/layer.js:95:5)";
+        let input =
+            || Cursor::new(format!("GET /users 200\n{}\n\nGET /health 200\n", trace).into_bytes());
+        let config = |blocks| Config {
+            error_patterns: vec!["    at ".to_string()],
+            error_threshold: 5,
+            blocks,
+            ..Config::default()
+        };
+        let errors = |config| {
+            let (tx, rx) = mpsc::channel();
+            let handle =
+                ProcessMonitor::new(config).spawn_reader(input(), OutputStream::Stdout, tx);
+            handle.join().unwrap().unwrap();
+            rx.try_iter()
+                .map(|message| match message {
+                    WatchMessage::ErrorDetected(error) => error,
+                    other => panic!("unexpected message: {:?}", other),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Line by line, every frame is an error of its own
+        assert_eq!(errors(config(None)).len(), 2);
+
+        let blocks = BlockDelimiters {
+            start: "Error:".to_string(),
+            end: None,
+            max_lines: 200,
+        };
+        let errors = errors(config(Some(blocks)));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, trace);
+        assert_eq!(errors[0].hits, 1);
+    }
+
+    #[test]
+    fn test_reader_reports_first_output_once() {
+        let config = Config {
+            first_output_timeout: Some(Duration::from_secs(5)),
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let (tx, rx) = mpsc::channel();
+        let input = Cursor::new(b"booting\nstill booting\n".to_vec());
+
+        let handle = monitor.spawn_reader(input, OutputStream::Stdout, tx);
+        handle.join().unwrap().unwrap();
+
+        let messages: Vec<WatchMessage> = rx.try_iter().collect();
+        assert!(matches!(messages.as_slice(), [WatchMessage::FirstOutput]));
+    }
+
+    #[test]
+    fn test_reader_reports_eof_only_with_restart_on_eof() {
+        let input = || Cursor::new(b"booting\n".to_vec());
+
+        let (tx, rx) = mpsc::channel();
+        let handle =
+            ProcessMonitor::new(Config::default()).spawn_reader(input(), OutputStream::Stdout, tx);
+        handle.join().unwrap().unwrap();
+        assert_eq!(rx.try_iter().count(), 0);
+
+        let config = Config {
+            restart_on_eof: true,
+            ..Config::default()
+        };
+        let (tx, rx) = mpsc::channel();
+        let handle = ProcessMonitor::new(config).spawn_reader(input(), OutputStream::Stdout, tx);
+        handle.join().unwrap().unwrap();
+        let messages: Vec<WatchMessage> = rx.try_iter().collect();
+        assert!(matches!(messages.as_slice(), [WatchMessage::OutputClosed]));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_restart_when_output_closes_but_process_lives() {
+        use crate::command::{CommandBuilder, CommandType};
+
+        let config = Config {
+            restart_on_eof: true,
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let command =
+            CommandBuilder::build(CommandType::Hook("exec >&- 2>&-; exec sleep 5".to_string()));
+        let process = ProcessManager::spawn(command).unwrap();
+        let attempt = AttemptInfo {
+            number: 1,
+            run_command: None,
+            restarts: 0,
+        };
+        assert_eq!(
+            monitor.monitor(process, &attempt).unwrap(),
+            Some(RestartReason::OutputClosed)
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_ready_warn_after_warns_once_and_keeps_waiting() {
+        use crate::command::{CommandBuilder, CommandType};
+
+        let config = Config {
+            ready_pattern: Some("listening".to_string()),
+            ready_warn_after: Some(Duration::from_millis(200)),
+            ready_timeout: Some(Duration::from_secs(5)),
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let spawn = |script: &str| {
+            ProcessManager::spawn(CommandBuilder::build(CommandType::Hook(script.to_string())))
+                .unwrap()
+        };
+        let attempt = AttemptInfo {
+            number: 1,
+            run_command: None,
+            restarts: 0,
+        };
+
+        // A slow boot gets the warning but still counts as ready
+        assert_eq!(
+            monitor
+                .monitor(spawn("sleep 0.6; echo listening; sleep 0.3"), &attempt)
+                .unwrap(),
+            None
+        );
+        assert!(monitor.ready_warned.get());
+        assert!(monitor.ready_seen.get());
+        assert!(monitor.attempt_ready_at().is_some());
+
+        monitor.restart_ready_clock();
+        assert_eq!(
+            monitor
+                .monitor(spawn("echo listening; sleep 0.6"), &attempt)
+                .unwrap(),
+            None
+        );
+        assert!(!monitor.ready_warned.get());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_ready_timeout_stops_a_server_that_never_becomes_ready() {
+        use crate::command::{CommandBuilder, CommandType};
+
+        let config = Config {
+            ready_pattern: Some("listening".to_string()),
+            ready_timeout: Some(Duration::from_millis(600)),
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let spawn = |script: &str| {
+            ProcessManager::spawn(CommandBuilder::build(CommandType::Hook(script.to_string())))
+                .unwrap()
+        };
+        let attempt = AttemptInfo {
+            number: 1,
+            run_command: None,
+            restarts: 0,
+        };
+
+        assert_eq!(
+            monitor
+                .monitor(
+                    spawn("echo compiling; echo 'still compiling'; exec sleep 5"),
+                    &attempt
+                )
+                .unwrap(),
+            Some(RestartReason::ReadyTimeout(Duration::from_millis(600)))
+        );
+        assert_eq!(
+            *monitor.recent_output.lock().unwrap(),
+            ["compiling", "still compiling"]
+        );
+
+        // The clock spans restarts: a crash doesn't buy the next attempt more time
+        monitor.restart_ready_clock();
+        assert_eq!(
+            monitor.monitor(spawn("exit 1"), &attempt).unwrap(),
+            Some(RestartReason::ExitFailure(Some(1)))
+        );
+        thread::sleep(Duration::from_millis(700));
+        assert_eq!(
+            monitor.monitor(spawn("exec sleep 5"), &attempt).unwrap(),
+            Some(RestartReason::ReadyTimeout(Duration::from_millis(600)))
+        );
+
+        // Ready in time: the timeout no longer applies
+        monitor.restart_ready_clock();
+        assert_eq!(
+            monitor
+                .monitor(spawn("echo listening; sleep 1"), &attempt)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_restart_when_sequence_restarts_the_server() {
+        use crate::command::{CommandBuilder, CommandType};
+
+        let config = Config {
+            restart_when: serde_json::from_str(
+                r#"[{"sequence": [{"pattern": "pool exhausted"}, {"pattern": "timeout"}]}]"#,
+            )
+            .unwrap(),
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let command = CommandBuilder::build(CommandType::Hook(
+            "echo timeout; echo 'pool exhausted'; echo 'request timeout'; sleep 5".to_string(),
+        ));
+        let process = ProcessManager::spawn(command).unwrap();
+        let attempt = AttemptInfo {
+            number: 1,
+            run_command: None,
+            restarts: 0,
+        };
+        assert_eq!(
+            monitor.monitor(process, &attempt).unwrap(),
+            Some(RestartReason::ConditionMet(
+                "'pool exhausted' then 'timeout'".to_string()
+            ))
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_rebuild_grace_ignores_errors_only_after_a_config_restart() {
+        use crate::command::{CommandBuilder, CommandType};
+
+        let config = Config {
+            rebuild_grace: Duration::from_millis(800),
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let spawn = || {
+            ProcessManager::spawn(CommandBuilder::build(CommandType::Hook(
+                "echo '[Error] transient'; sleep 1; echo '[Error] real'; sleep 5".to_string(),
+            )))
+            .unwrap()
+        };
+        let attempt = AttemptInfo {
+            number: 1,
+            run_command: None,
+            restarts: 0,
+        };
+        let error_line = |reason| match reason {
+            Some(RestartReason::ErrorDetected(error)) => error.line,
+            other => panic!("unexpected result: {:?}", other),
+        };
+
+        monitor.rebuilding.set(true);
+        assert_eq!(
+            error_line(monitor.monitor(spawn(), &attempt).unwrap()),
+            "[Error] real"
+        );
+        // The grace only covers the attempt right after a config change
+        assert_eq!(
+            error_line(monitor.monitor(spawn(), &attempt).unwrap()),
+            "[Error] transient"
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_pattern_in_cooldown_is_ignored_but_others_still_restart() {
+        use crate::command::{CommandBuilder, CommandType};
+
+        let cli_config: CliConfig = serde_json::from_str(
+            r#"{"run_command": "npm run dev", "error_pattern": "[Error",
+                "pattern_rules": [{"pattern": "ECONNRESET", "cooldown_secs": 60}]}"#,
+        )
+        .unwrap();
+        let monitor = ProcessMonitor::new(Config::new().with_cli_config(&cli_config).unwrap());
+        let spawn = |script: &str| {
+            ProcessManager::spawn(CommandBuilder::build(CommandType::Hook(script.to_string())))
+                .unwrap()
+        };
+        let attempt = AttemptInfo {
+            number: 1,
+            run_command: None,
+            restarts: 0,
+        };
+        let error_line = |reason| match reason {
+            Some(RestartReason::ErrorDetected(error)) => error.line,
+            other => panic!("unexpected result: {:?}", other),
+        };
+
+        assert_eq!(
+            error_line(
+                monitor
+                    .monitor(spawn("echo 'ECONNRESET first'; exec sleep 5"), &attempt)
+                    .unwrap()
+            ),
+            "ECONNRESET first"
+        );
+        assert!(monitor.cooldown_left("ECONNRESET").is_some());
+        // The same pattern again is ignored, while a different one still restarts
+        assert_eq!(
+            error_line(
+                monitor
+                    .monitor(
+                        spawn(
+                            "echo 'ECONNRESET again'; sleep 0.3; echo '[Error] boom'; exec sleep 5"
+                        ),
+                        &attempt,
+                    )
+                    .unwrap()
+            ),
+            "[Error] boom"
+        );
+        assert_eq!(monitor.cooldown_left("[Error"), None);
+    }
+
+    #[test]
+    fn test_reader_stops_quietly_once_the_monitor_stops_listening() {
+        let config = Config {
+            ready_pattern: Some("listening".to_string()),
+            restart_when: vec![serde_json::from_str(r#"{"pattern": "retrying"}"#).unwrap()],
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        for input in [
+            "listening on 3000\n[Error] boom\n",
+            "retrying\n[Error] boom\n",
+        ] {
+            let (tx, rx) = mpsc::channel();
+            drop(rx);
+            let handle = monitor.spawn_reader(
+                Cursor::new(input.as_bytes().to_vec()),
+                OutputStream::Stdout,
+                tx,
+            );
+            assert!(handle.join().unwrap().is_ok(), "{:?}", input);
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_a_crash_on_both_streams_restarts_and_counts_once() {
+        use crate::command::{CommandBuilder, CommandType};
+
+        let stats = SharedStats::default();
+        let monitor = ProcessMonitor::new(Config::default()).with_stats(Arc::clone(&stats));
+        let process = ProcessManager::spawn(CommandBuilder::build(CommandType::Hook(
+            "echo '[Error] boom'; echo '[Error] boom' >&2; exec sleep 5".to_string(),
+        )))
+        .unwrap();
+        let attempt = AttemptInfo {
+            number: 1,
+            run_command: None,
+            restarts: 0,
+        };
+        assert!(matches!(
+            monitor.monitor(process, &attempt).unwrap(),
+            Some(RestartReason::ErrorDetected(_))
+        ));
+
+        let stats = stats.lock().unwrap();
+        assert_eq!(stats.pattern_hits["[Error"], 1);
+        assert_eq!(stats.last_error, Some(("[Error] boom".to_string(), 1)));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_lines_are_tagged_with_the_attempt_that_printed_them() {
+        use crate::command::{CommandBuilder, CommandType};
+
+        let config = Config {
+            attempt_prefix: true,
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let patterns = LivePatterns::default();
+        for number in [1, 2] {
+            let process = ProcessManager::spawn(CommandBuilder::build(CommandType::Hook(
+                "echo '[Error] boom'; exec sleep 5".to_string(),
+            )))
+            .unwrap();
+            let attempt = AttemptInfo {
+                number,
+                run_command: None,
+                restarts: number - 1,
+            };
+            assert!(monitor.monitor(process, &attempt).unwrap().is_some());
+            // Readers of the restarted server take the new number
+            let format = monitor.line_format(OutputStream::Stdout);
+            assert_eq!(
+                format.display("[Error] boom", false, &patterns),
+                format!("#{} | [Error] boom", number)
+            );
+        }
+        let untagged = ProcessMonitor::new(Config::default()).line_format(OutputStream::Stdout);
+        assert_eq!(untagged.display("GET / 200", false, &patterns), "GET / 200");
+    }
+
+    #[test]
+    fn test_reader_reports_ready_then_error() {
+        let config = Config {
+            ready_pattern: Some("listening".to_string()),
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let (tx, rx) = mpsc::channel();
+        let input = Cursor::new(b"booting\nlistening on 3000\n[Error] boom\nafter\n".to_vec());
+
+        let handle = monitor.spawn_reader(input, OutputStream::Stdout, tx);
+        handle.join().unwrap().unwrap();
+
+        let messages: Vec<WatchMessage> = rx.try_iter().collect();
+        match messages.as_slice() {
+            [
+                WatchMessage::ReadyDetected,
+                WatchMessage::ErrorDetected(error),
+            ] => {
+                assert_eq!(error.pattern, "[Error");
+                assert_eq!(error.line, "[Error] boom");
+            }
+            other => panic!("unexpected messages: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_muted_lines_are_hidden_but_still_matched() {
+        let config = Config {
+            mute_patterns: vec!["DeprecationWarning".to_string()],
+            ready_pattern: Some("listening".to_string()),
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        {
+            let patterns = monitor.patterns.read().unwrap();
+            assert!(patterns.is_muted("(node:42) DeprecationWarning: Buffer() is deprecated"));
+            assert!(!patterns.is_muted("GET /api 200"));
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let output = b"DeprecationWarning: listening on 3000\nGET /api 200\nDeprecationWarning: [Error] in handler\n";
+        let handle = monitor.spawn_reader(Cursor::new(output.to_vec()), OutputStream::Stdout, tx);
+        handle.join().unwrap().unwrap();
+        match rx.try_iter().collect::<Vec<_>>().as_slice() {
+            [
+                WatchMessage::ReadyDetected,
+                WatchMessage::ErrorDetected(error),
+            ] => {
+                assert_eq!(error.line, "DeprecationWarning: [Error] in handler");
+            }
+            other => panic!("unexpected messages: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reader_reports_stalls_separately_from_errors() {
+        let config = Config {
+            stall_patterns: vec!["timed out waiting for".to_string()],
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let read = |output: &[u8]| {
+            let (tx, rx) = mpsc::channel();
+            let handle =
+                monitor.spawn_reader(Cursor::new(output.to_vec()), OutputStream::Stdout, tx);
+            handle.join().unwrap().unwrap();
+            rx.try_iter().collect::<Vec<WatchMessage>>()
+        };
+
+        match read(b"booting\ntimed out waiting for db\n").as_slice() {
+            [WatchMessage::StallDetected(stall)] => {
+                assert_eq!(stall.pattern, "timed out waiting for");
+                assert_eq!(stall.line, "timed out waiting for db");
+            }
+            other => panic!("unexpected messages: {:?}", other),
+        }
+        // A line matching both is an error
+        match read(b"[Error] timed out waiting for db\n").as_slice() {
+            [WatchMessage::ErrorDetected(error)] => assert_eq!(error.pattern, "[Error"),
+            other => panic!("unexpected messages: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reader_reports_regex_captures() {
+        let config = Config {
+            error_patterns: Vec::new(),
+            regex_patterns: vec![r"ERR(?P<code>\d+) in (?P<module>\w+)".to_string()],
+            ..Config::default()
+        };
+        let monitor = ProcessMonitor::new(config);
+        let (tx, rx) = mpsc::channel();
+        let input = Cursor::new(b"ok\nERR42 in billing\n".to_vec());
+
+        let handle = monitor.spawn_reader(input, OutputStream::Stdout, tx);
+        handle.join().unwrap().unwrap();
+
+        let messages: Vec<WatchMessage> = rx.try_iter().collect();
+        match messages.as_slice() {
+            [WatchMessage::ErrorDetected(error)] => {
+                assert_eq!(error.line, "ERR42 in billing");
+                assert_eq!(error.captures["code"], "42");
+                assert_eq!(error.captures["module"], "billing");
+            }
+            other => panic!("unexpected messages: {:?}", other),
         }
     }
 }
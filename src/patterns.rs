@@ -0,0 +1,328 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Result, ServerError};
+
+/// An error pattern with its own settings, listed under `pattern_rules`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternRule {
+    pub pattern: String,
+    /// Seconds to wait before restarting when this pattern triggered; overrides `restart_delay`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_delay_secs: Option<u64>,
+    /// Command run to completion before restarting when this pattern triggered, e.g. `prisma generate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_command: Option<String>,
+    /// After this pattern triggered a restart, ignore it for this many seconds; other
+    /// patterns still restart the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u64>,
+    /// Treat `pattern` as a regular expression; its named groups are reported on a match
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub regex: bool,
+}
+
+/// `error_pattern`: one pattern (`"[Error"`) or a list of them (`["[Error", "panic:"]`).
+/// A single pattern is written back as a plain string, so older files keep their shape.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "OneOrMany", into = "OneOrMany")]
+pub struct ErrorPatterns(Vec<String>);
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<OneOrMany> for ErrorPatterns {
+    fn from(value: OneOrMany) -> Self {
+        match value {
+            OneOrMany::One(pattern) => Self(vec![pattern]),
+            OneOrMany::Many(patterns) => Self(patterns),
+        }
+    }
+}
+
+impl From<ErrorPatterns> for OneOrMany {
+    fn from(patterns: ErrorPatterns) -> Self {
+        match <[String; 1]>::try_from(patterns.0) {
+            Ok([pattern]) => OneOrMany::One(pattern),
+            Err(patterns) => OneOrMany::Many(patterns),
+        }
+    }
+}
+
+impl From<&str> for ErrorPatterns {
+    fn from(pattern: &str) -> Self {
+        Self(vec![pattern.to_string()])
+    }
+}
+
+impl ErrorPatterns {
+    /// Split the comma-separated list typed at the setup prompt
+    pub fn parse_list(input: &str) -> Self {
+        Self(
+            input
+                .split(',')
+                .map(str::trim)
+                .filter(|pattern| !pattern.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    /// The patterns that can match; an empty one would match every line
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0
+            .iter()
+            .map(String::as_str)
+            .filter(|pattern| !pattern.is_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+impl fmt::Display for ErrorPatterns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.iter().collect::<Vec<_>>().join(", "))
+    }
+}
+
+/// Names of the built-in pattern sets (`pattern_set`, `--patterns`)
+pub const PATTERN_SETS: [&str; 1] = ["universal"];
+
+/// Error markers common across ecosystems, for a project no pattern was configured for.
+/// Each is anchored or specific enough not to fire on ordinary log lines that merely
+/// mention errors, such as "0 errors" or "error handler registered".
+pub const UNIVERSAL_PATTERNS: [&str; 10] = [
+    // Compilers: rustc/cargo, gcc/clang, tsc-style `file:line: error: ...`
+    r"(^|: )error(\[E\d+\])?: ",
+    // npm and yarn v1
+    r"\bERR! ",
+    // Node, JavaScript in general: `TypeError: ...`, `Error: listen EADDRINUSE`
+    r"^(Uncaught )?([A-Z][A-Za-z]*)?Error: ",
+    r"^Traceback \(most recent call last\):",
+    // Go
+    r"^panic: ",
+    // Rust
+    r"thread '.*' panicked at",
+    r#"^Exception in thread ""#,
+    // Elixir
+    r"^\*\* \([A-Z]\w*(Error|Exception)\)",
+    r"\bFATAL\b",
+    // PHP
+    r"\bFatal error: ",
+];
+
+/// The regex patterns of a built-in set, by name
+pub fn pattern_set(name: &str) -> Result<&'static [&'static str]> {
+    match name {
+        "universal" => Ok(&UNIVERSAL_PATTERNS),
+        _ => Err(ServerError::ConfigError(format!(
+            "unknown pattern set '{}'; available: {}",
+            name,
+            PATTERN_SETS.join(", ")
+        ))),
+    }
+}
+
+/// Compile a regex error pattern, naming the setting it came from on failure
+pub fn compile_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| {
+        ServerError::ConfigError(format!("invalid regex pattern '{}': {}", pattern, e))
+    })
+}
+
+/// Named groups of the first match in `line` (empty for a regex without named groups),
+/// or `None` when the regex doesn't match. Groups that didn't take part are left out.
+pub fn named_captures(regex: &Regex, line: &str) -> Option<BTreeMap<String, String>> {
+    let captures = regex.captures(line)?;
+    Some(
+        regex
+            .capture_names()
+            .flatten()
+            .filter_map(|name| {
+                captures
+                    .name(name)
+                    .map(|value| (name.to_string(), value.as_str().to_string()))
+            })
+            .collect(),
+    )
+}
+
+/// `code=TS2304, file=app.ts` for reporting a match's captures
+pub fn format_captures(captures: &BTreeMap<String, String>) -> String {
+    captures
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Load error patterns from a shared patterns file
+pub fn load_patterns_file(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        ServerError::ConfigError(format!(
+            "Failed to read patterns file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    parse_patterns(&content).map_err(|e| {
+        ServerError::ConfigError(format!(
+            "Failed to parse patterns file {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Parse either a JSON array of strings or one pattern per line.
+/// In the line format, blank lines and lines starting with `#` are ignored.
+pub fn parse_patterns(content: &str) -> std::result::Result<Vec<String>, String> {
+    if content.trim_start().starts_with('[') {
+        return serde_json::from_str(content).map_err(|e| e.to_string());
+    }
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_universal_patterns_catch_each_ecosystem_but_not_chatter() {
+        let patterns: Vec<Regex> = UNIVERSAL_PATTERNS
+            .iter()
+            .map(|pattern| compile_regex(pattern).unwrap())
+            .collect();
+        let matches = |line: &str| patterns.iter().any(|regex| regex.is_match(line));
+
+        for line in [
+            "error[E0308]: mismatched types",
+            "error: could not compile `api` (bin \"api\") due to 2 previous errors",
+            "src/main.c:12:5: error: expected ';' before 'return'",
+            "npm ERR! code ELIFECYCLE",
+            "TypeError: Cannot read properties of undefined (reading 'id')",
+            "Uncaught ReferenceError: process is not defined",
+            "Error: listen EADDRINUSE: address already in use :::3000",
+            "Traceback (most recent call last):",
+            "panic: runtime error: invalid memory address or nil pointer dereference",
+            "thread 'main' panicked at src/main.rs:12:5:",
+            "Exception in thread \"main\" java.lang.NullPointerException",
+            "** (RuntimeError) boom",
+            "2024-05-01 12:00:00 FATAL: database \"app\" does not exist",
+            "PHP Fatal error:  Uncaught Error: Call to undefined function foo()",
+        ] {
+            assert!(matches(line), "should match: {}", line);
+        }
+        for line in [
+            "Compiled successfully: 0 errors, 0 warnings",
+            "GET /api/errors 200 3ms",
+            "Registered error handler middleware",
+            "INFO  server: listening on http://localhost:3000",
+            "  at Object.<anonymous> (/app/src/index.js:3:9)",
+            "tests: test_error_path ... ok",
+        ] {
+            assert!(!matches(line), "should not match: {}", line);
+        }
+    }
+
+    #[test]
+    fn test_error_pattern_is_a_string_or_a_list() {
+        let one: ErrorPatterns = serde_json::from_str(r#""[Error""#).unwrap();
+        assert_eq!(one, ErrorPatterns::from("[Error"));
+        assert_eq!(serde_json::to_string(&one).unwrap(), r#""[Error""#);
+
+        let many: ErrorPatterns = serde_json::from_str(r#"["[Error", "panic:", ""]"#).unwrap();
+        assert_eq!(many.iter().collect::<Vec<_>>(), ["[Error", "panic:"]);
+        assert_eq!(many.to_string(), "[Error, panic:");
+        assert_eq!(
+            serde_json::to_string(&many).unwrap(),
+            r#"["[Error","panic:",""]"#
+        );
+        assert!(serde_json::from_str::<ErrorPatterns>("3").is_err());
+
+        assert!(ErrorPatterns::from("").is_empty());
+        assert_eq!(
+            ErrorPatterns::parse_list(" [Error, panic: ,,FATAL "),
+            serde_json::from_str(r#"["[Error", "panic:", "FATAL"]"#).unwrap()
+        );
+        assert!(ErrorPatterns::parse_list(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_pattern_set_by_name() {
+        assert_eq!(pattern_set("universal").unwrap(), &UNIVERSAL_PATTERNS);
+        assert!(pattern_set("everything").is_err());
+    }
+
+    #[test]
+    fn test_parse_line_format_with_comments() {
+        let content = "# shared patterns\n[Error\n\n  panic:  \n# FATAL is too noisy\nERR!\n";
+        assert_eq!(
+            parse_patterns(content).unwrap(),
+            vec!["[Error", "panic:", "ERR!"]
+        );
+    }
+
+    #[test]
+    fn test_parse_json_array() {
+        let content = r#"["[Error", "Traceback"]"#;
+        assert_eq!(
+            parse_patterns(content).unwrap(),
+            vec!["[Error", "Traceback"]
+        );
+    }
+
+    #[test]
+    fn test_named_captures_are_extracted() {
+        let regex = compile_regex(r"error (?P<code>TS\d+) in (?P<file>\S+)").unwrap();
+        let captures = named_captures(&regex, "src/app.ts: error TS2304 in app.ts").unwrap();
+        assert_eq!(captures["code"], "TS2304");
+        assert_eq!(format_captures(&captures), "code=TS2304, file=app.ts");
+        assert_eq!(named_captures(&regex, "all good"), None);
+    }
+
+    #[test]
+    fn test_regex_without_named_captures_still_matches() {
+        let regex = compile_regex(r"panicked at .*\.rs").unwrap();
+        assert_eq!(
+            named_captures(&regex, "thread 'main' panicked at src/main.rs:3"),
+            Some(BTreeMap::new())
+        );
+        // Optional groups that didn't match are left out
+        let regex = compile_regex(r"FATAL(?: \[(?P<module>\w+)\])?").unwrap();
+        assert!(named_captures(&regex, "FATAL oops").unwrap().is_empty());
+        assert!(compile_regex("(unclosed").is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_json_array() {
+        assert!(parse_patterns("[\"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_missing_patterns_file() {
+        let result = load_patterns_file(Path::new("does-not-exist.patterns"));
+        match result {
+            Err(ServerError::ConfigError(msg)) => {
+                assert!(msg.contains("does-not-exist.patterns"))
+            }
+            other => panic!("expected ConfigError, got {:?}", other),
+        }
+    }
+}
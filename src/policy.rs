@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::exit_rules::ExitAction;
+use crate::monitor::RestartReason;
+use crate::server::RunOutcome;
+use crate::stats::SessionStats;
+
+/// What to do after an attempt ended with a restart reason
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// Start the server again after this delay
+    Restart { delay: Duration },
+    /// End the session; `message` says why, when the attempt's own report doesn't
+    Stop {
+        outcome: RunOutcome,
+        message: Option<String>,
+    },
+}
+
+/// Decides whether the session goes on after an attempt ends. The monitor reports what
+/// happened as a `RestartReason`; the policy only decides what to do about it, so it can be
+/// tested without spawning anything.
+pub trait RestartPolicy: Send {
+    fn decide(&self, reason: &RestartReason, stats: &SessionStats) -> Decision;
+}
+
+/// The policy described by dev-cli.json: `restart_on_nonzero_exit`, `exit_code_rules`,
+/// `max_restarts` and the restart delays
+pub struct DefaultPolicy<'a> {
+    config: &'a Config,
+}
+
+impl<'a> DefaultPolicy<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self { config }
+    }
+}
+
+impl RestartPolicy for DefaultPolicy<'_> {
+    fn decide(&self, reason: &RestartReason, stats: &SessionStats) -> Decision {
+        match reason {
+            // Reported by the monitor as it stopped the server
+            RestartReason::ReadyTimeout(_) => {
+                return Decision::Stop {
+                    outcome: RunOutcome::ReadyTimeout,
+                    message: None,
+                };
+            }
+            RestartReason::ConfigChanged => {
+                return Decision::Restart {
+                    delay: Duration::ZERO,
+                };
+            }
+            // A clean exit, so `max_restarts` doesn't count it
+            RestartReason::ExitRule(code) => {
+                return Decision::Restart {
+                    delay: self.config.exit_restart_delay(Some(*code)),
+                };
+            }
+            RestartReason::ExitFailure(code)
+                if self.config.exit_action(*code) == ExitAction::Stop =>
+            {
+                let setting = match self.config.exit_code_rule(*code) {
+                    Some(rule) => format!("exit_code_rules entry for {}", rule),
+                    None => "restart_on_nonzero_exit is false".to_string(),
+                };
+                return Decision::Stop {
+                    outcome: RunOutcome::ErrorExit,
+                    message: Some(format!(
+                        "Dev server {}; not restarting ({})",
+                        reason, setting
+                    )),
+                };
+            }
+            _ => {}
+        }
+
+        if let Some(max) = self.config.max_restarts
+            && stats.restarts >= max
+        {
+            let last_error = match reason {
+                RestartReason::ErrorDetected(error) | RestartReason::Stalled(error) => {
+                    error.line.clone()
+                }
+                other => other.to_string(),
+            };
+            return Decision::Stop {
+                outcome: RunOutcome::ErrorExit,
+                message: Some(stats.render_message(&self.config.max_restarts_message, &last_error)),
+            };
+        }
+
+        let delay = match reason {
+            RestartReason::ErrorDetected(error) => self.config.restart_delay_for(&error.pattern),
+            RestartReason::ExitFailure(code) => self.config.exit_restart_delay(*code),
+            _ => self.config.restart_delay,
+        };
+        Decision::Restart { delay }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_config::CliConfig;
+    use crate::monitor::ErrorMatch;
+    use std::collections::BTreeMap;
+
+    fn config(json: &str) -> Config {
+        let cli_config: CliConfig = serde_json::from_str(json).unwrap();
+        Config::new().with_cli_config(&cli_config).unwrap()
+    }
+
+    fn error(pattern: &str) -> RestartReason {
+        RestartReason::ErrorDetected(ErrorMatch {
+            pattern: pattern.to_string(),
+            line: format!("{} boom", pattern),
+            captures: BTreeMap::new(),
+            hits: 1,
+        })
+    }
+
+    #[test]
+    fn test_default_policy_restarts_with_the_configured_delay() {
+        let config = config(
+            r#"{"run_command": "npm run dev", "error_pattern": "[Error",
+                "pattern_rules": [{"pattern": "ECONNRESET", "restart_delay_secs": 0}]}"#,
+        );
+        let policy = DefaultPolicy::new(&config);
+        let stats = SessionStats::default();
+
+        assert_eq!(
+            policy.decide(&error("[Error"), &stats),
+            Decision::Restart {
+                delay: config.restart_delay
+            }
+        );
+        assert_eq!(
+            policy.decide(&error("ECONNRESET"), &stats),
+            Decision::Restart {
+                delay: Duration::ZERO
+            }
+        );
+        assert_eq!(
+            policy.decide(&RestartReason::ExitFailure(Some(1)), &stats),
+            Decision::Restart {
+                delay: config.restart_delay
+            }
+        );
+        assert_eq!(
+            policy.decide(&RestartReason::ReadyTimeout(Duration::from_secs(5)), &stats),
+            Decision::Stop {
+                outcome: RunOutcome::ReadyTimeout,
+                message: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_default_policy_stops_at_max_restarts_and_on_exit_when_told_to() {
+        let config = config(
+            r#"{"run_command": "npm run dev", "error_pattern": "[Error", "max_restarts": 2,
+                "max_restarts_message": "gave up after {count}: {last_error}",
+                "restart_on_nonzero_exit": false}"#,
+        );
+        let policy = DefaultPolicy::new(&config);
+        let mut stats = SessionStats::default();
+
+        assert!(matches!(
+            policy.decide(&error("[Error"), &stats),
+            Decision::Restart { .. }
+        ));
+        stats.restarts = 2;
+        assert_eq!(
+            policy.decide(&error("[Error"), &stats),
+            Decision::Stop {
+                outcome: RunOutcome::ErrorExit,
+                message: Some("gave up after 2: [Error boom".to_string())
+            }
+        );
+
+        // With restarts on exit turned off, an exit ends the session regardless of the count
+        stats.restarts = 0;
+        let Decision::Stop { outcome, message } =
+            policy.decide(&RestartReason::ExitFailure(Some(3)), &stats)
+        else {
+            panic!("expected the session to stop");
+        };
+        assert_eq!(outcome, RunOutcome::ErrorExit);
+        assert!(
+            message
+                .unwrap()
+                .contains("restart_on_nonzero_exit is false")
+        );
+    }
+
+    #[test]
+    fn test_exit_code_rules_decide_before_restart_on_nonzero_exit() {
+        let config = config(
+            r#"{"run_command": "npm run dev", "error_pattern": "[Error",
+                "restart_on_nonzero_exit": false,
+                "exit_code_rules": [
+                    {"code": 137, "action": "restart", "restart_delay_secs": 30},
+                    {"code": 0, "action": "restart"},
+                    {"code_range": [64, 78], "action": "stop"}]}"#,
+        );
+        let policy = DefaultPolicy::new(&config);
+        let stats = SessionStats::default();
+
+        assert_eq!(
+            policy.decide(&RestartReason::ExitFailure(Some(137)), &stats),
+            Decision::Restart {
+                delay: Duration::from_secs(30)
+            }
+        );
+        assert_eq!(
+            policy.decide(&RestartReason::ExitRule(0), &stats),
+            Decision::Restart {
+                delay: config.restart_delay
+            }
+        );
+        // Restarting after a clean exit isn't a crash restart
+        let worn_out = SessionStats {
+            restarts: 100,
+            ..SessionStats::default()
+        };
+        let mut limited = config.clone();
+        limited.max_restarts = Some(1);
+        assert!(matches!(
+            DefaultPolicy::new(&limited).decide(&RestartReason::ExitRule(0), &worn_out),
+            Decision::Restart { .. }
+        ));
+        assert_eq!(
+            policy.decide(&RestartReason::ExitFailure(Some(70)), &stats),
+            Decision::Stop {
+                outcome: RunOutcome::ErrorExit,
+                message: Some(
+                    "Dev server exited with code 70; not restarting (exit_code_rules entry for exit codes 64-78)"
+                        .to_string()
+                )
+            }
+        );
+        // No rule: restart_on_nonzero_exit still applies
+        let Decision::Stop { message, .. } =
+            policy.decide(&RestartReason::ExitFailure(Some(1)), &stats)
+        else {
+            panic!("expected the session to stop");
+        };
+        assert!(
+            message
+                .unwrap()
+                .contains("restart_on_nonzero_exit is false")
+        );
+    }
+}
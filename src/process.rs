@@ -1,8 +1,191 @@
-use std::process::{Child, Stdio};
+use std::io;
+use std::process::{Child, ExitStatus, Stdio};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::error::{Result, ServerError};
+use crate::exit_status;
+use crate::status::status;
+use crate::theme::{self, Icon};
+
+#[cfg(windows)]
+const CTRL_BREAK_EVENT: u32 = 1;
+
+#[cfg(windows)]
+unsafe extern "system" {
+    fn GenerateConsoleCtrlEvent(ctrl_event: u32, process_group_id: u32) -> i32;
+}
+
+/// Ask the process with the given PID to exit.
+/// `force` uses `taskkill /F /T` / `kill -9`; otherwise a graceful CTRL_BREAK / SIGTERM is sent.
+pub fn signal_pid(pid: u32, force: bool) {
+    #[cfg(windows)]
+    {
+        if force {
+            let _ = std::process::Command::new("taskkill")
+                .args(["/F", "/T", "/PID", &pid.to_string()])
+                .output();
+        } else {
+            // The dev command runs in its own process group (CREATE_NEW_PROCESS_GROUP),
+            // so its PID doubles as the group id for the console control event
+            unsafe {
+                GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid);
+            }
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        let signal = if force { "-9" } else { "-TERM" };
+        let _ = std::process::Command::new("kill")
+            .arg(signal)
+            .arg(pid.to_string())
+            .output();
+    }
+}
+
+/// Command line of a running process, or `None` if no process has this PID
+pub fn command_line(pid: u32) -> Option<String> {
+    #[cfg(windows)]
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "(Get-CimInstance Win32_Process -Filter \"ProcessId={}\").CommandLine",
+                pid
+            ),
+        ])
+        .output();
+    #[cfg(not(windows))]
+    let output = std::process::Command::new("ps")
+        .args(["-o", "args=", "-p", &pid.to_string()])
+        .output();
+
+    let output = output.ok()?;
+    let command_line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !command_line.is_empty()).then_some(command_line)
+}
+
+/// PIDs of all processes below `pid`, children before grandchildren.
+/// `taskkill /T` already covers the tree on Windows, so this is Unix only.
+#[cfg(not(windows))]
+pub fn descendant_pids(pid: u32) -> Vec<u32> {
+    let Ok(output) = std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid="])
+        .output()
+    else {
+        return Vec::new();
+    };
+    let pairs: Vec<(u32, u32)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some((fields.next()?.parse().ok()?, fields.next()?.parse().ok()?))
+        })
+        .collect();
+
+    let mut descendants = Vec::new();
+    let mut parents = vec![pid];
+    while let Some(parent) = parents.pop() {
+        for (child, _) in pairs.iter().filter(|(_, ppid)| *ppid == parent) {
+            descendants.push(*child);
+            parents.push(*child);
+        }
+    }
+    descendants
+}
+
+/// How long and how often to wait for a killed process to exit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShutdownWait {
+    /// Give up waiting after this long
+    pub timeout: Duration,
+    /// How often to check whether the process has exited
+    pub poll_interval: Duration,
+    /// Start printing progress once the wait has taken this long
+    pub warn_after: Duration,
+}
+
+impl Default for ShutdownWait {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            poll_interval: Duration::from_millis(50),
+            warn_after: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Which of the server's output streams dev reads; an uncaptured stream is inherited, so
+/// the server writes straight to the terminal with its own buffering and colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capture {
+    pub stdout: bool,
+    pub stderr: bool,
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self {
+            stdout: true,
+            stderr: true,
+        }
+    }
+}
+
+impl Capture {
+    /// How many streams dev reads
+    pub fn streams(&self) -> usize {
+        usize::from(self.stdout) + usize::from(self.stderr)
+    }
+}
+
+fn stdio(captured: bool) -> Stdio {
+    if captured {
+        Stdio::piped()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// Whether a stopped process is really gone, i.e. whether its port is free again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    Exited,
+    /// Waiting timed out and the process is still alive
+    StillRunning,
+}
+
+/// What a non-blocking wait found out about the child
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildState {
+    Running,
+    Exited(ExitStatus),
+    /// Something else (an init system, a container runtime) reaped the child first, so
+    /// its exit status is lost
+    ReapedElsewhere,
+}
+
+/// Interpret a `try_wait` result; "no child processes" (ECHILD) means the child was
+/// already reaped by someone else rather than that waiting failed
+fn child_state(result: io::Result<Option<ExitStatus>>) -> Result<ChildState> {
+    match result {
+        Ok(Some(status)) => Ok(ChildState::Exited(status)),
+        Ok(None) => Ok(ChildState::Running),
+        Err(e) if reaped_elsewhere(&e) => Ok(ChildState::ReapedElsewhere),
+        Err(e) => Err(ServerError::from(e)),
+    }
+}
+
+#[cfg(unix)]
+fn reaped_elsewhere(e: &io::Error) -> bool {
+    e.raw_os_error() == Some(libc::ECHILD)
+}
+
+#[cfg(not(unix))]
+fn reaped_elsewhere(_: &io::Error) -> bool {
+    false
+}
 
 /// Manages the lifecycle of a child process
 pub struct ProcessManager {
@@ -10,10 +193,15 @@ pub struct ProcessManager {
 }
 
 impl ProcessManager {
-    pub fn spawn(mut command: std::process::Command) -> Result<Self> {
+    pub fn spawn(command: std::process::Command) -> Result<Self> {
+        Self::spawn_capturing(command, Capture::default())
+    }
+
+    /// Spawn with only the streams in `capture` piped to dev; the others are inherited
+    pub fn spawn_capturing(mut command: std::process::Command, capture: Capture) -> Result<Self> {
         let child = command
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+            .stdout(stdio(capture.stdout))
+            .stderr(stdio(capture.stderr))
             .spawn()
             .map_err(|e| ServerError::ProcessStart(e.to_string()))?;
 
@@ -22,11 +210,12 @@ impl ProcessManager {
 
     pub fn spawn_with_pid_handle(
         mut command: std::process::Command,
+        capture: Capture,
         pid_handle: std::sync::Arc<std::sync::Mutex<Option<u32>>>,
     ) -> Result<Self> {
         let child = command
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+            .stdout(stdio(capture.stdout))
+            .stderr(stdio(capture.stderr))
             .spawn()
             .map_err(|e| ServerError::ProcessStart(e.to_string()))?;
         // Set the PID in the Arc
@@ -37,6 +226,10 @@ impl ProcessManager {
         Ok(Self { child })
     }
 
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
     pub fn take_stdout(&mut self) -> Option<std::process::ChildStdout> {
         self.child.stdout.take()
     }
@@ -45,22 +238,16 @@ impl ProcessManager {
         self.child.stderr.take()
     }
 
-    pub fn try_wait(&mut self) -> Result<Option<std::process::ExitStatus>> {
-        self.child.try_wait().map_err(ServerError::from)
+    pub fn try_wait(&mut self) -> Result<ChildState> {
+        child_state(self.child.try_wait())
     }
 
-    pub fn kill_and_wait(&mut self, timeout: Duration) -> Result<()> {
-        println!("🛑 Terminating process...");
+    pub fn kill_and_wait(&mut self, wait: &ShutdownWait) -> Result<ShutdownOutcome> {
+        status!("{} Terminating process...", theme::icon(Icon::Stop));
 
         // On Windows, try to terminate the process tree
         #[cfg(windows)]
-        {
-            let pid = self.child.id();
-            // Use taskkill to terminate the process tree
-            let _ = std::process::Command::new("taskkill")
-                .args(["/F", "/T", "/PID", &pid.to_string()])
-                .output();
-        }
+        signal_pid(self.child.id(), true);
 
         // Try to kill the direct child process
         if let Err(e) = self.child.kill() {
@@ -68,7 +255,7 @@ impl ProcessManager {
             match e.kind() {
                 std::io::ErrorKind::InvalidInput => {
                     // On Windows, InvalidInput may indicate the process has already exited
-                    println!("Process already exited");
+                    status!("Process already exited");
                 }
                 _ => {
                     eprintln!("Failed to kill process: {}", e);
@@ -77,23 +264,208 @@ impl ProcessManager {
             }
         }
 
+        self.wait_for_exit(wait)
+    }
+
+    /// Ask the process to exit, and force-kill it only if it hasn't within `wait.timeout`
+    pub fn stop_gracefully(&mut self, wait: &ShutdownWait) -> Result<ShutdownOutcome> {
+        status!("{} Asking process to exit...", theme::icon(Icon::Stop));
+        signal_pid(self.child.id(), false);
+        if self.wait_for_exit(wait)? == ShutdownOutcome::Exited {
+            return Ok(ShutdownOutcome::Exited);
+        }
+        self.kill_and_wait(wait)
+    }
+
+    /// Poll until the process exits or `wait.timeout` passes, printing progress every
+    /// second once `wait.warn_after` has passed
+    fn wait_for_exit(&mut self, wait: &ShutdownWait) -> Result<ShutdownOutcome> {
+        let start = Instant::now();
+        let mut next_progress = wait.warn_after;
+        loop {
+            match self.try_wait()? {
+                ChildState::Exited(status) => {
+                    status!(
+                        "{} Process terminated: {}",
+                        theme::icon(Icon::Success),
+                        exit_status::describe(&status).0
+                    );
+                    return Ok(ShutdownOutcome::Exited);
+                }
+                ChildState::ReapedElsewhere => {
+                    status!(
+                        "{} Process terminated (reaped by another process)",
+                        theme::icon(Icon::Success)
+                    );
+                    return Ok(ShutdownOutcome::Exited);
+                }
+                ChildState::Running => {
+                    let elapsed = start.elapsed();
+                    if elapsed >= wait.timeout {
+                        status!(
+                            "{} Process {} still running after {}s, giving up waiting",
+                            theme::icon(Icon::Warning),
+                            self.child.id(),
+                            elapsed.as_secs()
+                        );
+                        return Ok(ShutdownOutcome::StillRunning);
+                    }
+                    if elapsed >= next_progress {
+                        status!(
+                            "{} Waiting for shutdown... {}s",
+                            theme::icon(Icon::Waiting),
+                            elapsed.as_secs()
+                        );
+                        next_progress += Duration::from_secs(1);
+                    }
+                    thread::sleep(wait.poll_interval);
+                }
+            }
+        }
+    }
+
+    /// Ask the process to exit without ever force-killing it.
+    /// Waits until it exits, warning every `warn_after` that restarts are blocked.
+    pub fn terminate_gracefully(&mut self, warn_after: Duration) -> Result<()> {
+        status!(
+            "{} Asking process to exit (force kill disabled)...",
+            theme::icon(Icon::Stop)
+        );
+        signal_pid(self.child.id(), false);
+
         let start = Instant::now();
+        let mut next_warning = warn_after;
         loop {
-            match self.child.try_wait() {
-                Ok(Some(status)) => {
-                    println!("✅ Process terminated with status: {}", status);
+            match self.try_wait()? {
+                ChildState::Exited(status) => {
+                    status!(
+                        "{} Process terminated: {}",
+                        theme::icon(Icon::Success),
+                        exit_status::describe(&status).0
+                    );
                     return Ok(());
                 }
-                Ok(None) => {
-                    if start.elapsed() >= timeout {
-                        println!("⚠️ Process didn't terminate within timeout, giving up");
-                        return Ok(());
+                ChildState::ReapedElsewhere => {
+                    status!(
+                        "{} Process terminated (reaped by another process)",
+                        theme::icon(Icon::Success)
+                    );
+                    return Ok(());
+                }
+                ChildState::Running => {
+                    if start.elapsed() >= next_warning {
+                        status!(
+                            "{} Process still running after {}s; not force-killing (no_force_kill). Restart is blocked until it exits.",
+                            theme::icon(Icon::Warning),
+                            start.elapsed().as_secs()
+                        );
+                        next_warning += warn_after;
                     }
                     thread::sleep(Duration::from_millis(50));
-                    continue;
                 }
-                Err(e) => return Err(ServerError::ProcessManagement(e.to_string())),
             }
         }
     }
 }
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+    use crate::command::{CommandBuilder, CommandType};
+
+    #[test]
+    fn test_uncaptured_streams_are_inherited() {
+        let capture = Capture {
+            stdout: false,
+            stderr: true,
+        };
+        assert_eq!(capture.streams(), 1);
+        let mut process = ProcessManager::spawn_capturing(
+            CommandBuilder::build(CommandType::Hook("true".to_string())),
+            capture,
+        )
+        .unwrap();
+        // An inherited stream has no pipe for dev to read
+        assert!(process.take_stdout().is_none());
+        assert!(process.take_stderr().is_some());
+        process.kill_and_wait(&ShutdownWait::default()).unwrap();
+
+        let mut process =
+            ProcessManager::spawn(CommandBuilder::build(CommandType::Hook("true".to_string())))
+                .unwrap();
+        assert!(process.take_stdout().is_some());
+        assert!(process.take_stderr().is_some());
+        process.kill_and_wait(&ShutdownWait::default()).unwrap();
+    }
+
+    #[test]
+    fn test_child_reaped_elsewhere_is_not_an_error() {
+        use std::os::unix::process::ExitStatusExt;
+
+        assert_eq!(child_state(Ok(None)).unwrap(), ChildState::Running);
+        assert_eq!(
+            child_state(Ok(Some(ExitStatus::from_raw(0)))).unwrap(),
+            ChildState::Exited(ExitStatus::from_raw(0))
+        );
+        assert_eq!(
+            child_state(Err(io::Error::from_raw_os_error(libc::ECHILD))).unwrap(),
+            ChildState::ReapedElsewhere
+        );
+        assert!(child_state(Err(io::Error::from_raw_os_error(libc::EINTR))).is_err());
+    }
+
+    #[test]
+    fn test_shutdown_outcome_tells_gave_up_from_exited() {
+        let command = CommandBuilder::build(CommandType::Hook("exec sleep 5".to_string()));
+        let mut process = ProcessManager::spawn(command).unwrap();
+        let wait = ShutdownWait {
+            timeout: Duration::from_millis(100),
+            poll_interval: Duration::from_millis(10),
+            warn_after: Duration::from_secs(60),
+        };
+
+        assert_eq!(
+            process.wait_for_exit(&wait).unwrap(),
+            ShutdownOutcome::StillRunning
+        );
+        assert_eq!(
+            process.kill_and_wait(&ShutdownWait::default()).unwrap(),
+            ShutdownOutcome::Exited
+        );
+    }
+
+    #[test]
+    fn test_stop_gracefully_lets_the_process_clean_up() {
+        let marker = std::env::temp_dir().join("dev-cli-test-graceful-stop");
+        let _ = std::fs::remove_file(&marker);
+        let script = format!(
+            "trap 'touch {}; exit 0' TERM; while :; do sleep 0.05; done",
+            marker.display()
+        );
+        let mut process =
+            ProcessManager::spawn(CommandBuilder::build(CommandType::Hook(script))).unwrap();
+        thread::sleep(Duration::from_millis(200));
+
+        assert_eq!(
+            process.stop_gracefully(&ShutdownWait::default()).unwrap(),
+            ShutdownOutcome::Exited
+        );
+        assert!(marker.exists());
+        std::fs::remove_file(&marker).unwrap();
+
+        // One that ignores the request is still stopped
+        let mut stubborn = ProcessManager::spawn(CommandBuilder::build(CommandType::Hook(
+            "trap '' TERM; while :; do sleep 0.05; done".to_string(),
+        )))
+        .unwrap();
+        thread::sleep(Duration::from_millis(200));
+        let wait = ShutdownWait {
+            timeout: Duration::from_millis(300),
+            ..ShutdownWait::default()
+        };
+        assert_eq!(
+            stubborn.stop_gracefully(&wait).unwrap(),
+            ShutdownOutcome::Exited
+        );
+    }
+}
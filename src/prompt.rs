@@ -0,0 +1,247 @@
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::{Result, ServerError};
+use crate::theme::{self, Icon};
+
+/// Environment variable overriding how long interactive setup waits for input (`0` waits forever)
+pub const INPUT_TIMEOUT_ENV: &str = "DEV_INPUT_TIMEOUT_SECS";
+
+/// How long interactive setup waits for an answer before using defaults
+pub const DEFAULT_INPUT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Input timeout from `DEV_INPUT_TIMEOUT_SECS`, or the default when unset
+pub fn input_timeout_from_env() -> Option<Duration> {
+    parse_input_timeout(env::var(INPUT_TIMEOUT_ENV).ok().as_deref())
+}
+
+fn parse_input_timeout(value: Option<&str>) -> Option<Duration> {
+    let Some(value) = value else {
+        return Some(DEFAULT_INPUT_TIMEOUT);
+    };
+    match value.trim().parse::<u64>() {
+        Ok(0) => None,
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => {
+            eprintln!(
+                "{} Ignoring invalid {}={:?}; waiting {}s for input",
+                theme::icon(Icon::Warning),
+                INPUT_TIMEOUT_ENV,
+                value,
+                DEFAULT_INPUT_TIMEOUT.as_secs()
+            );
+            Some(DEFAULT_INPUT_TIMEOUT)
+        }
+    }
+}
+
+/// Answer to `Restart now? [Y/n/q]` (`confirm_restart`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartChoice {
+    Restart,
+    /// Leave the crash output on screen and restart when asked to
+    Wait,
+    Quit,
+}
+
+type Lines = Arc<Mutex<Receiver<io::Result<String>>>>;
+
+/// Reads lines on a background thread so an unattended terminal can't block forever.
+/// After the first timeout every further read returns `None` right away.
+pub struct LineReader {
+    lines: Lines,
+    timeout: Option<Duration>,
+    timed_out: bool,
+}
+
+impl LineReader {
+    /// Read from stdin. All readers share one stdin thread so a line typed after a
+    /// timeout goes to the next prompt instead of being swallowed.
+    pub fn stdin(timeout: Option<Duration>) -> Self {
+        static STDIN_LINES: OnceLock<Lines> = OnceLock::new();
+        let lines = STDIN_LINES.get_or_init(|| spawn_line_thread(io::BufReader::new(io::stdin())));
+        Self {
+            lines: Arc::clone(lines),
+            timeout,
+            timed_out: false,
+        }
+    }
+
+    #[cfg(test)]
+    fn from_reader<R: BufRead + Send + 'static>(reader: R, timeout: Option<Duration>) -> Self {
+        Self {
+            lines: spawn_line_thread(reader),
+            timeout,
+            timed_out: false,
+        }
+    }
+
+    /// Next line of input, or `None` at end of input or once the timeout has passed
+    pub fn read_line(&mut self) -> Result<Option<String>> {
+        if self.timed_out {
+            return Ok(None);
+        }
+
+        let lines = self.lines.lock().unwrap();
+        let line = match self.timeout {
+            Some(timeout) => match lines.recv_timeout(timeout) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.timed_out = true;
+                    println!();
+                    println!(
+                        "{} No input after {}s; continuing with defaults",
+                        theme::icon(Icon::PromptTimeout),
+                        timeout.as_secs()
+                    );
+                    return Ok(None);
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(None),
+            },
+            None => match lines.recv() {
+                Ok(line) => line,
+                Err(_) => return Ok(None),
+            },
+        };
+        line.map(Some)
+            .map_err(|e| ServerError::IoError(format!("Failed to read input: {}", e)))
+    }
+
+    /// Ask a yes/no question; an empty answer, end of input or a timeout picks `default`
+    pub fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let hint = if default { "[Y/n]" } else { "[y/N]" };
+        print!("{} {}: ", question, hint);
+        let _ = io::stdout().flush();
+        Ok(self
+            .read_line()?
+            .map_or(default, |answer| parse_answer(&answer, default)))
+    }
+
+    /// Ask whether to restart after an error; an empty answer, end of input or a timeout
+    /// restarts
+    pub fn restart_choice(&mut self) -> Result<RestartChoice> {
+        print!("Restart now? [Y/n/q]: ");
+        let _ = io::stdout().flush();
+        Ok(self.read_line()?.map_or(RestartChoice::Restart, |answer| {
+            parse_restart_choice(&answer)
+        }))
+    }
+}
+
+fn spawn_line_thread<R: BufRead + Send + 'static>(mut reader: R) -> Lines {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    if tx.send(Ok(line)).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    break;
+                }
+            }
+        }
+    });
+    Arc::new(Mutex::new(rx))
+}
+
+/// Ask a yes/no question on stdin. An empty answer (or no input at all) picks `default`.
+pub fn confirm(question: &str, default: bool) -> bool {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    print!("{} {}: ", question, hint);
+    let _ = io::stdout().flush();
+
+    match LineReader::stdin(None).read_line() {
+        Ok(Some(answer)) => parse_answer(&answer, default),
+        Ok(None) | Err(_) => default,
+    }
+}
+
+fn parse_answer(answer: &str, default: bool) -> bool {
+    match answer.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    }
+}
+
+fn parse_restart_choice(answer: &str) -> RestartChoice {
+    match answer.trim().to_ascii_lowercase().as_str() {
+        "n" | "no" => RestartChoice::Wait,
+        "q" | "quit" => RestartChoice::Quit,
+        _ => RestartChoice::Restart,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_input_timeout() {
+        assert_eq!(parse_input_timeout(None), Some(DEFAULT_INPUT_TIMEOUT));
+        assert_eq!(parse_input_timeout(Some("5")), Some(Duration::from_secs(5)));
+        assert_eq!(parse_input_timeout(Some("0")), None);
+        assert_eq!(
+            parse_input_timeout(Some("soon")),
+            Some(DEFAULT_INPUT_TIMEOUT)
+        );
+    }
+
+    #[test]
+    fn test_line_reader_reads_until_end_of_input() {
+        let mut reader = LineReader::from_reader(io::Cursor::new("pnpm dev\n"), None);
+        assert_eq!(reader.read_line().unwrap().as_deref(), Some("pnpm dev\n"));
+        assert_eq!(reader.read_line().unwrap(), None);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_line_reader_times_out() {
+        let (_writer, pipe) = std::os::unix::net::UnixStream::pair().unwrap();
+        let mut reader =
+            LineReader::from_reader(io::BufReader::new(pipe), Some(Duration::from_millis(20)));
+        assert_eq!(reader.read_line().unwrap(), None);
+        assert_eq!(reader.read_line().unwrap(), None);
+    }
+
+    #[test]
+    fn test_line_reader_confirm_defaults_without_an_answer() {
+        let mut reader = LineReader::from_reader(io::Cursor::new("y\n\n"), None);
+        assert!(reader.confirm("Test this command now?", false).unwrap());
+        assert!(!reader.confirm("Test this command now?", false).unwrap());
+        // End of input
+        assert!(reader.confirm("Test this command now?", true).unwrap());
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        assert!(parse_answer("y\n", false));
+        assert!(parse_answer("YES", false));
+        assert!(!parse_answer("n", true));
+        assert!(parse_answer("", true));
+        assert!(!parse_answer("maybe", false));
+    }
+
+    #[test]
+    fn test_restart_choice_restarts_unless_told_otherwise() {
+        assert_eq!(parse_restart_choice("n\n"), RestartChoice::Wait);
+        assert_eq!(parse_restart_choice("Q"), RestartChoice::Quit);
+        assert_eq!(parse_restart_choice("y"), RestartChoice::Restart);
+
+        let mut reader = LineReader::from_reader(io::Cursor::new("quit\n\n"), None);
+        assert_eq!(reader.restart_choice().unwrap(), RestartChoice::Quit);
+        assert_eq!(reader.restart_choice().unwrap(), RestartChoice::Restart);
+        // End of input
+        assert_eq!(reader.restart_choice().unwrap(), RestartChoice::Restart);
+    }
+}
@@ -0,0 +1,344 @@
+use std::cell::Cell;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::backoff::Backoff;
+use crate::command::{CommandBuilder, CommandType};
+use crate::config::Config;
+use crate::error::{Result, ServerError};
+use crate::monitor::WatchMessage;
+use crate::process;
+use crate::status::status;
+use crate::theme::{self, Icon};
+
+/// Pause before the first `ready_command` retry when `ready_command_interval_ms` isn't set
+pub const DEFAULT_READY_COMMAND_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long one run of `ready_command` may take when `ready_command_timeout_secs` isn't set
+pub const DEFAULT_READY_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The pause between retries doubles up to this, unless the interval is longer
+const READY_COMMAND_MAX_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often a running probe or a pause checks whether the attempt is over
+const READY_COMMAND_TICK: Duration = Duration::from_millis(20);
+
+/// A command run after each spawn until it exits with 0, meaning the server is ready
+/// (`ready_command`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadyCommand {
+    pub command: String,
+    pub interval: Duration,
+    /// A run still going after this long counts as not ready and is killed
+    pub timeout: Duration,
+}
+
+/// Stops the `ready_command` poller when dropped at the end of an attempt
+pub struct ReadyCommandWatch {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for ReadyCommandWatch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+impl ReadyCommand {
+    /// Run the command, backing off between failures, until it succeeds once; then send
+    /// `ReadyDetected` and stop
+    pub fn watch(&self, tx: Sender<WatchMessage>) -> ReadyCommandWatch {
+        let stop = Arc::new(AtomicBool::new(false));
+        let watch = ReadyCommandWatch {
+            stop: Arc::clone(&stop),
+        };
+        let probe = self.clone();
+        thread::spawn(move || {
+            let mut backoff = Backoff::new(
+                probe.interval,
+                probe.interval.max(READY_COMMAND_MAX_INTERVAL),
+            );
+            while !stop.load(Ordering::SeqCst) {
+                if probe.run(&stop) {
+                    let _ = tx.send(WatchMessage::ReadyDetected);
+                    return;
+                }
+                let resume_at = Instant::now() + backoff.next_delay();
+                while Instant::now() < resume_at && !stop.load(Ordering::SeqCst) {
+                    thread::sleep(READY_COMMAND_TICK);
+                }
+            }
+        });
+        watch
+    }
+
+    /// One run of the probe: whether it exited with 0 within the timeout
+    fn run(&self, stop: &AtomicBool) -> bool {
+        let mut child = match CommandBuilder::build(CommandType::Hook(self.command.clone()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!(
+                    "{} ready_command failed to start: {}",
+                    theme::icon(Icon::Warning),
+                    e
+                );
+                return false;
+            }
+        };
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return status.success(),
+                Ok(None) if Instant::now() < deadline && !stop.load(Ordering::SeqCst) => {
+                    thread::sleep(READY_COMMAND_TICK);
+                }
+                _ => {
+                    // Killing `sh` alone would leave what the probe started running, so
+                    // its tree goes first, deepest first
+                    #[cfg(not(windows))]
+                    for pid in process::descendant_pids(child.id()).into_iter().rev() {
+                        process::signal_pid(pid, true);
+                    }
+                    #[cfg(windows)]
+                    process::signal_pid(child.id(), true);
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Announces the first time the dev server becomes ready so external tools can synchronize on it
+pub struct ReadyAnnouncer {
+    marker: String,
+    print_marker: bool,
+    ready_file: Option<PathBuf>,
+    on_ready: Option<String>,
+    announced: Cell<bool>,
+}
+
+impl ReadyAnnouncer {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            marker: config.ready_marker.clone(),
+            print_marker: true,
+            ready_file: config.ready_file.clone(),
+            on_ready: config.on_ready.clone(),
+            announced: Cell::new(false),
+        }
+    }
+
+    /// Whether the marker is printed to stdout; the ready file still gets it
+    pub fn set_print_marker(&mut self, print_marker: bool) {
+        self.print_marker = print_marker;
+    }
+
+    /// Remove a ready file left behind by a previous session so pollers don't see stale readiness
+    pub fn clear_stale(&self) -> Result<()> {
+        if let Some(path) = &self.ready_file
+            && path.exists()
+        {
+            fs::remove_file(path).map_err(|e| {
+                ServerError::IoError(format!("Failed to remove {}: {}", path.display(), e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Emit the ready marker, write the ready file and start the on_ready command.
+    /// Only the first call of a session does anything; returns whether it announced.
+    pub fn announce(&self) -> Result<bool> {
+        if self.announced.replace(true) {
+            return Ok(false);
+        }
+
+        if self.print_marker && !self.marker.is_empty() {
+            println!("{}", self.marker);
+        }
+
+        if let Some(path) = &self.ready_file {
+            fs::write(path, format!("{}\n", self.marker)).map_err(|e| {
+                ServerError::IoError(format!("Failed to write {}: {}", path.display(), e))
+            })?;
+        }
+
+        if let Some(on_ready) = &self.on_ready {
+            status!(
+                "{} Running on_ready command: {}",
+                theme::icon(Icon::Hook),
+                on_ready
+            );
+            let mut child = CommandBuilder::build(CommandType::Hook(on_ready.clone()))
+                .spawn()
+                .map_err(|e| ServerError::ProcessStart(format!("on_ready command: {}", e)))?;
+            // Nothing waits for it, but it is reaped once it exits instead of lingering
+            // as a zombie for the rest of the session
+            thread::spawn(move || {
+                let _ = child.wait();
+            });
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_announce_only_once() {
+        let ready_file = std::env::temp_dir().join("dev-cli-test-ready-once");
+        let config = Config {
+            ready_file: Some(ready_file.clone()),
+            ..Config::default()
+        };
+        let announcer = ReadyAnnouncer::new(&config);
+
+        assert!(announcer.announce().unwrap());
+        assert_eq!(fs::read_to_string(&ready_file).unwrap(), "DEV_READY\n");
+        assert!(!announcer.announce().unwrap());
+
+        fs::remove_file(&ready_file).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_on_ready_command_is_reaped() {
+        let pid_file = std::env::temp_dir().join("dev-cli-test-on-ready-pid");
+        let _ = fs::remove_file(&pid_file);
+        let config = Config {
+            on_ready: Some(format!("echo $$ > '{}'", pid_file.display())),
+            ..Config::default()
+        };
+        let mut announcer = ReadyAnnouncer::new(&config);
+        announcer.set_print_marker(false);
+        assert!(announcer.announce().unwrap());
+
+        // Gone from the process table, not just exited
+        let listed = |pid: &str| {
+            std::process::Command::new("ps")
+                .args(["-o", "stat=", "-p", pid])
+                .output()
+                .is_ok_and(|output| !output.stdout.trim_ascii().is_empty())
+        };
+        let started = Instant::now();
+        let pid = loop {
+            if let Ok(pid) = fs::read_to_string(&pid_file)
+                && !pid.trim().is_empty()
+            {
+                break pid.trim().to_string();
+            }
+            assert!(started.elapsed() < Duration::from_secs(5), "never ran");
+            thread::sleep(Duration::from_millis(20));
+        };
+        while listed(&pid) {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "on_ready {} was never reaped",
+                pid
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+        fs::remove_file(&pid_file).unwrap();
+    }
+
+    #[test]
+    fn test_clear_stale_ready_file() {
+        let ready_file = std::env::temp_dir().join("dev-cli-test-ready-stale");
+        fs::write(&ready_file, "DEV_READY\n").unwrap();
+        let config = Config {
+            ready_file: Some(ready_file.clone()),
+            ..Config::default()
+        };
+
+        ReadyAnnouncer::new(&config).clear_stale().unwrap();
+        assert!(!ready_file.exists());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_ready_command_reports_ready_once_after_failures() {
+        let counter = std::env::temp_dir().join("dev-cli-test-ready-command");
+        let _ = fs::remove_file(&counter);
+        let probe = ReadyCommand {
+            // Fails twice, then succeeds on every later run
+            command: format!(
+                "n=$(cat {0} 2>/dev/null || echo 0); n=$((n + 1)); echo $n > {0}; [ $n -ge 3 ]",
+                counter.display()
+            ),
+            interval: Duration::from_millis(10),
+            timeout: DEFAULT_READY_COMMAND_TIMEOUT,
+        };
+        let (tx, rx) = mpsc::channel();
+        let _watch = probe.watch(tx);
+
+        // The poller drops its sender once it has reported, which ends the iteration
+        let messages: Vec<WatchMessage> = rx.iter().collect();
+        assert!(matches!(messages.as_slice(), [WatchMessage::ReadyDetected]));
+        assert_eq!(fs::read_to_string(&counter).unwrap().trim(), "3");
+        fs::remove_file(&counter).unwrap();
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_ready_command_that_hangs_is_killed_and_not_ready() {
+        let probe = ReadyCommand {
+            command: "exec sleep 5".to_string(),
+            interval: DEFAULT_READY_COMMAND_INTERVAL,
+            timeout: Duration::from_millis(100),
+        };
+        let started = Instant::now();
+        assert!(!probe.run(&AtomicBool::new(false)));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hanging_ready_command_is_killed_with_what_it_started() {
+        let pid_file = std::env::temp_dir().join("dev-cli-test-ready-command-tree");
+        let _ = fs::remove_file(&pid_file);
+        let probe = ReadyCommand {
+            command: format!("sleep 5 & echo $! > '{}'; wait", pid_file.display()),
+            interval: DEFAULT_READY_COMMAND_INTERVAL,
+            timeout: Duration::from_millis(300),
+        };
+        assert!(!probe.run(&AtomicBool::new(false)));
+
+        // A zombie waiting for its new parent to reap it has stopped all the same
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let running = || {
+            std::process::Command::new("ps")
+                .args(["-o", "stat=", "-p", pid.trim()])
+                .output()
+                .is_ok_and(|output| {
+                    let stat = String::from_utf8_lossy(&output.stdout);
+                    !stat.trim().is_empty() && !stat.trim().starts_with('Z')
+                })
+        };
+        let started = Instant::now();
+        while running() {
+            assert!(
+                started.elapsed() < Duration::from_secs(2),
+                "the probe's sleep {} survived",
+                pid.trim()
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+        fs::remove_file(&pid_file).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use crate::error::{Result, ServerError};
+use crate::events::LifecycleEvent;
+use crate::theme::{self, Icon};
+
+/// Version of the `--record` file format, written in its first line.
+/// Bump when records are removed or fields change meaning; adding optional fields keeps it.
+pub const RECORD_FORMAT_VERSION: u32 = 1;
+
+/// One line of a `--record` file
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    /// First line: what was run and what the monitor looked for
+    Session {
+        version: u32,
+        dev_version: &'static str,
+        run_command: String,
+        error_patterns: Vec<String>,
+    },
+    /// A line the server printed, undecorated
+    Line {
+        t_ms: u128,
+        stream: &'static str,
+        text: String,
+    },
+    /// A lifecycle event, as sent to `event_command`
+    Event { t_ms: u128, event: LifecycleEvent },
+}
+
+/// Cheap handle for adding to the recording; does nothing without `--record`
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    tx: Option<(Sender<Record>, Instant)>,
+}
+
+impl Recorder {
+    pub fn line(&self, stream: &'static str, text: &str) {
+        if let Some((tx, started)) = &self.tx {
+            let _ = tx.send(Record::Line {
+                t_ms: started.elapsed().as_millis(),
+                stream,
+                text: text.to_string(),
+            });
+        }
+    }
+
+    pub fn event(&self, event: &LifecycleEvent) {
+        if let Some((tx, started)) = &self.tx {
+            let _ = tx.send(Record::Event {
+                t_ms: started.elapsed().as_millis(),
+                event: event.clone(),
+            });
+        }
+    }
+}
+
+/// Background thread that writes the recording, so the output readers only pay for a send
+pub struct RecordWorker {
+    handle: JoinHandle<()>,
+}
+
+impl RecordWorker {
+    /// Wait for queued records to be written. All recorders must be dropped first.
+    pub fn finish(self) {
+        let _ = self.handle.join();
+    }
+}
+
+/// Start recording to `path` (truncated), beginning with `session`, or return a no-op
+/// recorder when there is no path
+pub fn start(path: Option<&str>, session: Record) -> Result<(Recorder, Option<RecordWorker>)> {
+    let Some(path) = path else {
+        return Ok((Recorder::default(), None));
+    };
+    let file = File::create(path)
+        .map_err(|e| ServerError::IoError(format!("Failed to create {}: {}", path, e)))?;
+
+    let (tx, rx) = mpsc::channel::<Record>();
+    tx.send(session).ok();
+    let handle = thread::spawn(move || {
+        let mut out = BufWriter::new(file);
+        while let Ok(record) = rx.recv() {
+            // Flush whenever the queue runs dry: cheap under load, and little is lost
+            // when Ctrl+C ends dev without waiting for this thread
+            let result = std::iter::once(record)
+                .chain(rx.try_iter())
+                .try_for_each(|record| write_record(&mut out, &record))
+                .and_then(|()| out.flush());
+            if let Err(e) = result {
+                eprintln!(
+                    "{} Failed to write the recording, no longer recording: {}",
+                    theme::icon(Icon::Warning),
+                    e
+                );
+                break;
+            }
+        }
+    });
+
+    Ok((
+        Recorder {
+            tx: Some((tx, Instant::now())),
+        },
+        Some(RecordWorker { handle }),
+    ))
+}
+
+fn write_record(out: &mut impl Write, record: &Record) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventKind;
+    use std::fs;
+
+    #[test]
+    fn test_recording_is_json_lines_starting_with_the_session() {
+        let path = std::env::temp_dir().join("dev-cli-test-record.jsonl");
+        let session = Record::Session {
+            version: RECORD_FORMAT_VERSION,
+            dev_version: env!("CARGO_PKG_VERSION"),
+            run_command: "npm run dev".to_string(),
+            error_patterns: vec!["[Error".to_string()],
+        };
+        let (recorder, worker) = start(path.to_str(), session).unwrap();
+        recorder.line("stdout", "listening on 3000");
+        recorder.event(&LifecycleEvent::new(EventKind::Restart, 1));
+        drop(recorder);
+        worker.unwrap().finish();
+
+        let records: Vec<serde_json::Value> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["type"], "session");
+        assert_eq!(records[0]["version"], RECORD_FORMAT_VERSION);
+        assert_eq!(records[0]["run_command"], "npm run dev");
+        assert_eq!(records[1]["type"], "line");
+        assert_eq!(records[1]["stream"], "stdout");
+        assert_eq!(records[1]["text"], "listening on 3000");
+        assert!(records[1]["t_ms"].is_u64());
+        assert_eq!(records[2]["type"], "event");
+        assert_eq!(records[2]["event"]["event"], "restart");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_recorder_without_path_is_a_no_op() {
+        let (recorder, worker) = start(
+            None,
+            Record::Line {
+                t_ms: 0,
+                stream: "stdout",
+                text: String::new(),
+            },
+        )
+        .unwrap();
+        assert!(worker.is_none());
+        recorder.line("stdout", "ignored");
+    }
+}
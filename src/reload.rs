@@ -0,0 +1,318 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::cli_config::{CliConfig, ConfigDiff};
+use crate::theme::{self, Icon};
+
+/// How often the configuration file is checked for changes
+pub const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A changed configuration picked up by the watcher
+#[derive(Debug, Clone)]
+pub struct ConfigReload {
+    pub config: CliConfig,
+    pub diff: ConfigDiff,
+    /// `patterns_file` or `regex_patterns_file` was edited, even if the config itself wasn't
+    pub pattern_files_changed: bool,
+}
+
+/// Reports a burst of file changes once, after the files have stopped changing for `quiet`
+#[derive(Debug)]
+pub struct Debouncer {
+    quiet: Duration,
+    last_change: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            last_change: None,
+        }
+    }
+
+    /// Record one poll; returns true when a burst of changes has settled
+    pub fn observe(&mut self, changed: bool, now: Instant) -> bool {
+        if changed {
+            self.last_change = Some(now);
+        }
+        match self.last_change {
+            Some(last) if now.duration_since(last) >= self.quiet => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Merge reloads that piled up, e.g. while the server was restarting, into one
+pub fn coalesce(reloads: impl IntoIterator<Item = ConfigReload>) -> Option<ConfigReload> {
+    reloads.into_iter().reduce(|earlier, later| ConfigReload {
+        diff: earlier.diff.followed_by(&later.diff),
+        config: later.config,
+        pattern_files_changed: earlier.pattern_files_changed || later.pattern_files_changed,
+    })
+}
+
+/// Poll the configuration file and the pattern files it names, and send every valid change
+/// once they have been quiet for `debounce`, so a burst of writes becomes a single reload.
+/// Invalid edits are reported and skipped so a half-saved file doesn't stop the server.
+/// `overrides` puts back what the command line replaced in the file, as at startup.
+/// The thread ends once the receiver is dropped.
+pub fn watch(
+    path: PathBuf,
+    profile: Option<String>,
+    initial: CliConfig,
+    interval: Duration,
+    debounce: Duration,
+    overrides: impl Fn(CliConfig) -> CliConfig + Send + 'static,
+) -> Receiver<ConfigReload> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut current = initial;
+        let mut last_modified = modified_time(&path);
+        let mut last_pattern_files = pattern_files_modified(&current);
+        let mut pattern_files_changed = false;
+        let mut debouncer = Debouncer::new(debounce);
+        loop {
+            thread::sleep(interval);
+
+            let modified = modified_time(&path);
+            let pattern_files = pattern_files_modified(&current);
+            pattern_files_changed |= pattern_files != last_pattern_files;
+            let changed = modified != last_modified || pattern_files != last_pattern_files;
+            last_modified = modified;
+            last_pattern_files = pattern_files;
+            if !debouncer.observe(changed, Instant::now()) {
+                continue;
+            }
+
+            let config = match CliConfig::read_from_file(&path).and_then(|config| {
+                let mut config = config
+                    .with_profile(profile.as_deref(), &path)?
+                    .resolve_working_dir(&path)?;
+                // The environment still wins over the edited file, as it did at startup
+                config.apply_env_overrides(|name| env::var(name).ok());
+                let config = overrides(config);
+                config.validate()?;
+                Ok(config)
+            }) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!(
+                        "{} Ignoring invalid {}: {}",
+                        theme::icon(Icon::Warning),
+                        path.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            let diff = current.diff(&config);
+            if diff.is_empty() && !pattern_files_changed {
+                continue;
+            }
+            current = config.clone();
+            // The reload may point at other pattern files
+            last_pattern_files = pattern_files_modified(&current);
+            let reload = ConfigReload {
+                config,
+                diff,
+                pattern_files_changed,
+            };
+            pattern_files_changed = false;
+            if tx.send(reload).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn pattern_files_modified(config: &CliConfig) -> Vec<Option<SystemTime>> {
+    [&config.patterns_file, &config.regex_patterns_file]
+        .into_iter()
+        .flatten()
+        .map(|file| modified_time(Path::new(file)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_reports_changed_fields() {
+        let path = std::env::temp_dir().join("dev-cli-test-watch.json");
+        let initial = CliConfig::default();
+        fs::write(&path, serde_json::to_string(&initial).unwrap()).unwrap();
+
+        let rx = watch(
+            path.clone(),
+            None,
+            initial.clone(),
+            Duration::from_millis(20),
+            Duration::ZERO,
+            |config| config,
+        );
+        thread::sleep(Duration::from_millis(50));
+
+        let changed = CliConfig {
+            error_pattern: "FATAL".into(),
+            ..initial
+        };
+        fs::write(&path, serde_json::to_string(&changed).unwrap()).unwrap();
+
+        let reload = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(reload.config.error_pattern, "FATAL".into());
+        assert_eq!(reload.diff.field_names(), vec!["error_pattern"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_watch_keeps_command_line_overrides() {
+        let path = std::env::temp_dir().join("dev-cli-test-watch-overrides.json");
+        let file = CliConfig::default();
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        // As with --patterns universal
+        let with_patterns = |config| CliConfig {
+            pattern_set: Some("universal".to_string()),
+            ..config
+        };
+
+        let rx = watch(
+            path.clone(),
+            None,
+            with_patterns(file.clone()),
+            Duration::from_millis(20),
+            Duration::ZERO,
+            with_patterns,
+        );
+        thread::sleep(Duration::from_millis(50));
+
+        let changed = CliConfig {
+            error_pattern: "FATAL".into(),
+            ..file
+        };
+        fs::write(&path, serde_json::to_string(&changed).unwrap()).unwrap();
+
+        let reload = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(reload.diff.field_names(), vec!["error_pattern"]);
+        let regex_patterns = reload.config.regex_patterns().unwrap();
+        for pattern in crate::patterns::pattern_set("universal").unwrap() {
+            assert!(regex_patterns.contains(&pattern.to_string()));
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_watch_reports_edited_pattern_files() {
+        let path = std::env::temp_dir().join("dev-cli-test-watch-patterns.json");
+        let patterns = std::env::temp_dir().join("dev-cli-test-watch.patterns");
+        fs::write(&patterns, "FATAL\n").unwrap();
+        let initial = CliConfig {
+            patterns_file: Some(patterns.display().to_string()),
+            ..CliConfig::default()
+        };
+        fs::write(&path, serde_json::to_string(&initial).unwrap()).unwrap();
+
+        let rx = watch(
+            path.clone(),
+            None,
+            initial.clone(),
+            Duration::from_millis(20),
+            Duration::ZERO,
+            |config| config,
+        );
+        thread::sleep(Duration::from_millis(50));
+        // Some filesystems only keep whole seconds
+        let modified = modified_time(&patterns).unwrap();
+        fs::write(&patterns, "FATAL\nPANIC\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&patterns)
+            .unwrap()
+            .set_modified(modified + Duration::from_secs(2))
+            .unwrap();
+
+        let reload = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(reload.pattern_files_changed);
+        assert!(reload.diff.is_empty());
+        assert!(
+            reload
+                .config
+                .error_patterns()
+                .unwrap()
+                .contains(&"PANIC".to_string())
+        );
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&patterns).unwrap();
+    }
+
+    #[test]
+    fn test_burst_of_changes_settles_once() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(300));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // A checkout touching the file every 100ms keeps postponing the reload
+        for ms in [0, 100, 200, 300, 400] {
+            assert!(!debouncer.observe(true, at(ms)));
+        }
+        assert!(!debouncer.observe(false, at(600)));
+        assert!(debouncer.observe(false, at(700)));
+        // Nothing pending afterwards
+        assert!(!debouncer.observe(false, at(1500)));
+    }
+
+    #[test]
+    fn test_pending_reloads_coalesce_into_one() {
+        let base = CliConfig::default();
+        let first = CliConfig {
+            run_command: "npm run dev".to_string(),
+            error_pattern: "FATAL".into(),
+            ..base.clone()
+        };
+        let second = CliConfig {
+            error_pattern: base.error_pattern.clone(),
+            ready_pattern: Some("listening".to_string()),
+            ..first.clone()
+        };
+        let reloads = vec![
+            ConfigReload {
+                diff: base.diff(&first),
+                config: first.clone(),
+                pattern_files_changed: true,
+            },
+            ConfigReload {
+                diff: first.diff(&second),
+                config: second.clone(),
+                pattern_files_changed: false,
+            },
+        ];
+
+        let merged = coalesce(reloads).unwrap();
+        assert_eq!(merged.config.ready_pattern.as_deref(), Some("listening"));
+        // error_pattern went back to its original value, so only two fields changed overall
+        assert_eq!(
+            merged.diff.field_names(),
+            vec!["ready_pattern", "run_command"]
+        );
+        assert_eq!(merged.diff, base.diff(&second));
+        assert!(merged.pattern_files_changed);
+        assert!(coalesce(Vec::new()).is_none());
+    }
+}
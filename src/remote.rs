@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// Exit code ssh uses when the connection itself failed or dropped
+pub const SSH_CONNECTION_LOST: i32 = 255;
+
+/// Host that `run_command` is executed on over SSH
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Private key passed to `ssh -i`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
+
+impl RemoteConfig {
+    /// `user@host`, or just the host when no user is configured
+    pub fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    /// Arguments for `ssh` that run `command` on the remote host.
+    ///
+    /// `-tt` gives the remote command a terminal: when the local ssh is killed, sshd
+    /// closes that terminal and the remote process group receives SIGHUP, so stopping
+    /// the local process also stops the remote server. Keepalives turn a silent network
+    /// drop into ssh exiting with code 255, which the monitor treats as a restart.
+    pub fn ssh_args(&self, command: &str) -> Vec<String> {
+        let mut args = vec![
+            "-tt".to_string(),
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            "ServerAliveInterval=5".to_string(),
+            "-o".to_string(),
+            "ServerAliveCountMax=3".to_string(),
+        ];
+        if let Some(key) = &self.key {
+            args.push("-i".to_string());
+            args.push(key.clone());
+        }
+        if let Some(port) = self.port {
+            args.push("-p".to_string());
+            args.push(port.to_string());
+        }
+        args.push(self.destination());
+        args.push(command.to_string());
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_args() {
+        let remote = RemoteConfig {
+            host: "devbox".to_string(),
+            user: Some("me".to_string()),
+            key: Some("~/.ssh/devbox".to_string()),
+            port: Some(2222),
+        };
+        let args = remote.ssh_args("cd app && npm run dev");
+        assert_eq!(args[0], "-tt");
+        assert_eq!(
+            &args[args.len() - 6..],
+            [
+                "-i",
+                "~/.ssh/devbox",
+                "-p",
+                "2222",
+                "me@devbox",
+                "cd app && npm run dev"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_destination_without_user() {
+        let remote: RemoteConfig = serde_json::from_str(r#"{"host": "devbox"}"#).unwrap();
+        assert_eq!(remote.destination(), "devbox");
+    }
+}
@@ -0,0 +1,357 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf, absolute};
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::cli_config::CliConfig;
+use crate::command::{CommandBuilder, CommandType};
+use crate::exit_status;
+use crate::process;
+use crate::reload::Debouncer;
+use crate::secrets::ResolvedEnv;
+use crate::status::status;
+use crate::theme::{self, Icon};
+use crate::workdir::FreshWorkdir;
+
+/// How often the watched files are scanned for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Directories never scanned for changes, besides hidden ones: build output and dependencies
+pub const IGNORED_DIRS: [&str; 2] = ["target", "node_modules"];
+
+/// What `dev` does with `run_command` (`mode` in dev-cli.json)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunMode {
+    /// Keep a long-lived server running and restart it when it fails
+    #[default]
+    Server,
+    /// Run the command to completion, then again whenever a watched file changes
+    Command,
+}
+
+impl RunMode {
+    pub fn is_server(&self) -> bool {
+        *self == RunMode::Server
+    }
+}
+
+/// Modification time and size of every file under the watched paths
+type Snapshot = HashMap<PathBuf, (SystemTime, u64)>;
+
+/// Detects file changes under a set of paths by comparing snapshots
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    ignored: Vec<PathBuf>,
+    snapshot: Snapshot,
+}
+
+impl FileWatcher {
+    /// Changes to the `ignored` files never count
+    pub fn new(paths: Vec<PathBuf>, ignored: &[PathBuf]) -> Self {
+        let ignored: Vec<PathBuf> = ignored.iter().filter_map(|p| absolute(p).ok()).collect();
+        let snapshot = take_snapshot(&paths, &ignored);
+        Self {
+            paths,
+            ignored,
+            snapshot,
+        }
+    }
+
+    /// Rescan; returns whether any file was added, removed or modified since the last scan
+    pub fn poll(&mut self) -> bool {
+        let snapshot = take_snapshot(&self.paths, &self.ignored);
+        let changed = snapshot != self.snapshot;
+        self.snapshot = snapshot;
+        changed
+    }
+}
+
+fn take_snapshot(paths: &[PathBuf], ignored: &[PathBuf]) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    for path in paths {
+        scan(path, ignored, &mut snapshot);
+    }
+    snapshot
+}
+
+fn scan(path: &Path, ignored: &[PathBuf], snapshot: &mut Snapshot) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    if metadata.is_file() {
+        if absolute(path).is_ok_and(|path| ignored.contains(&path)) {
+            return;
+        }
+        if let Ok(modified) = metadata.modified() {
+            snapshot.insert(path.to_path_buf(), (modified, metadata.len()));
+        }
+        return;
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+        if is_dir && IGNORED_DIRS.contains(&name.as_ref()) {
+            continue;
+        }
+        scan(&entry.path(), ignored, snapshot);
+    }
+}
+
+/// Runs `run_command` to completion on start and on every settled change to the watched
+/// files, cancelling a run that is still going when a new change arrives
+pub struct Rerunner {
+    cli_config: CliConfig,
+    watch_paths: Vec<PathBuf>,
+    ignored: Vec<PathBuf>,
+    debounce: Duration,
+    env: ResolvedEnv,
+    pid_handle: Option<Arc<Mutex<Option<u32>>>>,
+    fresh_workdir: Option<Arc<FreshWorkdir>>,
+}
+
+impl Rerunner {
+    pub fn new(cli_config: CliConfig, watch_paths: Vec<PathBuf>, debounce: Duration) -> Self {
+        Self {
+            cli_config,
+            watch_paths,
+            ignored: Vec::new(),
+            debounce,
+            env: ResolvedEnv::default(),
+            pid_handle: None,
+            fresh_workdir: None,
+        }
+    }
+
+    /// Files dev writes itself (logs, state), whose changes must not trigger a rerun
+    pub fn with_ignored(mut self, ignored: Vec<PathBuf>) -> Self {
+        self.ignored = ignored;
+        self
+    }
+
+    /// Environment variables set for every run
+    pub fn with_env(mut self, env: ResolvedEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Share the running command's PID with the Ctrl+C handler
+    pub fn with_pid_handle(mut self, pid_handle: Option<Arc<Mutex<Option<u32>>>>) -> Self {
+        self.pid_handle = pid_handle;
+        self
+    }
+
+    /// Run every time in a new copy of the project (`fresh_workdir`)
+    pub fn with_fresh_workdir(mut self, fresh_workdir: Option<Arc<FreshWorkdir>>) -> Self {
+        self.fresh_workdir = fresh_workdir;
+        self
+    }
+
+    /// Watch and rerun until dev is stopped with Ctrl+C, whose handler stops the running
+    /// command and the background `pre_commands`
+    pub fn run(&self) -> ! {
+        let paths: Vec<String> = self
+            .watch_paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        status!(
+            "{} Running '{}' on every change to {}",
+            theme::icon(Icon::Watch),
+            self.cli_config.run_command,
+            paths.join(", ")
+        );
+
+        let mut watcher = FileWatcher::new(self.watch_paths.clone(), &self.ignored);
+        let mut debouncer = Debouncer::new(self.debounce);
+        let mut running = self.start();
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            if let Some(child) = &mut running
+                && let Ok(Some(status)) = child.try_wait()
+            {
+                let icon = if status.success() {
+                    Icon::Success
+                } else {
+                    Icon::Failure
+                };
+                status!(
+                    "{} Command {}; waiting for changes...",
+                    theme::icon(icon),
+                    exit_status::format(&status)
+                );
+                running = None;
+                self.set_pid(None);
+            }
+
+            if !debouncer.observe(watcher.poll(), Instant::now()) {
+                continue;
+            }
+            match running.take() {
+                Some(child) => {
+                    status!(
+                        "\n{} Change detected; cancelling the running command and starting over\n",
+                        theme::icon(Icon::Restart)
+                    );
+                    cancel(child);
+                }
+                None => status!(
+                    "\n{} Change detected; running again\n",
+                    theme::icon(Icon::Restart)
+                ),
+            }
+            running = self.start();
+        }
+    }
+
+    fn start(&self) -> Option<Child> {
+        status!(
+            "{} Running {}",
+            theme::icon(Icon::Start),
+            self.cli_config.run_command
+        );
+        let mut command =
+            CommandBuilder::build(CommandType::Dev(Box::new(self.cli_config.clone())));
+        self.env.apply(&mut command);
+        if let Some(workdir) = &self.fresh_workdir {
+            match workdir.refresh() {
+                Ok(dir) => {
+                    command.current_dir(dir);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{} {}; waiting for changes...",
+                        theme::icon(Icon::Failure),
+                        e
+                    );
+                    return None;
+                }
+            }
+        }
+        match command.spawn() {
+            Ok(child) => {
+                self.set_pid(Some(child.id()));
+                Some(child)
+            }
+            Err(e) => {
+                eprintln!(
+                    "{} Failed to start command: {}; waiting for changes...",
+                    theme::icon(Icon::Failure),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    fn set_pid(&self, pid: Option<u32>) {
+        if let Some(handle) = &self.pid_handle {
+            *handle.lock().unwrap() = pid;
+        }
+    }
+}
+
+/// Kill a run that is still going, including what it started (e.g. the test binaries of
+/// `cargo test`), so the next run doesn't compete with it
+fn cancel(mut child: Child) {
+    // Collected before the shell dies and its children are reparented; the shell goes
+    // first so it can't carry on with the rest of the script
+    #[cfg(not(windows))]
+    let descendants = process::descendant_pids(child.id());
+    process::signal_pid(child.id(), true);
+    #[cfg(not(windows))]
+    for pid in descendants {
+        process::signal_pid(pid, true);
+    }
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_mode_defaults_to_server() {
+        let config: CliConfig =
+            serde_json::from_str(r#"{"run_command": "cargo test", "error_pattern": "[Error"}"#)
+                .unwrap();
+        assert_eq!(config.mode, RunMode::Server);
+
+        let config: CliConfig = serde_json::from_str(
+            r#"{"run_command": "cargo test", "error_pattern": "[Error", "mode": "command"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.mode, RunMode::Command);
+        assert!(
+            serde_json::to_string(&config)
+                .unwrap()
+                .contains(r#""mode":"command""#)
+        );
+    }
+
+    #[test]
+    fn test_file_watcher_sees_changes_but_not_ignored_dirs() {
+        let root = std::env::temp_dir().join("dev-cli-test-rerun-watch");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join("src/lib.rs"), "fn a() {}").unwrap();
+
+        let mut watcher = FileWatcher::new(vec![root.clone()], &[]);
+        assert!(!watcher.poll());
+
+        fs::write(root.join("target/out.o"), "build output").unwrap();
+        fs::write(root.join(".git/index"), "git").unwrap();
+        assert!(!watcher.poll());
+
+        fs::write(root.join("src/lib.rs"), "fn a() { b() }").unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        fs::write(root.join("src/new.rs"), "").unwrap();
+        assert!(watcher.poll());
+        fs::remove_file(root.join("src/new.rs")).unwrap();
+        assert!(watcher.poll());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_file_watcher_skips_files_dev_writes() {
+        let root = std::env::temp_dir().join("dev-cli-test-rerun-ignored");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+
+        // Named the way dev-cli.json names them, relative to where dev runs
+        let log = root.join("dev.log");
+        let relative_log = log
+            .strip_prefix(std::env::current_dir().unwrap())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| log.clone());
+        let mut watcher = FileWatcher::new(vec![root.join(".")], &[relative_log]);
+
+        fs::write(&log, "Running cargo test").unwrap();
+        assert!(!watcher.poll());
+        fs::write(&log, "Running cargo test\nok").unwrap();
+        assert!(!watcher.poll());
+
+        fs::write(root.join("main.rs"), "fn main() { run() }").unwrap();
+        assert!(watcher.poll());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use crate::monitor::WatchMessage;
+#[cfg(not(feature = "resource-limits"))]
+use crate::theme::{self, Icon};
+
+/// Memory/CPU limits for the dev server process tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceLimits {
+    pub max_memory_mb: Option<u64>,
+    pub max_cpu_percent: Option<f32>,
+    pub sample_interval: Duration,
+    /// How long a limit must stay exceeded before the server is restarted
+    pub window: Duration,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_memory_mb: None,
+            max_cpu_percent: None,
+            sample_interval: Duration::from_secs(2),
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ResourceLimits {
+    pub fn is_enabled(&self) -> bool {
+        self.max_memory_mb.is_some() || self.max_cpu_percent.is_some()
+    }
+}
+
+/// One measurement of the whole process tree
+#[cfg_attr(not(feature = "resource-limits"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceSample {
+    pub memory_mb: u64,
+    pub cpu_percent: f32,
+}
+
+/// Decides when a limit has been exceeded for the whole window
+#[cfg_attr(not(feature = "resource-limits"), allow(dead_code))]
+#[derive(Debug)]
+struct LimitTracker {
+    limits: ResourceLimits,
+    exceeded_since: Option<Instant>,
+}
+
+#[cfg_attr(not(feature = "resource-limits"), allow(dead_code))]
+impl LimitTracker {
+    fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            exceeded_since: None,
+        }
+    }
+
+    /// Record a sample; returns a description once a limit has been exceeded for the window
+    fn observe(&mut self, sample: ResourceSample, now: Instant) -> Option<String> {
+        let mut exceeded = Vec::new();
+        if let Some(max) = self.limits.max_memory_mb
+            && sample.memory_mb > max
+        {
+            exceeded.push(format!("memory {} MB > {} MB", sample.memory_mb, max));
+        }
+        if let Some(max) = self.limits.max_cpu_percent
+            && sample.cpu_percent > max
+        {
+            exceeded.push(format!("CPU {:.0}% > {:.0}%", sample.cpu_percent, max));
+        }
+
+        if exceeded.is_empty() {
+            self.exceeded_since = None;
+            return None;
+        }
+        let since = *self.exceeded_since.get_or_insert(now);
+        if now.duration_since(since) >= self.limits.window {
+            Some(format!(
+                "{} for {}s",
+                exceeded.join(", "),
+                self.limits.window.as_secs()
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Stops the sampling thread when dropped
+pub struct ResourceWatch {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for ResourceWatch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Sample the process tree rooted at `pid` and send `ResourceLimitExceeded` when a
+/// limit stays exceeded for the configured window
+#[cfg(feature = "resource-limits")]
+pub fn watch(pid: u32, limits: &ResourceLimits, tx: Sender<WatchMessage>) -> Option<ResourceWatch> {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+    if !limits.is_enabled() {
+        return None;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let watch = ResourceWatch {
+        stop: Arc::clone(&stop),
+    };
+    let interval = limits.sample_interval;
+    let mut tracker = LimitTracker::new(limits.clone());
+    std::thread::spawn(move || {
+        let root = Pid::from_u32(pid);
+        let mut system = System::new();
+        while !stop.load(Ordering::SeqCst) {
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing().with_memory().with_cpu(),
+            );
+            if !system.processes().contains_key(&root) {
+                return;
+            }
+
+            // The dev command runs under a shell, so count every descendant of the root
+            let mut tree = vec![root];
+            let mut i = 0;
+            while i < tree.len() {
+                let parent = tree[i];
+                tree.extend(
+                    system
+                        .processes()
+                        .iter()
+                        .filter(|(_, p)| p.parent() == Some(parent))
+                        .map(|(child, _)| *child),
+                );
+                i += 1;
+            }
+            let (memory, cpu_percent) = tree
+                .iter()
+                .filter_map(|pid| system.process(*pid))
+                .fold((0, 0.0), |(memory, cpu), p| {
+                    (memory + p.memory(), cpu + p.cpu_usage())
+                });
+            let sample = ResourceSample {
+                memory_mb: memory / (1024 * 1024),
+                cpu_percent,
+            };
+
+            if let Some(reason) = tracker.observe(sample, Instant::now()) {
+                let _ = tx.send(WatchMessage::ResourceLimitExceeded(reason));
+                return;
+            }
+            std::thread::sleep(interval);
+        }
+    });
+    Some(watch)
+}
+
+/// Resource limits need the `resource-limits` feature; warn once and keep running without them
+#[cfg(not(feature = "resource-limits"))]
+pub fn watch(
+    _pid: u32,
+    limits: &ResourceLimits,
+    _tx: Sender<WatchMessage>,
+) -> Option<ResourceWatch> {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+    if limits.is_enabled() && !WARNED.swap(true, Ordering::SeqCst) {
+        eprintln!(
+            "{} max_memory_mb/max_cpu_percent are ignored: dev was built without the resource-limits feature",
+            theme::icon(Icon::Warning)
+        );
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> ResourceLimits {
+        ResourceLimits {
+            max_memory_mb: Some(500),
+            max_cpu_percent: Some(90.0),
+            window: Duration::from_secs(10),
+            ..ResourceLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_limit_must_be_exceeded_for_whole_window() {
+        let mut tracker = LimitTracker::new(limits());
+        let start = Instant::now();
+        let high = ResourceSample {
+            memory_mb: 800,
+            cpu_percent: 10.0,
+        };
+
+        assert_eq!(tracker.observe(high, start), None);
+        assert_eq!(tracker.observe(high, start + Duration::from_secs(5)), None);
+        assert_eq!(
+            tracker
+                .observe(high, start + Duration::from_secs(10))
+                .as_deref(),
+            Some("memory 800 MB > 500 MB for 10s")
+        );
+    }
+
+    #[test]
+    fn test_dropping_below_limit_resets_window() {
+        let mut tracker = LimitTracker::new(limits());
+        let start = Instant::now();
+        let busy = ResourceSample {
+            memory_mb: 100,
+            cpu_percent: 150.0,
+        };
+        let idle = ResourceSample {
+            memory_mb: 100,
+            cpu_percent: 5.0,
+        };
+
+        assert_eq!(tracker.observe(busy, start), None);
+        assert_eq!(tracker.observe(idle, start + Duration::from_secs(8)), None);
+        assert_eq!(tracker.observe(busy, start + Duration::from_secs(12)), None);
+        assert!(
+            tracker
+                .observe(busy, start + Duration::from_secs(22))
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,859 @@
+use serde_json::{Map, Value, json};
+
+use crate::blocks::DEFAULT_MAX_BLOCK_LINES;
+use crate::patterns::PATTERN_SETS;
+use crate::theme::THEME_NAMES;
+
+/// Where the schema says it was written for; editors only use it as an identifier
+const SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// JSON Schema of dev-cli.json (`dev --schema`), for editor autocomplete and validation.
+/// Written by hand next to `CliConfig`; a test checks that every field is described.
+pub fn schema() -> Value {
+    let mut properties = Map::new();
+    properties.insert(
+        "$schema".to_string(),
+        string("Path or URL of this schema, for editors"),
+    );
+    properties.insert(
+        "extends".to_string(),
+        string("Config file whose settings this one builds on, relative to this file"),
+    );
+    for (name, property) in fields() {
+        properties.insert(name.to_string(), property);
+    }
+
+    json!({
+        "$schema": SCHEMA_DRAFT,
+        "title": "dev-cli.json",
+        "description": "Configuration of the dev server monitor",
+        "type": "object",
+        "required": ["run_command", "error_pattern"],
+        "properties": properties,
+        "additionalProperties": false,
+        "definitions": {
+            "condition": condition(),
+        },
+    })
+}
+
+/// Every `CliConfig` field, in the order they're declared
+fn fields() -> Vec<(&'static str, Value)> {
+    vec![
+        (
+            "run_command",
+            string("Shell command that starts the dev server, e.g. \"npm run dev\""),
+        ),
+        (
+            "error_pattern",
+            json!({
+                "description": "Output text that means the server hit an error and should be restarted; a list restarts on any of them",
+                "oneOf": [{ "type": "string" }, { "type": "array", "items": { "type": "string" } }],
+            }),
+        ),
+        (
+            "mode",
+            one_of(
+                "\"server\" keeps run_command running; \"command\" reruns it to completion on file changes",
+                &["server", "command"],
+                "server",
+            ),
+        ),
+        (
+            "watch_paths",
+            strings(
+                "Files and directories watched in \"command\" mode (default: the project directory)",
+            ),
+        ),
+        (
+            "initial_delay_secs",
+            integer(
+                "Seconds to wait before the first start, e.g. for a tunnel or container to come up (not between restarts)",
+            ),
+        ),
+        (
+            "first_output_timeout_secs",
+            integer(
+                "Count the attempt as a failed start if no output arrives within this many seconds",
+            ),
+        ),
+        (
+            "ready_timeout_secs",
+            integer(
+                "Stop dev with an error if the server isn't ready within this many seconds of starting",
+            ),
+        ),
+        (
+            "ready_warn_after_secs",
+            integer(
+                "Warn, but keep waiting, if the server isn't ready within this many seconds of starting",
+            ),
+        ),
+        (
+            "restart_on_eof",
+            boolean(
+                "Restart when the server closes stdout and stderr but its process keeps running",
+                false,
+            ),
+        ),
+        (
+            "restart_on_nonzero_exit",
+            boolean(
+                "Restart when the server exits with an error; false ends the session instead",
+                true,
+            ),
+        ),
+        (
+            "exit_code_rules",
+            array(
+                "What to do for particular exit codes, e.g. [{\"code\": 137, \"action\": \"restart\", \"notify\": \"OOM-killed\"}]; unmatched exits follow the defaults",
+                exit_code_rule(),
+            ),
+        ),
+        (
+            "restart_on_error",
+            boolean(
+                "Restart when an error pattern matches; false only reports the match",
+                true,
+            ),
+        ),
+        (
+            "confirm_restart",
+            boolean(
+                "Ask \"Restart now? [Y/n/q]\" before restarting after an error pattern (interactive terminals only)",
+                false,
+            ),
+        ),
+        (
+            "confirm_restart_timeout_secs",
+            with_default(
+                integer(
+                    "Seconds confirm_restart waits for an answer before restarting; 0 waits forever",
+                ),
+                10,
+            ),
+        ),
+        (
+            "max_spawn_retries",
+            integer("Give up after this many consecutive spawn failures (unlimited when absent)"),
+        ),
+        (
+            "max_restarts",
+            integer(
+                "Stop instead of restarting once the server has been restarted after a crash this many times",
+            ),
+        ),
+        (
+            "max_restarts_message",
+            string(
+                "Message printed when max_restarts is reached; {count} and {last_error} are filled in",
+            ),
+        ),
+        (
+            "restart_burst",
+            json!({
+                "type": "integer",
+                "minimum": 1,
+                "description": "Crash restarts allowed back to back before restart_refill limits them",
+            }),
+        ),
+        (
+            "restart_refill",
+            json!({
+                "type": "number",
+                "exclusiveMinimum": 0,
+                "description": "Crash restarts earned back per minute, up to restart_burst",
+            }),
+        ),
+        (
+            "restart_budget_strict",
+            boolean(
+                "Stop instead of waiting for a restart to be earned back",
+                false,
+            ),
+        ),
+        (
+            "backoff_multiplier",
+            json!({
+                "type": "number",
+                "minimum": 1,
+                "description": "Grow the restart delay by this factor for each crash in a row, e.g. 2 to double it",
+            }),
+        ),
+        (
+            "max_restart_delay_secs",
+            with_default(
+                integer("Upper bound in seconds for the restart delay grown by backoff_multiplier"),
+                json!(60),
+            ),
+        ),
+        (
+            "stability_window_secs",
+            with_default(
+                integer(
+                    "Seconds a server must run before a crash counts as new rather than in a row",
+                ),
+                json!(30),
+            ),
+        ),
+        (
+            "spawn_backoff_cap_secs",
+            integer("Upper bound in seconds for the spawn-failure backoff delay"),
+        ),
+        (
+            "ready_pattern",
+            string("Output text that means the server is up and serving"),
+        ),
+        (
+            "ready_command",
+            string(
+                "Command run after each start until it exits with 0, meaning the server is ready",
+            ),
+        ),
+        (
+            "ready_command_interval_ms",
+            with_default(
+                integer("Milliseconds before the first ready_command retry, doubling up to 5s"),
+                500,
+            ),
+        ),
+        (
+            "ready_command_timeout_secs",
+            with_default(
+                integer(
+                    "Seconds one run of ready_command may take before it's killed and counts as not ready",
+                ),
+                5,
+            ),
+        ),
+        (
+            "ready_marker",
+            with_default(
+                string("Line printed to stdout the first time the server becomes ready"),
+                "DEV_READY",
+            ),
+        ),
+        (
+            "ready_file",
+            string("File written the first time the server becomes ready"),
+        ),
+        (
+            "on_ready",
+            string("Command started (without waiting) the first time the server becomes ready"),
+        ),
+        (
+            "on_restart",
+            string(
+                "Command run to completion before each restart, told why through DEV_RESTART_REASON",
+            ),
+        ),
+        (
+            "on_clean_exit",
+            string("Command run to completion when the server exits normally and the session ends"),
+        ),
+        (
+            "parse_levels",
+            boolean(
+                "Color each output line by its leading level token (INFO, WARN, ERROR, ...)",
+                false,
+            ),
+        ),
+        (
+            "level_patterns",
+            string_map("Level name → color name used when parse_levels is on"),
+        ),
+        (
+            "highlight_match",
+            boolean(
+                "Highlight the part of an error line that matched an error pattern",
+                false,
+            ),
+        ),
+        (
+            "attempt_prefix",
+            boolean(
+                "Prefix each output line with the number of the attempt that printed it (#3 | ...)",
+                false,
+            ),
+        ),
+        (
+            "stderr_is_error",
+            boolean(
+                "Show stderr lines in red as errors; by default stderr is just another output stream",
+                false,
+            ),
+        ),
+        (
+            "capture_stdout",
+            boolean(
+                "Read and match the server's stdout; false leaves it on the terminal untouched",
+                true,
+            ),
+        ),
+        (
+            "capture_stderr",
+            boolean(
+                "Read and match the server's stderr; false leaves it on the terminal untouched",
+                true,
+            ),
+        ),
+        (
+            "no_force_kill",
+            boolean(
+                "Only ever ask the server to exit (SIGTERM / CTRL_BREAK); never force-kill it",
+                false,
+            ),
+        ),
+        (
+            "nohup_mode",
+            boolean(
+                "Keep dev and the server running when the terminal closes (Unix); by default they are stopped",
+                false,
+            ),
+        ),
+        (
+            "shutdown_timeout_secs",
+            with_default(
+                integer("Seconds to wait for a killed server to exit before giving up"),
+                5,
+            ),
+        ),
+        (
+            "shutdown_poll_interval_ms",
+            with_default(
+                integer("Milliseconds between checks whether a killed server has exited"),
+                50,
+            ),
+        ),
+        (
+            "shutdown_warn_after_secs",
+            with_default(
+                integer("Seconds into a shutdown wait before progress is printed"),
+                1,
+            ),
+        ),
+        (
+            "graceful_shutdown_warn_secs",
+            integer(
+                "Seconds between \"still waiting\" warnings while a no_force_kill shutdown is pending",
+            ),
+        ),
+        (
+            "pattern_rules",
+            array(
+                "Extra error patterns with per-pattern settings such as restart_delay_secs",
+                pattern_rule(),
+            ),
+        ),
+        (
+            "restart_when",
+            array(
+                "Restart when a combination of patterns, uptime and exit code is met, e.g. [{\"all\": [{\"pattern\": \"[Error\"}, {\"uptime_at_least_secs\": 10}]}]",
+                restart_rule(),
+            ),
+        ),
+        (
+            "error_threshold",
+            with_default(integer("Restart only after this many error matches"), 1),
+        ),
+        (
+            "error_window_secs",
+            integer("Only count matches from the last this-many seconds toward error_threshold"),
+        ),
+        (
+            "count_per_line",
+            one_of(
+                "\"once\": a line adds one toward error_threshold; \"all\": one per match in it",
+                &["once", "all"],
+                "once",
+            ),
+        ),
+        (
+            "report_matches",
+            boolean(
+                "Log every error match with its running count toward error_threshold",
+                false,
+            ),
+        ),
+        (
+            "stall_patterns",
+            strings(
+                "Output lines meaning the server is wedged; they restart it like errors but are reported as stalls",
+            ),
+        ),
+        (
+            "block_start",
+            string(
+                "Text that starts a multi-line block such as a stack trace, matched and counted as one error",
+            ),
+        ),
+        (
+            "block_end",
+            string("Text on a block's last line; without it a blank line ends the block"),
+        ),
+        (
+            "max_block_lines",
+            with_default(
+                integer("Lines after which a block is cut off and matched"),
+                DEFAULT_MAX_BLOCK_LINES,
+            ),
+        ),
+        (
+            "mute_patterns",
+            strings(
+                "Output lines that are never printed, e.g. a deprecation warning on every request; still matched against the error patterns",
+            ),
+        ),
+        (
+            "dedupe_errors",
+            boolean(
+                "Report an error identical to the previous restart's as a repeat count instead of in full",
+                false,
+            ),
+        ),
+        (
+            "read_buffer_size",
+            with_default(
+                integer(
+                    "Bytes buffered per output stream; larger buffers mean fewer reads for very long lines",
+                ),
+                8192,
+            ),
+        ),
+        (
+            "delimiter",
+            with_default(
+                json!({
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": 255,
+                    "description": "Byte that separates output records, e.g. 0 for null-delimited output (10 is a newline)",
+                }),
+                10,
+            ),
+        ),
+        (
+            "patterns_file",
+            string(
+                "Shared file of extra error patterns (one per line, # comments, or a JSON array)",
+            ),
+        ),
+        (
+            "regex_patterns_file",
+            string(
+                "Like patterns_file, but every entry is a regular expression whose named groups are reported",
+            ),
+        ),
+        (
+            "pattern_set",
+            json!({
+                "type": "string",
+                "enum": PATTERN_SETS,
+                "description": "Built-in regex error patterns added to the others; used on its own when error_pattern is empty and nothing else is configured",
+            }),
+        ),
+        (
+            "state_file",
+            string(
+                "File where state such as the last known good command is remembered between sessions",
+            ),
+        ),
+        (
+            "fallback_after_failures",
+            integer("Offer the last known good command after this many consecutive start failures"),
+        ),
+        (
+            "event_command",
+            string("Command run for every lifecycle event with a JSON event object on stdin"),
+        ),
+        (
+            "event_log",
+            string(
+                "File every lifecycle event is appended to as one JSON line, e.g. for tail -f | jq",
+            ),
+        ),
+        (
+            "max_memory_mb",
+            integer(
+                "Restart the server when its process tree uses more memory than this (needs the resource-limits feature)",
+            ),
+        ),
+        (
+            "max_cpu_percent",
+            json!({
+                "type": "number",
+                "exclusiveMinimum": 0,
+                "description": "Restart the server when its process tree uses more CPU than this; 100 = one full core",
+            }),
+        ),
+        (
+            "resource_sample_interval_secs",
+            with_default(integer("Seconds between resource usage samples"), 2),
+        ),
+        (
+            "resource_limit_window_secs",
+            with_default(
+                integer("Seconds a resource limit must stay exceeded before restarting"),
+                10,
+            ),
+        ),
+        (
+            "crash_signals",
+            strings(
+                "Signals that count as a crash when they end the server, e.g. [\"SIGSEGV\"] (Unix; default: all)",
+            ),
+        ),
+        (
+            "stop_signals",
+            strings(
+                "Signals that mean the server was stopped on purpose, so it isn't restarted (Unix)",
+            ),
+        ),
+        ("remote", remote()),
+        (
+            "theme",
+            one_of("Prefix set for dev's output", &THEME_NAMES, "emoji"),
+        ),
+        (
+            "echo_command",
+            boolean(
+                "Print the exact program, arguments, environment and directory before each spawn",
+                false,
+            ),
+        ),
+        (
+            "env",
+            string_map(
+                "Environment variables set for the dev command; ${vault:<path>} in a value is filled in by secret_command",
+            ),
+        ),
+        (
+            "env_clear",
+            boolean(
+                "Start the dev command with an empty environment instead of dev's own, plus env",
+                false,
+            ),
+        ),
+        (
+            "env_inherit",
+            strings(
+                "Variables kept from dev's environment when env_clear is set, e.g. [\"PATH\", \"HOME\"]",
+            ),
+        ),
+        (
+            "secret_command",
+            string(
+                "Command that prints the secret at {path} to stdout, e.g. \"vault kv get -field=value {path}\"",
+            ),
+        ),
+        (
+            "wrapper",
+            string("Command the dev command runs under, e.g. \"time -v\" or \"strace -f\""),
+        ),
+        (
+            "umask",
+            json!({
+                "type": "string",
+                "pattern": "^\\s*[0-7]{1,4}\\s*$",
+                "description": "Octal umask the dev command is started with on Unix, e.g. \"0077\"",
+            }),
+        ),
+        (
+            "run_as_uid",
+            integer(
+                "User ID the dev command runs as on Unix; dev must run as root to switch to it",
+            ),
+        ),
+        (
+            "run_as_gid",
+            integer(
+                "Group ID the dev command runs as on Unix; dev must run as root to switch to it",
+            ),
+        ),
+        (
+            "working_dir",
+            string(
+                "Directory the dev command runs in, e.g. \"frontend\"; relative to the config file",
+            ),
+        ),
+        (
+            "fresh_workdir",
+            string(
+                "Copy this directory (e.g. \".\") to a temporary directory before every start and run there",
+            ),
+        ),
+        ("pre_commands", hook_group()),
+        (
+            "warmup",
+            string("One-time setup command (migrations, code generation) run before pre_commands"),
+        ),
+        (
+            "on_tick",
+            string(
+                "Command run periodically while the server runs, for custom liveness checks or heartbeats",
+            ),
+        ),
+        (
+            "on_tick_interval_secs",
+            with_default(integer("Minimum seconds between on_tick runs"), 5),
+        ),
+        (
+            "watch_config",
+            boolean(
+                "Reload dev-cli.json when it changes while the server is running",
+                false,
+            ),
+        ),
+        (
+            "watch_debounce_ms",
+            with_default(
+                integer(
+                    "Milliseconds dev-cli.json must stay unchanged before a burst of writes is reloaded",
+                ),
+                300,
+            ),
+        ),
+        (
+            "rebuild_grace_ms",
+            integer(
+                "Milliseconds after a watch_config restart during which error patterns don't restart the server",
+            ),
+        ),
+        (
+            "log_file",
+            string("File that receives a plain copy of the server's stdout and stderr"),
+        ),
+        (
+            "monitor_log",
+            string("Where dev's own status lines go instead of stdout: a file path, or \"stderr\""),
+        ),
+        (
+            "profiles",
+            json!({
+                "type": "object",
+                "description": "Named sets of fields that override the base settings, selected with --profile or DEV_PROFILE",
+                "additionalProperties": { "type": "object" },
+            }),
+        ),
+    ]
+}
+
+fn string(description: &str) -> Value {
+    json!({ "type": "string", "description": description })
+}
+
+fn strings(description: &str) -> Value {
+    json!({ "type": "array", "items": { "type": "string" }, "description": description })
+}
+
+fn string_map(description: &str) -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": { "type": "string" },
+        "description": description,
+    })
+}
+
+fn boolean(description: &str, default: bool) -> Value {
+    json!({ "type": "boolean", "default": default, "description": description })
+}
+
+fn integer(description: &str) -> Value {
+    json!({ "type": "integer", "minimum": 0, "description": description })
+}
+
+fn one_of(description: &str, values: &[&str], default: &str) -> Value {
+    json!({ "type": "string", "enum": values, "default": default, "description": description })
+}
+
+fn array(description: &str, items: Value) -> Value {
+    json!({ "type": "array", "items": items, "description": description })
+}
+
+fn with_default(mut property: Value, default: impl Into<Value>) -> Value {
+    property["default"] = default.into();
+    property
+}
+
+fn pattern_rule() -> Value {
+    json!({
+        "type": "object",
+        "required": ["pattern"],
+        "additionalProperties": false,
+        "properties": {
+            "pattern": string("Output text that triggers a restart"),
+            "restart_delay_secs": integer("Seconds to wait before restarting when this pattern triggered"),
+            "recovery_command": string("Command run to completion before restarting when this pattern triggered, e.g. prisma generate"),
+            "cooldown_secs": integer("After this pattern triggered a restart, ignore it for this many seconds; other patterns still restart the server"),
+            "regex": boolean("Treat pattern as a regular expression; its named groups are reported on a match", false),
+        },
+    })
+}
+
+fn exit_code_rule() -> Value {
+    json!({
+        "type": "object",
+        "required": ["action"],
+        "additionalProperties": false,
+        "properties": {
+            "code": { "type": "integer", "description": "The exit code this rule is for" },
+            "code_range": {
+                "type": "array",
+                "description": "Inclusive range of exit codes this rule is for, e.g. [128, 255]",
+                "items": { "type": "integer" },
+                "minItems": 2,
+                "maxItems": 2,
+            },
+            "action": {
+                "enum": ["restart", "stop"],
+                "description": "restart the server, or end the session (cleanly for code 0, as a failure otherwise)",
+            },
+            "notify": string("Warning printed when the server exits with a matching code"),
+            "recovery_command": string("Command run to completion before restarting, e.g. to free memory after an OOM kill"),
+            "restart_delay_secs": integer("Seconds to wait before restarting; overrides restart_delay"),
+        },
+    })
+}
+
+/// Properties of a `restart_when` condition, each a single-key object
+fn condition_properties() -> Map<String, Value> {
+    let conditions = json!({ "type": "array", "items": { "$ref": "#/definitions/condition" } });
+    let mut properties = Map::new();
+    properties.insert(
+        "pattern".to_string(),
+        string("An output line contains this text"),
+    );
+    properties.insert(
+        "uptime_at_least_secs".to_string(),
+        integer("The server has been running for at least this many seconds"),
+    );
+    properties.insert(
+        "exit_code".to_string(),
+        json!({ "type": "integer", "description": "The server exited with this code" }),
+    );
+    properties.insert("all".to_string(), conditions.clone());
+    properties.insert("sequence".to_string(), conditions);
+    properties
+}
+
+fn condition() -> Value {
+    json!({
+        "type": "object",
+        "minProperties": 1,
+        "maxProperties": 1,
+        "additionalProperties": false,
+        "properties": condition_properties(),
+    })
+}
+
+fn restart_rule() -> Value {
+    let mut properties = condition_properties();
+    properties.insert(
+        "within_secs".to_string(),
+        integer("For a sequence: the seconds its last step may come after its first"),
+    );
+    json!({
+        "type": "object",
+        "minProperties": 1,
+        "additionalProperties": false,
+        "properties": properties,
+    })
+}
+
+fn remote() -> Value {
+    json!({
+        "type": "object",
+        "description": "Run run_command on this host over SSH instead of locally",
+        "required": ["host"],
+        "additionalProperties": false,
+        "properties": {
+            "host": string("Host name or address"),
+            "user": string("User to log in as"),
+            "key": string("Private key passed to ssh -i"),
+            "port": { "type": "integer", "minimum": 1, "maximum": 65535, "description": "SSH port" },
+        },
+    })
+}
+
+fn hook_group() -> Value {
+    json!({
+        "type": "object",
+        "description": "Commands run before the dev server first starts",
+        "required": ["commands"],
+        "additionalProperties": false,
+        "properties": {
+            "commands": strings("Shell commands to run"),
+            "parallel": boolean("Start every command at once instead of one after another", false),
+            "wait": boolean("With parallel, wait for all commands to finish; false leaves them running in the background until dev exits", true),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_config::CliConfig;
+
+    /// Field names declared in `pub struct CliConfig`, read from its source
+    fn declared_fields() -> Vec<String> {
+        let source = include_str!("cli_config.rs");
+        let start = source.find("pub struct CliConfig {").unwrap();
+        let body = &source[start..];
+        let body = &body[..body.find("\n}").unwrap()];
+        body.lines()
+            .filter_map(|line| line.strip_prefix("    pub "))
+            .filter_map(|line| line.split_once(':').map(|(name, _)| name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_schema_describes_every_config_field() {
+        let schema = schema();
+        let text = serde_json::to_string_pretty(&schema).unwrap();
+        let parsed: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed, schema);
+
+        let properties = schema["properties"].as_object().unwrap();
+        let declared = declared_fields();
+        assert!(declared.len() > 50, "{:?}", declared);
+        let documented: Vec<&str> = fields().iter().map(|(name, _)| *name).collect();
+        assert_eq!(documented, declared, "fields() must follow CliConfig");
+        for (name, property) in properties {
+            assert!(
+                property["description"].is_string(),
+                "{} has no description",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_required_fields_and_enums_match_the_config() {
+        let schema = schema();
+        let required = schema["required"].as_array().unwrap();
+        for name in required {
+            let mut config =
+                serde_json::json!({"run_command": "npm run dev", "error_pattern": "[Error"});
+            config
+                .as_object_mut()
+                .unwrap()
+                .remove(name.as_str().unwrap());
+            assert!(serde_json::from_value::<CliConfig>(config).is_err());
+        }
+
+        for (name, property) in schema["properties"].as_object().unwrap() {
+            let Some(values) = property.get("enum") else {
+                continue;
+            };
+            for value in values.as_array().unwrap() {
+                let config = serde_json::json!({
+                    "run_command": "npm run dev",
+                    "error_pattern": "[Error",
+                    name.as_str(): value,
+                });
+                let config: CliConfig = serde_json::from_value(config).unwrap();
+                assert!(config.validate().is_ok(), "{} = {}", name, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, HashMap};
+use std::process::Command;
+
+use crate::command::{self, CommandBuilder, CommandType};
+use crate::error::{Result, ServerError};
+use crate::exit_status;
+
+/// Start of a secret reference in an `env` value: `${vault:path/to/secret}`
+const SECRET_START: &str = "${vault:";
+
+/// Placeholder in `secret_command` replaced by the (quoted) secret path
+const PATH_PLACEHOLDER: &str = "{path}";
+
+/// The dev command's environment from `env`, with secret references resolved
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolvedEnv {
+    vars: Vec<(String, String)>,
+    /// Variables whose value contains a secret; their values are never printed
+    secret_names: Vec<String>,
+}
+
+impl ResolvedEnv {
+    /// Set the variables on a command about to be spawned
+    pub fn apply(&self, command: &mut Command) {
+        command.envs(self.vars.iter().map(|(name, value)| (name, value)));
+    }
+
+    pub fn secret_names(&self) -> &[String] {
+        &self.secret_names
+    }
+}
+
+/// Resolve `env` once, running `secret_command` for each distinct `${vault:...}` path
+pub fn resolve(
+    env: &BTreeMap<String, String>,
+    secret_command: Option<&str>,
+) -> Result<ResolvedEnv> {
+    let mut cache: HashMap<String, String> = HashMap::new();
+    let mut resolved = ResolvedEnv::default();
+    for (name, value) in env {
+        let mut has_secret = false;
+        let value = interpolate(value, |path| {
+            has_secret = true;
+            let Some(secret_command) = secret_command else {
+                return Err(format!(
+                    "uses {}{}}} but secret_command is not set",
+                    SECRET_START, path
+                ));
+            };
+            if let Some(secret) = cache.get(path) {
+                return Ok(secret.clone());
+            }
+            let secret = fetch(secret_command, path)?;
+            cache.insert(path.to_string(), secret.clone());
+            Ok(secret)
+        })
+        .map_err(|e| ServerError::ConfigError(format!("env {}: {}", name, e)))?;
+        if has_secret {
+            resolved.secret_names.push(name.clone());
+        }
+        resolved.vars.push((name.clone(), value));
+    }
+    Ok(resolved)
+}
+
+/// Replace every `${vault:<path>}` in `value` with what `lookup` returns for the path
+fn interpolate(
+    value: &str,
+    mut lookup: impl FnMut(&str) -> std::result::Result<String, String>,
+) -> std::result::Result<String, String> {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find(SECRET_START) {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + SECRET_START.len()..];
+        let Some(end) = after.find('}') else {
+            return Err(format!("{} is missing its closing }}", SECRET_START));
+        };
+        let path = after[..end].trim();
+        if path.is_empty() {
+            return Err(format!("{}}} needs a secret path", SECRET_START));
+        }
+        result.push_str(&lookup(path)?);
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Run `secret_command` for one path. Its output is the secret, so errors only ever
+/// include what it printed to stderr.
+fn fetch(secret_command: &str, path: &str) -> std::result::Result<String, String> {
+    let script = secret_command.replace(PATH_PLACEHOLDER, &command::quote(path));
+    let output = CommandBuilder::build(CommandType::Hook(script))
+        .output()
+        .map_err(|e| format!("secret_command could not start: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let (status, _) = exit_status::describe(&output.status);
+        return Err(match stderr.lines().find(|line| !line.trim().is_empty()) {
+            Some(reason) => format!(
+                "secret_command {} for '{}': {}",
+                status,
+                path,
+                reason.trim()
+            ),
+            None => format!("secret_command {} for '{}'", status, path),
+        });
+    }
+    let secret = String::from_utf8(output.stdout).map_err(|_| {
+        format!(
+            "secret_command printed a secret for '{}' that isn't UTF-8",
+            path
+        )
+    })?;
+    let secret = secret.strip_suffix('\n').unwrap_or(&secret);
+    let secret = secret.strip_suffix('\r').unwrap_or(secret);
+    if secret.is_empty() {
+        return Err(format!("secret_command printed nothing for '{}'", path));
+    }
+    Ok(secret.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> BTreeMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_secrets_are_resolved_with_the_secret_command() {
+        let resolved = resolve(
+            &env(&[
+                ("DB_PASSWORD", "${vault:db/pass}"),
+                (
+                    "DATABASE_URL",
+                    "postgres://app:${vault:db/pass}@localhost/${vault:db/name}",
+                ),
+                ("NODE_ENV", "development"),
+            ]),
+            Some("printf 'secret-for-%s\\n' {path}"),
+        )
+        .unwrap();
+        let mut command = Command::new("true");
+        resolved.apply(&mut command);
+        let vars: Vec<(String, String)> = command
+            .get_envs()
+            .map(|(name, value)| {
+                (
+                    name.to_string_lossy().into_owned(),
+                    value.unwrap().to_string_lossy().into_owned(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vars,
+            [
+                (
+                    "DATABASE_URL".to_string(),
+                    "postgres://app:secret-for-db/pass@localhost/secret-for-db/name".to_string()
+                ),
+                ("DB_PASSWORD".to_string(), "secret-for-db/pass".to_string()),
+                ("NODE_ENV".to_string(), "development".to_string()),
+            ]
+        );
+        assert_eq!(resolved.secret_names(), ["DATABASE_URL", "DB_PASSWORD"]);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_secret_failures_are_clear_and_never_show_the_output() {
+        let error = |vars: &[(&str, &str)], secret_command: Option<&str>| match resolve(
+            &env(vars),
+            secret_command,
+        ) {
+            Err(ServerError::ConfigError(msg)) => msg,
+            other => panic!("expected a config error, got {:?}", other),
+        };
+
+        let msg = error(
+            &[("TOKEN", "${vault:api/token}")],
+            Some("echo hunter2; echo 'permission denied' >&2; exit 2"),
+        );
+        assert_eq!(
+            msg,
+            "env TOKEN: secret_command exited with code 2 for 'api/token': permission denied"
+        );
+        assert!(
+            error(&[("TOKEN", "${vault:api/token}")], None).contains("secret_command is not set")
+        );
+        assert!(error(&[("TOKEN", "${vault:api/token")], Some("echo x")).contains("closing }"));
+        assert!(error(&[("TOKEN", "${vault:}")], Some("echo x")).contains("needs a secret path"));
+        assert!(error(&[("TOKEN", "${vault:a}")], Some("true")).contains("printed nothing"));
+        // Plain values don't need a secret command
+        assert!(resolve(&env(&[("PORT", "3000")]), None).is_ok());
+    }
+}
@@ -1,11 +1,43 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::cli_config::CliConfig;
-use crate::command::{CommandBuilder, CommandType};
+use crate::backoff::{Backoff, RestartBackoff};
+use crate::budget::TokenBucket;
+use crate::cli_config::{CONFIG_FILE, CliConfig};
+use crate::command::{self, CommandBuilder, CommandType};
 use crate::config::Config;
-use crate::error::Result;
-use crate::monitor::ProcessMonitor;
-use crate::process::ProcessManager;
+use crate::error::{Result, ServerError};
+use crate::events::{self, EventEmitter, EventKind, EventLog, LifecycleEvent};
+use crate::hooks::{self, BackgroundPids, HookRunner};
+use crate::monitor::{AttemptInfo, ProcessMonitor, RestartReason};
+use crate::policy::{Decision, DefaultPolicy, RestartPolicy};
+use crate::process::{self, ProcessManager};
+use crate::prompt::{self, LineReader, RestartChoice};
+use crate::record::{self, RECORD_FORMAT_VERSION, Record};
+use crate::reload;
+use crate::rerun::Rerunner;
+use crate::secrets::{self, ResolvedEnv};
+use crate::state::StateStore;
+use crate::stats::{RepeatTally, SessionStats, SharedStats, format_duration};
+use crate::status::{self, ChildLog, status};
+use crate::theme::{self, Icon};
+use crate::workdir::{CurrentCopy, FreshWorkdir};
+
+/// How a monitoring session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Completed,
+    /// `--fail-fast` stopped at the first error pattern or crash
+    ErrorExit,
+    /// The server wasn't ready within `ready_timeout_secs`
+    ReadyTimeout,
+}
 
 /// Main server management logic
 pub struct DevServer {
@@ -13,6 +45,32 @@ pub struct DevServer {
     cli_config: Option<CliConfig>,
     test_mode: bool,
     child_pid_handle: Option<std::sync::Arc<std::sync::Mutex<Option<u32>>>>,
+    force_kill_handle: Option<Arc<AtomicBool>>,
+    nohup_handle: Option<Arc<AtomicBool>>,
+    /// Where the copy in use is shared with the Ctrl+C handler
+    fresh_workdir_handle: Option<CurrentCopy>,
+    background_pids_handle: Option<BackgroundPids>,
+    assume_yes: bool,
+    repeat: Option<u32>,
+    fail_fast: bool,
+    /// dev-cli.json, or the file given with `--config-path`
+    config_path: PathBuf,
+    profile: Option<String>,
+    wrapper: Option<String>,
+    watch_command: bool,
+    record: Option<String>,
+    raw: bool,
+    skip_initial_delay: bool,
+    wait_start: bool,
+    profile_startup: bool,
+    /// Replaces `pattern_set` from dev-cli.json (`--patterns`)
+    pattern_set: Option<String>,
+    /// Replaces `DefaultPolicy` for deciding whether to restart
+    restart_policy: Option<Box<dyn RestartPolicy>>,
+    /// `env` from the config with its secrets resolved
+    env: ResolvedEnv,
+    fresh_workdir: Option<Arc<FreshWorkdir>>,
+    stats: SharedStats,
 }
 
 impl DevServer {
@@ -22,6 +80,27 @@ impl DevServer {
             cli_config: None,
             test_mode,
             child_pid_handle: None,
+            force_kill_handle: None,
+            nohup_handle: None,
+            fresh_workdir_handle: None,
+            background_pids_handle: None,
+            assume_yes: false,
+            repeat: None,
+            fail_fast: false,
+            config_path: PathBuf::from(CONFIG_FILE),
+            profile: None,
+            wrapper: None,
+            watch_command: false,
+            record: None,
+            raw: false,
+            skip_initial_delay: false,
+            wait_start: false,
+            profile_startup: false,
+            pattern_set: None,
+            restart_policy: None,
+            env: ResolvedEnv::default(),
+            fresh_workdir: None,
+            stats: SharedStats::default(),
         }
     }
 
@@ -29,76 +108,1191 @@ impl DevServer {
         self.child_pid_handle = Some(handle);
     }
 
-    pub fn run(&mut self) -> Result<()> {
+    /// Share whether the Ctrl+C handler may force-kill the child
+    pub fn set_force_kill_handle(&mut self, handle: Arc<AtomicBool>) {
+        self.force_kill_handle = Some(handle);
+    }
+
+    /// Tell the SIGHUP handler whether to keep running when the terminal closes
+    pub fn set_nohup_handle(&mut self, handle: Arc<AtomicBool>) {
+        self.nohup_handle = Some(handle);
+    }
+
+    /// Share the `fresh_workdir` copy in use, so Ctrl+C can remove it
+    pub fn set_fresh_workdir_handle(&mut self, handle: CurrentCopy) {
+        self.fresh_workdir_handle = Some(handle);
+    }
+
+    /// Share the PIDs of background pre-commands with the Ctrl+C handler
+    pub fn set_background_pids_handle(&mut self, handle: BackgroundPids) {
+        self.background_pids_handle = Some(handle);
+    }
+
+    /// Share the session counters with the Ctrl+C handler
+    pub fn set_stats_handle(&mut self, handle: SharedStats) {
+        self.stats = handle;
+    }
+
+    /// Answer yes to prompts such as the last-known-good fallback (`--yes`)
+    pub fn set_assume_yes(&mut self, assume_yes: bool) {
+        self.assume_yes = assume_yes;
+    }
+
+    /// Run exactly this many attempts whatever their outcome, then stop (`--repeat`)
+    pub fn set_repeat(&mut self, repeat: Option<u32>) {
+        self.repeat = repeat;
+    }
+
+    /// Run a single attempt and exit with an error at the first failure instead of restarting (`--fail-fast`)
+    pub fn set_fail_fast(&mut self, fail_fast: bool) {
+        self.fail_fast = fail_fast;
+    }
+
+    /// Run the dev command under this wrapper instead of the configured one (`--wrapper`)
+    pub fn set_wrapper(&mut self, wrapper: Option<String>) {
+        self.wrapper = wrapper;
+    }
+
+    /// Rerun the command on file changes instead of monitoring a server (`--watch-command`)
+    pub fn set_watch_command(&mut self, watch_command: bool) {
+        self.watch_command = watch_command;
+    }
+
+    /// Write the session's output lines and lifecycle events to this file (`--record`)
+    pub fn set_record(&mut self, record: Option<String>) {
+        self.record = record;
+    }
+
+    /// Pass the server's output through undecorated and keep status lines off stdout (`--raw`)
+    pub fn set_raw(&mut self, raw: bool) {
+        self.raw = raw;
+    }
+
+    /// Start right away even if `initial_delay_secs` is set (`--no-delay`)
+    pub fn set_skip_initial_delay(&mut self, skip: bool) {
+        self.skip_initial_delay = skip;
+    }
+
+    /// Wait for Enter before the first start (`--wait-start`)
+    pub fn set_wait_start(&mut self, wait_start: bool) {
+        self.wait_start = wait_start;
+    }
+
+    /// Print where each boot spent its time once the server is ready (`--profile-startup`)
+    pub fn set_profile_startup(&mut self, profile_startup: bool) {
+        self.profile_startup = profile_startup;
+    }
+
+    /// Use this built-in pattern set instead of the one in dev-cli.json (`--patterns`)
+    pub fn set_pattern_set(&mut self, pattern_set: Option<String>) {
+        self.pattern_set = pattern_set;
+    }
+
+    /// Decide restarts with this policy instead of the settings in dev-cli.json. The `dev`
+    /// binary itself always goes by the settings; only embedders and the tests plug one in.
+    /// `--fail-fast` and `--repeat` never ask it: the first stops at the first failure and
+    /// the second runs every iteration whatever happened, so neither has a restart to decide.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn set_restart_policy(&mut self, policy: Box<dyn RestartPolicy>) {
+        self.restart_policy = Some(policy);
+    }
+
+    /// Read the configuration from this file instead of dev-cli.json (`--config-path`)
+    pub fn set_config_path(&mut self, config_path: PathBuf) {
+        self.config_path = config_path;
+    }
+
+    /// Merge this profile from dev-cli.json over the base settings (`--profile` / `DEV_PROFILE`)
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+    }
+
+    pub fn run(&mut self) -> Result<RunOutcome> {
+        if self.raw {
+            status::init(Some(status::STDERR_TARGET))?;
+        }
         // Load CLI configuration if not in test mode
         if !self.test_mode {
-            let cli_config = CliConfig::load_or_create()?;
-            // Update the error pattern from CLI config
-            self.config.error_pattern = cli_config.error_pattern.clone();
+            let mut cli_config =
+                CliConfig::load_or_create(&self.config_path, self.profile.as_deref())?;
+            for (var, field) in cli_config.apply_env_overrides(|name| env::var(name).ok()) {
+                status!("{} {} set from {}", theme::icon(Icon::Config), field, var);
+            }
+            let cli_config = self.with_overrides(cli_config);
+            self.config = self.config.clone().with_cli_config(&cli_config)?;
+            self.env = secrets::resolve(&cli_config.env, cli_config.secret_command.as_deref())?;
             self.cli_config = Some(cli_config);
         }
+        self.fresh_workdir = self.fresh_workdir()?;
+        if let Some(workdir) = &self.fresh_workdir {
+            status!(
+                "{} Every start runs in a fresh copy of {}",
+                theme::icon(Icon::Config),
+                workdir.source().display()
+            );
+        }
+        if self.fail_fast && !self.config.restart_on_error {
+            return Err(ServerError::ConfigError(
+                "--fail-fast exits at the first error pattern, but restart_on_error: false only reports them"
+                    .to_string(),
+            ));
+        }
+        if self.profile_startup
+            && self.config.ready_pattern.is_none()
+            && self.config.ready_command.is_none()
+        {
+            eprintln!(
+                "{} --profile-startup reports when the server is ready, which needs ready_pattern or ready_command",
+                theme::icon(Icon::Warning)
+            );
+        }
+        if let Some(force_kill) = &self.force_kill_handle {
+            force_kill.store(!self.config.no_force_kill, Ordering::SeqCst);
+        }
+        if let Some(nohup) = &self.nohup_handle {
+            nohup.store(self.config.nohup_mode, Ordering::SeqCst);
+        }
 
+        // In raw mode stdout carries only the server's output
+        let monitor_log = match self.config.monitor_log.as_deref() {
+            None if self.raw => Some(status::STDERR_TARGET),
+            monitor_log => monitor_log,
+        };
+        status::init(monitor_log)?;
+        theme::init(self.config.theme);
         self.print_startup_info();
 
-        let mut restart_count = 0;
-        let monitor = ProcessMonitor::new(self.config.clone());
+        if let Some(warmup) = &self.config.warmup {
+            hooks::run_warmup(warmup)?;
+        }
+        let mut hooks = HookRunner::new(self.background_pids_handle.clone());
+        if let Some(pre_commands) = &self.config.pre_commands {
+            hooks.run("pre_commands", pre_commands)?;
+        }
+        self.wait_initial_delay();
+        self.wait_for_start()?;
+
+        if let Some(rerunner) = self.rerunner()? {
+            // Only Ctrl+C ends it, and its handler stops the background pre_commands too
+            rerunner.run();
+        }
+
+        let session = Record::Session {
+            version: RECORD_FORMAT_VERSION,
+            dev_version: env!("CARGO_PKG_VERSION"),
+            run_command: self
+                .cli_config
+                .as_ref()
+                .map(|c| c.run_command.clone())
+                .unwrap_or_default(),
+            error_patterns: self.config.error_patterns.clone(),
+        };
+        let (recorder, record_worker) = record::start(self.record.as_deref(), session)?;
+        if let Some(path) = &self.record {
+            status!(
+                "{} Recording this session to {}",
+                theme::icon(Icon::Config),
+                path
+            );
+        }
+        let (events, event_worker) = events::start(self.config.event_command.clone());
+        let mut events = events.with_recorder(recorder.clone());
+        if let Some(path) = &self.config.event_log {
+            events = events.with_log(Arc::new(EventLog::open(path)?));
+        }
+        let mut monitor = ProcessMonitor::new(self.config.clone())
+            .with_events(events.clone())
+            .with_recorder(recorder.clone())
+            .with_stop_when_ready(self.repeat.is_some())
+            .with_raw_output(self.raw)
+            .with_profile_startup(self.profile_startup)
+            .with_stats(Arc::clone(&self.stats));
+        if let Some(path) = &self.config.log_file {
+            monitor = monitor.with_child_log(Arc::new(ChildLog::open(path)?));
+        }
+        if let Some(cli_config) = self.cli_config.as_ref().filter(|c| c.watch_config) {
+            status!(
+                "{} Watching {} for changes",
+                theme::icon(Icon::Watch),
+                self.config_path.display()
+            );
+            let reloads = reload::watch(
+                self.config_path.clone(),
+                self.profile.clone(),
+                cli_config.clone(),
+                reload::WATCH_INTERVAL,
+                self.config.watch_debounce,
+                {
+                    let pattern_set = self.pattern_set.clone();
+                    move |cli_config| with_pattern_set(cli_config, pattern_set.as_deref())
+                },
+            );
+            monitor = monitor.with_config_reloads(self.config_path.clone(), reloads);
+        }
+        monitor.reset_ready_state()?;
+
+        let result = if self.fail_fast {
+            self.run_fail_fast(&monitor, &events)
+        } else {
+            match self.repeat {
+                Some(iterations) => self
+                    .run_repeated(&mut monitor, &events, iterations)
+                    .map(|()| RunOutcome::Completed),
+                None => self.run_attempts(&mut monitor, &events),
+            }
+        };
+
+        // Let queued lifecycle events reach the event command before returning
+        drop(monitor);
+        drop(events);
+        if let Some(worker) = event_worker {
+            worker.finish();
+        }
+        drop(recorder);
+        if let Some(worker) = record_worker {
+            worker.finish();
+        }
+        hooks.stop_background();
+        result
+    }
+
+    /// The configuration with the command-line settings that replace parts of it
+    fn with_overrides(&self, cli_config: CliConfig) -> CliConfig {
+        with_pattern_set(cli_config, self.pattern_set.as_deref())
+    }
+
+    /// The fresh copy every start runs in, when `fresh_workdir` is set
+    fn fresh_workdir(&self) -> Result<Option<Arc<FreshWorkdir>>> {
+        let Some(source) = &self.config.fresh_workdir else {
+            return Ok(None);
+        };
+        let current = self.fresh_workdir_handle.clone().unwrap_or_default();
+        Ok(Some(Arc::new(FreshWorkdir::new(source, current)?)))
+    }
+
+    /// Give the environment time to come up before the first spawn; restarts don't wait
+    fn wait_initial_delay(&self) {
+        let Some(delay) = self.config.initial_delay.filter(|delay| !delay.is_zero()) else {
+            return;
+        };
+        if self.skip_initial_delay {
+            return;
+        }
+        status!(
+            "{} Waiting {}s before starting...",
+            theme::icon(Icon::Waiting),
+            delay.as_secs_f64()
+        );
+        thread::sleep(delay);
+    }
+
+    /// Hold the first start until Enter is pressed (`--wait-start`), e.g. to explain a demo.
+    /// Without a terminal to press it in, nothing waits; the input timeout still applies.
+    fn wait_for_start(&self) -> Result<()> {
+        if !self.wait_start || !io::stdin().is_terminal() {
+            return Ok(());
+        }
+        status!(
+            "{} Press Enter to start the dev server...",
+            theme::icon(Icon::Waiting)
+        );
+        LineReader::stdin(prompt::input_timeout_from_env()).read_line()?;
+        Ok(())
+    }
+
+    /// What to do about an attempt that ended with `reason`: the plugged-in policy's call,
+    /// or the one dev-cli.json describes
+    fn decide(&self, reason: &RestartReason, stats: &SessionStats) -> Decision {
+        match &self.restart_policy {
+            Some(policy) => policy.decide(reason, stats),
+            None => DefaultPolicy::new(&self.config).decide(reason, stats),
+        }
+    }
+
+    /// Ask before restarting after an error (`confirm_restart`); returns false to quit.
+    /// Only asks when someone can answer: stdin is a terminal and `--yes` isn't set.
+    fn confirm_restart(&self) -> Result<bool> {
+        if !self.config.confirm_restart || self.assume_yes || !io::stdin().is_terminal() {
+            return Ok(true);
+        }
+        match LineReader::stdin(self.config.confirm_restart_timeout).restart_choice()? {
+            RestartChoice::Restart => Ok(true),
+            RestartChoice::Quit => Ok(false),
+            RestartChoice::Wait => {
+                status!(
+                    "{} Paused; press Enter to restart or type q to quit",
+                    theme::icon(Icon::Waiting)
+                );
+                let answer = LineReader::stdin(None).read_line()?;
+                Ok(answer.is_none_or(|answer| {
+                    !matches!(answer.trim().to_ascii_lowercase().as_str(), "q" | "quit")
+                }))
+            }
+        }
+    }
+
+    /// Take a restart from the `restart_burst`/`restart_refill` budget, waiting for one to be
+    /// earned back if it's used up; returns false to stop instead (`restart_budget_strict`)
+    fn wait_for_restart_budget(&self, bucket: &mut TokenBucket) -> bool {
+        while let Err(wait) = bucket.try_take(Instant::now()) {
+            if self
+                .config
+                .restart_budget
+                .is_some_and(|budget| budget.strict)
+            {
+                eprintln!(
+                    "{} Restart budget used up; not restarting (restart_budget_strict)",
+                    theme::icon(Icon::Failure)
+                );
+                return false;
+            }
+            status!(
+                "{} Restart budget used up; restarting in {:.1}s",
+                theme::icon(Icon::Waiting),
+                wait.as_secs_f64()
+            );
+            thread::sleep(wait);
+        }
+        true
+    }
+
+    /// Run the `on_clean_exit` command, if configured, once the server has exited normally
+    fn run_on_clean_exit(&self, attempt: u32, stats: &SharedStats) {
+        if let Some(command) = &self.config.on_clean_exit {
+            let restarts = stats.lock().unwrap().restarts;
+            hooks::run_on_clean_exit(command, attempt, restarts);
+        }
+    }
+
+    /// Run the `on_restart` command, if configured, before restarting for this reason
+    fn run_on_restart(&self, reason: &RestartReason, attempt: u32, stats: &SharedStats) {
+        if let Some(command) = &self.config.on_restart {
+            let restarts = stats.lock().unwrap().restarts;
+            hooks::run_on_restart(command, reason.kind(), &reason.detail(), attempt, restarts);
+        }
+    }
+
+    /// Run a `recovery_command` where the server runs and with its environment
+    fn run_recovery(&self, trigger: &str, command: &str) {
+        let working_dir = self
+            .cli_config
+            .as_ref()
+            .and_then(|cli_config| cli_config.working_dir.as_deref());
+        hooks::run_recovery(trigger, command, working_dir, &self.env);
+    }
+
+    /// The rerun loop, when the command is to be rerun on file changes instead of kept running
+    fn rerunner(&self) -> Result<Option<Rerunner>> {
+        let Some(cli_config) = &self.cli_config else {
+            return Ok(None);
+        };
+        if !self.watch_command && self.config.mode.is_server() {
+            return Ok(None);
+        }
+        if self.fail_fast || self.repeat.is_some() {
+            return Err(ServerError::ConfigError(
+                "command mode reruns on file changes; it can't be combined with --fail-fast or --repeat"
+                    .to_string(),
+            ));
+        }
+
+        let mut cli_config = cli_config.clone();
+        if self.wrapper.is_some() {
+            cli_config.wrapper = self.wrapper.clone();
+        }
+        let watch_paths = if self.config.watch_paths.is_empty() {
+            vec![PathBuf::from(".")]
+        } else {
+            self.config.watch_paths.clone()
+        };
+        // What dev itself writes under the watched paths doesn't count as a change
+        let written = [
+            self.config
+                .monitor_log
+                .as_deref()
+                .filter(|log| *log != status::STDERR_TARGET),
+            self.config.log_file.as_deref(),
+            self.config.event_log.as_deref(),
+            self.record.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(PathBuf::from)
+        .chain(self.config.state_file.clone())
+        .collect();
+        Ok(Some(
+            Rerunner::new(cli_config, watch_paths, self.config.watch_debounce)
+                .with_ignored(written)
+                .with_env(self.env.clone())
+                .with_pid_handle(self.child_pid_handle.clone())
+                .with_fresh_workdir(self.fresh_workdir.clone()),
+        ))
+    }
+
+    fn run_attempts(
+        &mut self,
+        monitor: &mut ProcessMonitor,
+        events: &EventEmitter,
+    ) -> Result<RunOutcome> {
+        let stats = Arc::clone(&self.stats);
+        let mut spawn_backoff =
+            Backoff::new(self.config.error_delay, self.config.spawn_backoff_cap);
+        let mut consecutive_spawn_failures = 0;
+        let mut restart_budget = self
+            .config
+            .restart_budget
+            .map(|budget| TokenBucket::new(&budget, Instant::now()));
+        let mut restart_backoff = self.config.backoff_multiplier.map(|multiplier| {
+            RestartBackoff::new(
+                multiplier,
+                self.config.max_restart_delay,
+                self.config.stability_window,
+            )
+        });
 
         loop {
-            restart_count += 1;
-            println!("📡 Starting dev server (attempt #{})...", restart_count);
-
-            match self.start_server_attempt(&monitor) {
-                Ok(should_restart) => {
-                    if should_restart {
-                        println!("\n🔄 Error detected! Restarting dev server...\n");
-                        thread::sleep(self.config.restart_delay);
-                    } else {
-                        println!("\n✅ Dev server exited normally");
-                        break;
+            let (attempt, restarts) = {
+                let mut stats = stats.lock().unwrap();
+                stats.attempts += 1;
+                (stats.attempts, stats.restarts)
+            };
+            status!(
+                "{} Starting dev server (attempt #{})...",
+                theme::icon(Icon::Start),
+                attempt
+            );
+
+            let started = Instant::now();
+            let result = self.start_server_attempt(monitor, events, attempt, restarts);
+            // With a ready check, the stability window counts from readiness: a server
+            // that never got ready hasn't been up at all
+            let uptime =
+                if self.config.ready_pattern.is_some() || self.config.ready_command.is_some() {
+                    monitor
+                        .attempt_ready_at()
+                        .map_or(Duration::ZERO, |ready_at| ready_at.elapsed())
+                } else {
+                    started.elapsed()
+                };
+            if let Some(cli_config) = monitor.take_reloaded_config() {
+                self.apply_reloaded_config(monitor, cli_config);
+            }
+
+            match result {
+                Ok(Some(RestartReason::ConfigChanged)) => {
+                    consecutive_spawn_failures = 0;
+                    spawn_backoff.reset();
+                    events.emit(
+                        LifecycleEvent::new(EventKind::Restart, attempt)
+                            .with_message("config changed"),
+                    );
+                    status!(
+                        "\n{} Restarting dev server with the new configuration...\n",
+                        theme::icon(Icon::Reload)
+                    );
+                    self.run_on_restart(&RestartReason::ConfigChanged, attempt, &stats);
+                }
+                Ok(Some(reason)) => {
+                    let decision = self.decide(&reason, &stats.lock().unwrap());
+                    let mut delay = match decision {
+                        Decision::Restart { delay } => delay,
+                        Decision::Stop { outcome, message } => {
+                            if let Some(message) = message {
+                                eprintln!("{} {}", theme::icon(Icon::Failure), message);
+                            }
+                            status!("{}", stats.lock().unwrap().summary());
+                            return Ok(outcome);
+                        }
+                    };
+                    if matches!(reason, RestartReason::ErrorDetected(_))
+                        && !self.confirm_restart()?
+                    {
+                        status!("{}", stats.lock().unwrap().summary());
+                        return Ok(RunOutcome::Completed);
+                    }
+                    // A clean exit that `exit_code_rules` restarts on isn't a crash
+                    let crashed = !matches!(reason, RestartReason::ExitRule(_));
+                    if crashed
+                        && let Some(bucket) = &mut restart_budget
+                        && !self.wait_for_restart_budget(bucket)
+                    {
+                        status!("{}", stats.lock().unwrap().summary());
+                        return Ok(RunOutcome::ErrorExit);
+                    }
+                    consecutive_spawn_failures = 0;
+                    spawn_backoff.reset();
+                    if crashed {
+                        stats.lock().unwrap().restarts += 1;
                     }
+                    events.emit(LifecycleEvent::new(EventKind::Restart, attempt));
+                    match reason {
+                        RestartReason::ExitRule(_) => {
+                            status!(
+                                "\n{} Dev server exited; restarting (exit_code_rules)...\n",
+                                theme::icon(Icon::Restart)
+                            )
+                        }
+                        RestartReason::ExitFailure(_) => {
+                            status!(
+                                "\n{} Dev server exited with an error! Restarting...\n",
+                                theme::icon(Icon::Restart)
+                            )
+                        }
+                        RestartReason::ResourceLimit => {
+                            status!(
+                                "\n{} Restarting dev server to free resources...\n",
+                                theme::icon(Icon::Restart)
+                            )
+                        }
+                        RestartReason::OutputClosed => {
+                            status!(
+                                "\n{} Dev server output ended! Restarting...\n",
+                                theme::icon(Icon::Restart)
+                            )
+                        }
+                        RestartReason::Stalled(_) => {
+                            status!(
+                                "\n{} Dev server stalled! Restarting...\n",
+                                theme::icon(Icon::Restart)
+                            )
+                        }
+                        RestartReason::ConditionMet(_) => {
+                            status!(
+                                "\n{} restart_when rule met! Restarting dev server...\n",
+                                theme::icon(Icon::Restart)
+                            )
+                        }
+                        _ => status!(
+                            "\n{} Error detected! Restarting dev server...\n",
+                            theme::icon(Icon::Restart)
+                        ),
+                    }
+                    if let RestartReason::ErrorDetected(error) = &reason
+                        && let Some(command) = self.config.recovery_command_for(&error.pattern)
+                    {
+                        self.run_recovery(&error.pattern, command);
+                    }
+                    let exit_code = match reason {
+                        RestartReason::ExitFailure(code) => Some(code),
+                        RestartReason::ExitRule(code) => Some(Some(code)),
+                        _ => None,
+                    };
+                    if let Some(code) = exit_code
+                        && let Some(rule) = self.config.exit_code_rule(code)
+                        && let Some(command) = &rule.recovery_command
+                    {
+                        self.run_recovery(&rule.to_string(), command);
+                    }
+                    self.run_on_restart(&reason, attempt, &stats);
+                    if crashed && let Some(backoff) = &mut restart_backoff {
+                        let base = delay;
+                        delay = backoff.delay(base, uptime);
+                        if delay > base {
+                            status!(
+                                "{} Crashed again without staying up for {}; waiting {:.1}s before restarting",
+                                theme::icon(Icon::Waiting),
+                                format_duration(self.config.stability_window),
+                                delay.as_secs_f64()
+                            );
+                        }
+                    }
+                    thread::sleep(delay);
+                }
+                Ok(None) => {
+                    status!(
+                        "\n{} Dev server exited normally",
+                        theme::icon(Icon::Success)
+                    );
+                    self.run_on_clean_exit(attempt, &stats);
+                    break;
+                }
+                Err(e @ (ServerError::ProcessStart(_) | ServerError::ProcessManagement(_))) => {
+                    stats.lock().unwrap().spawn_failures += 1;
+                    consecutive_spawn_failures += 1;
+                    eprintln!(
+                        "{} Failed to start dev server: {}",
+                        theme::icon(Icon::Failure),
+                        e
+                    );
+
+                    if let Some(after) = self.config.fallback_after_failures
+                        && consecutive_spawn_failures >= after
+                        && self.fall_back_to_last_good_command()
+                    {
+                        consecutive_spawn_failures = 0;
+                        spawn_backoff.reset();
+                        continue;
+                    }
+
+                    if let Some(max) = self.config.max_spawn_retries
+                        && consecutive_spawn_failures > max
+                    {
+                        status!("{}", stats.lock().unwrap().summary());
+                        return Err(ServerError::ProcessStart(format!(
+                            "giving up after {} consecutive spawn failures",
+                            consecutive_spawn_failures
+                        )));
+                    }
+
+                    let delay = spawn_backoff.next_delay();
+                    status!(
+                        "{} Retrying spawn in {:.1}s...",
+                        theme::icon(Icon::Waiting),
+                        delay.as_secs_f64()
+                    );
+                    thread::sleep(delay);
                 }
                 Err(e) => {
-                    eprintln!("❌ Failed to start dev server: {}", e);
+                    eprintln!(
+                        "{} Dev server attempt failed: {}",
+                        theme::icon(Icon::Failure),
+                        e
+                    );
                     thread::sleep(self.config.error_delay);
                 }
             }
         }
 
+        status!("{}", stats.lock().unwrap().summary());
+        Ok(RunOutcome::Completed)
+    }
+
+    /// Run one attempt; the first error pattern or crash ends the session as a failure
+    fn run_fail_fast(&self, monitor: &ProcessMonitor, events: &EventEmitter) -> Result<RunOutcome> {
+        status!(
+            "{} Starting dev server (fail fast)...",
+            theme::icon(Icon::Start)
+        );
+        self.stats.lock().unwrap().attempts += 1;
+
+        match self.start_server_attempt(monitor, events, 1, 0)? {
+            // A clean exit, even if `exit_code_rules` would have restarted it
+            None | Some(RestartReason::ExitRule(_)) => {
+                status!(
+                    "\n{} Dev server exited normally",
+                    theme::icon(Icon::Success)
+                );
+                self.run_on_clean_exit(1, &self.stats);
+                Ok(RunOutcome::Completed)
+            }
+            Some(RestartReason::ReadyTimeout(_)) => Ok(RunOutcome::ReadyTimeout),
+            Some(RestartReason::ErrorDetected(error)) => {
+                eprintln!(
+                    "{} Error pattern '{}' matched: {}",
+                    theme::icon(Icon::Failure),
+                    error.pattern,
+                    error.line
+                );
+                Ok(RunOutcome::ErrorExit)
+            }
+            Some(reason) => {
+                eprintln!(
+                    "{} Dev server failed: {}",
+                    theme::icon(Icon::Failure),
+                    reason
+                );
+                Ok(RunOutcome::ErrorExit)
+            }
+        }
+    }
+
+    /// Run `iterations` attempts back to back and tally which ones succeeded.
+    /// A run succeeds when the server becomes ready or exits cleanly.
+    fn run_repeated(
+        &mut self,
+        monitor: &mut ProcessMonitor,
+        events: &EventEmitter,
+        iterations: u32,
+    ) -> Result<()> {
+        let mut tally = RepeatTally::default();
+        for attempt in 1..=iterations {
+            status!(
+                "{} Starting dev server (run {}/{})...",
+                theme::icon(Icon::Start),
+                attempt,
+                iterations
+            );
+            self.stats.lock().unwrap().attempts += 1;
+            // Every run is a fresh launch with its own time to become ready
+            monitor.restart_ready_clock();
+
+            let result = self.start_server_attempt(monitor, events, attempt, attempt - 1);
+            if let Some(cli_config) = monitor.take_reloaded_config() {
+                self.apply_reloaded_config(monitor, cli_config);
+            }
+
+            match result {
+                Ok(None | Some(RestartReason::ExitRule(_))) => {
+                    status!("{} Run {} succeeded", theme::icon(Icon::Success), attempt);
+                    tally.record_success();
+                }
+                Ok(Some(reason)) => {
+                    status!(
+                        "{} Run {} failed: {}",
+                        theme::icon(Icon::Failure),
+                        attempt,
+                        reason
+                    );
+                    tally.record_failure(reason.to_string());
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{} Run {} failed: {}",
+                        theme::icon(Icon::Failure),
+                        attempt,
+                        e
+                    );
+                    tally.record_failure(e.to_string());
+                }
+            }
+
+            if attempt < iterations {
+                thread::sleep(self.config.restart_delay);
+            }
+        }
+
+        status!("{}", tally.summary());
+        if let Some(patterns) = self.stats.lock().unwrap().pattern_summary() {
+            status!("{}", patterns);
+        }
+        Ok(())
+    }
+
+    /// Rebuild the runtime configuration from a reloaded config file. A reload that can't be
+    /// applied (a secret that can't be fetched, a fresh workdir that can't be copied) is
+    /// reported and the current configuration stays.
+    fn apply_reloaded_config(&mut self, monitor: &mut ProcessMonitor, cli_config: CliConfig) {
+        if let Err(e) = self.try_apply_reloaded_config(monitor, cli_config) {
+            eprintln!(
+                "{} Keeping the current configuration: {}",
+                theme::icon(Icon::Warning),
+                e
+            );
+            // The monitor already switched to the reloaded patterns
+            monitor.update_config(self.config.clone());
+        }
+    }
+
+    fn try_apply_reloaded_config(
+        &mut self,
+        monitor: &mut ProcessMonitor,
+        cli_config: CliConfig,
+    ) -> Result<()> {
+        let cli_config = self.with_overrides(cli_config);
+        let config = Config::new().with_cli_config(&cli_config)?;
+        let env = secrets::resolve(&cli_config.env, cli_config.secret_command.as_deref())?;
+        let previous = mem::replace(&mut self.config, config);
+        // Rebuilt for a changed source or .gitignore; the old copy goes first, since both
+        // share the Ctrl+C handle
+        self.fresh_workdir = None;
+        match self.fresh_workdir() {
+            Ok(fresh_workdir) => self.fresh_workdir = fresh_workdir,
+            Err(e) => {
+                self.config = previous;
+                self.fresh_workdir = self.fresh_workdir()?;
+                return Err(e);
+            }
+        }
+        self.env = env;
+        if let Some(force_kill) = &self.force_kill_handle {
+            force_kill.store(!self.config.no_force_kill, Ordering::SeqCst);
+        }
+        if let Some(nohup) = &self.nohup_handle {
+            nohup.store(self.config.nohup_mode, Ordering::SeqCst);
+        }
+        monitor.update_config(self.config.clone());
+        self.cli_config = Some(cli_config);
         Ok(())
     }
 
-    fn start_server_attempt(&self, monitor: &ProcessMonitor) -> Result<bool> {
+    /// Switch to the command that last reached readiness, if it differs from the current one.
+    /// Returns whether the run command was changed.
+    fn fall_back_to_last_good_command(&mut self) -> bool {
+        let (Some(state_file), Some(cli_config)) = (&self.config.state_file, &mut self.cli_config)
+        else {
+            return false;
+        };
+        let store = StateStore::new(state_file);
+        let last_good = match store.load() {
+            Ok(state) => state.last_good_command,
+            Err(e) => {
+                eprintln!("Failed to read state file: {}", e);
+                return false;
+            }
+        };
+        let Some(last_good) = last_good.filter(|command| *command != cli_config.run_command) else {
+            return false;
+        };
+
+        status!(
+            "{} '{}' keeps failing to start; the last command that reached readiness was '{}'",
+            theme::icon(Icon::Hint),
+            cli_config.run_command,
+            last_good
+        );
+        if !self.assume_yes && !prompt::confirm("Fall back to the last known good command?", false)
+        {
+            return false;
+        }
+
+        status!(
+            "{} Falling back to last known good command for this session: {}",
+            theme::icon(Icon::Fallback),
+            last_good
+        );
+        status!(
+            "   {} was not changed; fix run_command there to make it permanent",
+            self.config_path.display()
+        );
+        cli_config.run_command = last_good;
+        true
+    }
+
+    fn start_server_attempt(
+        &self,
+        monitor: &ProcessMonitor,
+        events: &EventEmitter,
+        attempt: u32,
+        restarts: u32,
+    ) -> Result<Option<RestartReason>> {
         let command_type = if self.test_mode {
             CommandType::Test
         } else {
-            CommandType::Dev(self.cli_config.as_ref().unwrap().clone())
+            let mut cli_config = self.cli_config.as_ref().unwrap().clone();
+            if self.wrapper.is_some() {
+                cli_config.wrapper = self.wrapper.clone();
+            }
+            CommandType::Dev(Box::new(cli_config))
         };
 
-        let command = CommandBuilder::build(command_type);
+        let mut command = CommandBuilder::build(command_type);
+        self.env.apply(&mut command);
+        if let Some(workdir) = &self.fresh_workdir {
+            command.current_dir(workdir.refresh()?);
+        }
+        if self.config.echo_command {
+            status!("$ {}", command::describe(&command, self.env.secret_names()));
+            if let Some(dir) = command.get_current_dir() {
+                status!("  in {}", dir.display());
+            }
+        }
         let process = if let Some(ref pid_handle) = self.child_pid_handle {
-            ProcessManager::spawn_with_pid_handle(command, pid_handle.clone())?
+            ProcessManager::spawn_with_pid_handle(command, self.config.capture, pid_handle.clone())?
         } else {
-            ProcessManager::spawn(command)?
+            ProcessManager::spawn_capturing(command, self.config.capture)?
         };
-        monitor.monitor(process)
+        events.emit(LifecycleEvent::new(EventKind::Start, attempt).with_pid(process.id()));
+
+        // Remember the process so `dev --cleanup` can find it if dev dies without stopping it
+        let pid = process.id();
+        let store = self.config.state_file.as_ref().map(StateStore::new);
+        if let Some(store) = &store {
+            let command = process::command_line(pid).unwrap_or_default();
+            if let Err(e) = store.record_spawned(pid, &command) {
+                eprintln!("Failed to update state file: {}", e);
+            }
+        }
+
+        let attempt = AttemptInfo {
+            number: attempt,
+            run_command: self.cli_config.as_ref().map(|c| c.run_command.clone()),
+            restarts,
+        };
+        let result = monitor.monitor(process, &attempt);
+        if let Some(store) = &store
+            && let Err(e) = store.forget_spawned(pid)
+        {
+            eprintln!("Failed to update state file: {}", e);
+        }
+        result
     }
 
     fn print_startup_info(&self) {
         if self.test_mode {
-            println!("🧪 Running in test mode");
+            status!("{} Running in test mode", theme::icon(Icon::Test));
         } else if let Some(cli_config) = &self.cli_config {
-            println!(
-                "🚀 Starting dev server monitor for: {}",
+            status!(
+                "{} Starting dev server monitor for: {}",
+                theme::icon(Icon::Launch),
                 cli_config.run_command
             );
         } else {
-            println!("🚀 Starting dev server monitor...");
+            status!(
+                "{} Starting dev server monitor...",
+                theme::icon(Icon::Launch)
+            );
+        }
+
+        if !self.test_mode && (self.watch_command || !self.config.mode.is_server()) {
+            status!("Press Ctrl+C to stop\n");
+            return;
+        }
+        let action = if self.fail_fast {
+            "exit"
+        } else if self.config.restart_on_error {
+            "restart"
+        } else {
+            "report"
+        };
+        let mut watched: Vec<String> = self
+            .config
+            .error_patterns
+            .iter()
+            .map(|pattern| format!("'{}'", pattern))
+            .collect();
+        if !self.config.regex_patterns.is_empty() {
+            watched.push(format!(
+                "{} regex pattern(s)",
+                self.config.regex_patterns.len()
+            ));
         }
+        status!(
+            "Monitoring for {} in output - will {} on detection",
+            watched.join(", "),
+            action
+        );
+        if !self.fail_fast
+            && let Some(policy) = self.config.restart_policy()
+        {
+            status!("{}", policy);
+        }
+        status!("Press Ctrl+C to stop\n");
+    }
+}
+
+/// `cli_config` with `--patterns` in place of its `pattern_set`; also applied to every
+/// reload, so an edit to the file doesn't drop it
+fn with_pattern_set(mut cli_config: CliConfig, pattern_set: Option<&str>) -> CliConfig {
+    if let Some(pattern_set) = pattern_set {
+        cli_config.pattern_set = Some(pattern_set.to_string());
+    }
+    cli_config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_initial_delay_elapses_before_the_first_spawn() {
+        let delay = Duration::from_millis(400);
+        let pid_handle = Arc::new(Mutex::new(None));
+        let mut server = DevServer::new(Config::new().with_initial_delay(delay), true);
+        server.set_child_pid_handle(Arc::clone(&pid_handle));
+        server.set_fail_fast(true);
+
+        let started = Instant::now();
+        let session = thread::spawn(move || server.run());
+        let pid = loop {
+            if let Some(pid) = *pid_handle.lock().unwrap() {
+                break pid;
+            }
+            assert!(started.elapsed() < Duration::from_secs(10), "never spawned");
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert!(
+            started.elapsed() >= delay,
+            "spawned after {:?}",
+            started.elapsed()
+        );
+
+        process::signal_pid(pid, true);
+        assert_eq!(session.join().unwrap().unwrap(), RunOutcome::ErrorExit);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_on_clean_exit_runs_only_when_the_server_exits_normally() {
+        let marker = |name: &str| std::env::temp_dir().join(format!("dev-cli-test-{}", name));
+        let session = |error_pattern: &str, name: &str| {
+            let out = marker(name);
+            let _ = std::fs::remove_file(&out);
+            let config = Config::new()
+                .with_error_pattern(error_pattern)
+                .with_on_clean_exit(format!("echo \"$DEV_ATTEMPT\" > '{}'", out.display()));
+            let mut server = DevServer::new(config, true);
+            server.set_fail_fast(true);
+            thread::spawn(move || server.run())
+        };
+
+        // The test command prints "[Error]: Simulated test error" before exiting with 0
+        let crashed = session("[Error]", "on-clean-exit-error");
+        let clean = session("never printed", "on-clean-exit-clean");
+        assert_eq!(crashed.join().unwrap().unwrap(), RunOutcome::ErrorExit);
+        assert_eq!(clean.join().unwrap().unwrap(), RunOutcome::Completed);
+
+        assert!(!marker("on-clean-exit-error").exists());
+        let out = marker("on-clean-exit-clean");
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "1\n");
+        std::fs::remove_file(&out).unwrap();
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_warmup_runs_once_across_restarts() {
+        let marker = std::env::temp_dir().join("dev-cli-test-warmup-once");
+        let _ = std::fs::remove_file(&marker);
+        let mut config = Config::new()
+            .with_error_pattern("[Error]")
+            .with_restart_delay(Duration::ZERO)
+            .with_warmup(format!("echo warmed >> '{}'", marker.display()));
+        config.max_restarts = Some(1);
+        let mut server = DevServer::new(config, true);
+        let stats = SharedStats::default();
+        server.set_stats_handle(Arc::clone(&stats));
+        server.set_assume_yes(true);
+
+        // The second error is past max_restarts and ends the session
+        assert_eq!(server.run().unwrap(), RunOutcome::ErrorExit);
+        assert_eq!(stats.lock().unwrap().restarts, 1);
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "warmed\n");
+        std::fs::remove_file(&marker).unwrap();
+    }
+
+    /// Run `script` as the dev command with the two restart triggers set as given and
+    /// `max_restarts: 1`; returns how the session ended and how many attempts it made
+    fn run_with_triggers(
+        name: &str,
+        script: &str,
+        restart_on_error: bool,
+        restart_on_nonzero_exit: bool,
+    ) -> (RunOutcome, u32) {
+        let path = std::env::temp_dir().join(format!("dev-cli-test-{}.json", name));
+        let cli_config = CliConfig {
+            run_command: script.to_string(),
+            error_pattern: "BOOM".into(),
+            restart_on_error: Some(restart_on_error),
+            restart_on_nonzero_exit: Some(restart_on_nonzero_exit),
+            max_restarts: Some(1),
+            ..CliConfig::default()
+        };
+        std::fs::write(&path, serde_json::to_string(&cli_config).unwrap()).unwrap();
+
+        let config = Config::new().with_restart_delay(Duration::ZERO);
+        let mut server = DevServer::new(config, false);
+        server.set_config_path(path.clone());
+        let stats = SharedStats::default();
+        server.set_stats_handle(Arc::clone(&stats));
+        server.set_assume_yes(true);
+        let outcome = server.run().unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        let attempts = stats.lock().unwrap().attempts;
+        (outcome, attempts)
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_both_triggers_restart_by_default() {
+        let error = "echo BOOM; sleep 5";
+        let crash = "echo starting; exit 3";
+        // Restarted once, then stopped by max_restarts
+        assert_eq!(
+            run_with_triggers("triggers-both-error", error, true, true),
+            (RunOutcome::ErrorExit, 2)
+        );
+        assert_eq!(
+            run_with_triggers("triggers-both-exit", crash, true, true),
+            (RunOutcome::ErrorExit, 2)
+        );
+    }
 
-        println!(
-            "Monitoring for '{}' in output - will restart on detection",
-            self.config.error_pattern
+    #[cfg(not(windows))]
+    #[test]
+    fn test_error_patterns_only_restart_without_restart_on_nonzero_exit() {
+        assert_eq!(
+            run_with_triggers(
+                "triggers-error-only-error",
+                "echo BOOM; sleep 5",
+                true,
+                false
+            ),
+            (RunOutcome::ErrorExit, 2)
+        );
+        // A failed exit ends the session at once
+        assert_eq!(
+            run_with_triggers(
+                "triggers-error-only-exit",
+                "echo starting; exit 3",
+                true,
+                false
+            ),
+            (RunOutcome::ErrorExit, 1)
         );
-        println!("Press Ctrl+C to stop\n");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_errors_are_only_reported_without_restart_on_error() {
+        // The server keeps running past the error and exits cleanly
+        assert_eq!(
+            run_with_triggers(
+                "triggers-exit-only-error",
+                "echo BOOM; sleep 0.3; echo done",
+                false,
+                true
+            ),
+            (RunOutcome::Completed, 1)
+        );
+        assert_eq!(
+            run_with_triggers(
+                "triggers-exit-only-exit",
+                "echo starting; exit 3",
+                false,
+                true
+            ),
+            (RunOutcome::ErrorExit, 2)
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_command_runs_once_with_both_triggers_off() {
+        assert_eq!(
+            run_with_triggers(
+                "triggers-none",
+                "echo BOOM; sleep 0.3; exit 3",
+                false,
+                false
+            ),
+            (RunOutcome::ErrorExit, 1)
+        );
+    }
+
+    /// Restarts after the first error only, then ends the session as a success
+    struct RestartOnce;
+
+    impl RestartPolicy for RestartOnce {
+        fn decide(&self, reason: &RestartReason, stats: &SessionStats) -> Decision {
+            assert!(matches!(reason, RestartReason::ErrorDetected(_)));
+            if stats.restarts == 0 {
+                Decision::Restart {
+                    delay: Duration::ZERO,
+                }
+            } else {
+                Decision::Stop {
+                    outcome: RunOutcome::Completed,
+                    message: None,
+                }
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_custom_restart_policy_decides_instead_of_the_config() {
+        // Without the policy, the config would restart on every error
+        let mut server = DevServer::new(Config::new().with_error_pattern("[Error]"), true);
+        let stats = SharedStats::default();
+        server.set_stats_handle(Arc::clone(&stats));
+        server.set_assume_yes(true);
+        server.set_restart_policy(Box::new(RestartOnce));
+
+        assert_eq!(server.run().unwrap(), RunOutcome::Completed);
+        let stats = stats.lock().unwrap();
+        assert_eq!((stats.attempts, stats.restarts), (2, 1));
     }
 }
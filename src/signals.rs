@@ -0,0 +1,137 @@
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+
+use crate::error::{Result, ServerError};
+
+const SIGNAL_NAMES: [(&str, i32); 15] = [
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+];
+
+/// Signal number for a name such as `SIGTERM`, `term` or `15`
+pub fn parse_signal(name: &str) -> Option<i32> {
+    let name = name.trim();
+    if let Ok(number) = name.parse::<i32>() {
+        return (number > 0).then_some(number);
+    }
+    let upper = name.to_ascii_uppercase();
+    let short = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNAL_NAMES
+        .iter()
+        .find(|(signal, _)| *signal == short)
+        .map(|(_, number)| *number)
+}
+
+/// Conventional name of a signal number, e.g. `SIGKILL` for 9
+pub fn signal_name(number: i32) -> Option<String> {
+    SIGNAL_NAMES
+        .iter()
+        .find(|(_, signal)| *signal == number)
+        .map(|(name, _)| format!("SIG{}", name))
+}
+
+/// Parse a `crash_signals` / `stop_signals` list from dev-cli.json
+pub fn parse_signals(field: &str, names: &[String]) -> Result<Vec<i32>> {
+    names
+        .iter()
+        .map(|name| {
+            parse_signal(name).ok_or_else(|| {
+                ServerError::ConfigError(format!("{}: unknown signal '{}'", field, name))
+            })
+        })
+        .collect()
+}
+
+/// Signal that ended the server. An exit code above 128 is not one: the server may exit
+/// with any code it likes, so only a real signal death counts.
+pub fn exit_signal(status: &ExitStatus) -> Option<i32> {
+    status.signal()
+}
+
+/// Point stdin, stdout and stderr at /dev/null, so dev outlives a closed terminal
+/// (`nohup_mode`) instead of failing on its next write
+pub fn detach_from_terminal() {
+    // SAFETY: plain file descriptor calls on a path that outlives them
+    unsafe {
+        let null = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+        if null < 0 {
+            return;
+        }
+        for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+            libc::dup2(null, fd);
+        }
+        if null > libc::STDERR_FILENO {
+            libc::close(null);
+        }
+    }
+}
+
+/// Whether a server ended by `signal` crashed (restart) rather than was stopped.
+/// `stop_signals` always win; a non-empty `crash_signals` limits crashes to the listed signals.
+pub fn is_crash_signal(signal: i32, crash_signals: &[i32], stop_signals: &[i32]) -> bool {
+    if stop_signals.contains(&signal) {
+        return false;
+    }
+    crash_signals.is_empty() || crash_signals.contains(&signal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signal_names_and_numbers() {
+        assert_eq!(parse_signal("SIGTERM"), Some(libc::SIGTERM));
+        assert_eq!(parse_signal("segv"), Some(libc::SIGSEGV));
+        assert_eq!(parse_signal("9"), Some(9));
+        assert_eq!(parse_signal("SIGNOPE"), None);
+        assert!(parse_signals("stop_signals", &["INT".to_string(), "bogus".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_signal_name() {
+        assert_eq!(signal_name(libc::SIGKILL).as_deref(), Some("SIGKILL"));
+        assert_eq!(signal_name(0), None);
+    }
+
+    #[test]
+    fn test_default_mapping_restarts_on_every_signal() {
+        assert!(is_crash_signal(libc::SIGSEGV, &[], &[]));
+        assert!(is_crash_signal(libc::SIGTERM, &[], &[]));
+    }
+
+    #[test]
+    fn test_stop_and_crash_lists() {
+        let stop = [libc::SIGINT, libc::SIGTERM];
+        assert!(!is_crash_signal(libc::SIGTERM, &[], &stop));
+        assert!(is_crash_signal(libc::SIGSEGV, &[], &stop));
+
+        let crash = [libc::SIGSEGV, libc::SIGABRT];
+        assert!(is_crash_signal(libc::SIGABRT, &crash, &[]));
+        assert!(!is_crash_signal(libc::SIGHUP, &crash, &[]));
+        assert!(!is_crash_signal(libc::SIGSEGV, &crash, &[libc::SIGSEGV]));
+    }
+
+    #[test]
+    fn test_exit_signal_only_counts_signal_deaths() {
+        assert_eq!(
+            exit_signal(&ExitStatus::from_raw(libc::SIGSEGV)),
+            Some(libc::SIGSEGV)
+        );
+        assert_eq!(exit_signal(&ExitStatus::from_raw(139 << 8)), None);
+        assert_eq!(exit_signal(&ExitStatus::from_raw(1 << 8)), None);
+    }
+}
@@ -0,0 +1,214 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::stats::format_duration;
+use crate::theme::{self, Icon};
+
+/// Lines kept per boot; a longer boot keeps its latest lines, so its earliest gaps are lost
+pub const MAX_PROFILED_LINES: usize = 10_000;
+
+/// Gaps listed once the server is ready
+pub const REPORTED_GAPS: usize = 5;
+
+/// Shorter gaps are just lines printed together and aren't listed
+const MIN_REPORTED_GAP: Duration = Duration::from_millis(10);
+
+/// Every output line of one boot with when it arrived, from the spawn until the server is
+/// ready (`--profile-startup`)
+#[derive(Debug)]
+pub struct StartupProfile {
+    started: Instant,
+    lines: VecDeque<(Duration, String)>,
+    /// Lines pushed out of the buffer by newer ones
+    dropped: usize,
+}
+
+/// A stretch of the boot without output, between two lines or a line and the spawn or
+/// readiness (`None`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gap {
+    /// When the quiet stretch began, from the spawn
+    pub start: Duration,
+    pub length: Duration,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl StartupProfile {
+    pub fn new(started: Instant) -> Self {
+        Self {
+            started,
+            lines: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    pub fn record(&mut self, at: Instant, line: &str) {
+        if self.lines.len() == MAX_PROFILED_LINES {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines
+            .push_back((at.saturating_duration_since(self.started), line.to_string()));
+    }
+
+    /// The breakdown printed when the server became ready at `ready_at`
+    pub fn report(&self, ready_at: Instant) -> Vec<String> {
+        let ready = ready_at.saturating_duration_since(self.started);
+        let lines: Vec<(Duration, String)> = self.lines.iter().cloned().collect();
+        let gaps: Vec<Gap> = longest_gaps(&lines, ready, self.dropped == 0, REPORTED_GAPS)
+            .into_iter()
+            .filter(|gap| gap.length >= MIN_REPORTED_GAP)
+            .collect();
+
+        let mut report = vec![format!(
+            "{} Startup profile: {} line(s) in {}; longest gaps:",
+            theme::icon(Icon::Timing),
+            self.lines.len() + self.dropped,
+            format_duration(ready)
+        )];
+        if gaps.is_empty() {
+            report.push(format!(
+                "   No pause of {} or more",
+                format_duration(MIN_REPORTED_GAP)
+            ));
+        }
+        for gap in gaps {
+            report.push(format!(
+                "   {} after {}",
+                format_duration(gap.length),
+                format_duration(gap.start)
+            ));
+            report.push(format!(
+                "     before: {}",
+                gap.before.as_deref().unwrap_or("(spawn)")
+            ));
+            report.push(format!(
+                "     after:  {}",
+                gap.after.as_deref().unwrap_or("(ready)")
+            ));
+        }
+        if self.dropped > 0 {
+            report.push(format!(
+                "   Only the last {} lines were kept; earlier gaps aren't listed",
+                MAX_PROFILED_LINES
+            ));
+        }
+        report
+    }
+}
+
+/// The `count` longest gaps between consecutive `lines` (time from the spawn, text), longest
+/// first. Readiness at `ready` ends the last gap, and with `from_spawn` the spawn starts the
+/// first one. Equal gaps are listed in boot order.
+pub fn longest_gaps(
+    lines: &[(Duration, String)],
+    ready: Duration,
+    from_spawn: bool,
+    count: usize,
+) -> Vec<Gap> {
+    let mut points: Vec<(Duration, Option<&str>)> = Vec::with_capacity(lines.len() + 2);
+    if from_spawn {
+        points.push((Duration::ZERO, None));
+    }
+    points.extend(lines.iter().map(|(at, line)| (*at, Some(line.as_str()))));
+    points.push((ready, None));
+
+    let mut gaps: Vec<Gap> = points
+        .windows(2)
+        .map(|pair| Gap {
+            start: pair[0].0,
+            length: pair[1].0.saturating_sub(pair[0].0),
+            before: pair[0].1.map(str::to_string),
+            after: pair[1].1.map(str::to_string),
+        })
+        .collect();
+    // Stable, so ties keep their order
+    gaps.sort_by_key(|gap| Reverse(gap.length));
+    gaps.truncate(count);
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn boot(lines: &[(u64, &str)]) -> Vec<(Duration, String)> {
+        lines
+            .iter()
+            .map(|(at, line)| (ms(*at), line.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_longest_gaps_point_at_the_stalled_step() {
+        let lines = boot(&[
+            (30, "starting"),
+            (40, "loading config"),
+            (1840, "connected to db"),
+            (1900, "compiling assets"),
+            (2600, "assets compiled"),
+            (2650, "listening on 3000"),
+        ]);
+        let gaps = longest_gaps(&lines, ms(2700), true, 2);
+        assert_eq!(
+            gaps,
+            [
+                Gap {
+                    start: ms(40),
+                    length: ms(1800),
+                    before: Some("loading config".to_string()),
+                    after: Some("connected to db".to_string()),
+                },
+                Gap {
+                    start: ms(1900),
+                    length: ms(700),
+                    before: Some("compiling assets".to_string()),
+                    after: Some("assets compiled".to_string()),
+                },
+            ]
+        );
+
+        // A slow first line or a long wait for readiness after the last one are gaps too
+        let lines = boot(&[(900, "booting"), (950, "up")]);
+        let gaps = longest_gaps(&lines, ms(3000), true, 5);
+        assert_eq!(gaps.len(), 3);
+        assert_eq!((gaps[0].length, gaps[0].after.as_deref()), (ms(2050), None));
+        assert_eq!((gaps[1].length, gaps[1].before.as_deref()), (ms(900), None));
+        // Without the spawn (older lines were dropped), the first kept line starts
+        assert_eq!(longest_gaps(&lines, ms(3000), false, 5).len(), 2);
+        assert_eq!(longest_gaps(&[], ms(500), true, 5)[0].length, ms(500));
+    }
+
+    #[test]
+    fn test_report_lists_gaps_with_their_surrounding_lines() {
+        let started = Instant::now();
+        let mut profile = StartupProfile::new(started);
+        profile.record(started + ms(10), "booting");
+        profile.record(started + ms(1510), "migrations done");
+        let report = profile.report(started + ms(1600));
+        assert!(report[0].ends_with("Startup profile: 2 line(s) in 1.6s; longest gaps:"));
+        assert_eq!(
+            report[1..4],
+            [
+                "   1.5s after 10ms",
+                "     before: booting",
+                "     after:  migrations done",
+            ]
+        );
+        assert_eq!(report.len(), 10);
+
+        // Lines printed together aren't pauses
+        let mut profile = StartupProfile::new(started);
+        profile.record(started, "listening on 3000");
+        assert_eq!(
+            profile.report(started + ms(2))[1],
+            "   No pause of 10ms or more"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::{Result, ServerError};
+
+/// Data remembered between sessions in the configured state file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Last run command that made the server reach readiness
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_good_command: Option<String>,
+    /// Server processes started by dev that haven't been seen to exit yet
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spawned: Vec<SpawnedProcess>,
+}
+
+/// A server process recorded so `dev --cleanup` can find it if dev dies first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnedProcess {
+    pub pid: u32,
+    pub command: String,
+}
+
+/// Reads and writes the state file
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    path: PathBuf,
+}
+
+impl StateStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Load the saved state, or an empty state if the file doesn't exist yet
+    pub fn load(&self) -> Result<SessionState> {
+        if !self.path.exists() {
+            return Ok(SessionState::default());
+        }
+        let content = fs::read_to_string(&self.path).map_err(|e| {
+            ServerError::IoError(format!("Failed to read {}: {}", self.path.display(), e))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            ServerError::IoError(format!("Failed to parse {}: {}", self.path.display(), e))
+        })
+    }
+
+    pub fn save(&self, state: &SessionState) -> Result<()> {
+        let json = serde_json::to_string_pretty(state)
+            .map_err(|e| ServerError::IoError(format!("Failed to serialize state: {}", e)))?;
+        fs::write(&self.path, json).map_err(|e| {
+            ServerError::IoError(format!("Failed to write {}: {}", self.path.display(), e))
+        })
+    }
+
+    /// Remember a command that successfully brought the server up
+    pub fn record_good_command(&self, command: &str) -> Result<()> {
+        let mut state = self.load()?;
+        if state.last_good_command.as_deref() != Some(command) {
+            state.last_good_command = Some(command.to_string());
+            self.save(&state)?;
+        }
+        Ok(())
+    }
+
+    pub fn record_spawned(&self, pid: u32, command: &str) -> Result<()> {
+        let mut state = self.load()?;
+        state.spawned.push(SpawnedProcess {
+            pid,
+            command: command.to_string(),
+        });
+        self.save(&state)
+    }
+
+    /// Drop a process that has exited from the spawned list
+    pub fn forget_spawned(&self, pid: u32) -> Result<()> {
+        let mut state = self.load()?;
+        let before = state.spawned.len();
+        state.spawned.retain(|process| process.pid != pid);
+        if state.spawned.len() != before {
+            self.save(&state)?;
+        }
+        Ok(())
+    }
+
+    /// Remove and return every recorded process
+    pub fn take_spawned(&self) -> Result<Vec<SpawnedProcess>> {
+        let mut state = self.load()?;
+        let spawned = std::mem::take(&mut state.spawned);
+        if !spawned.is_empty() {
+            self.save(&state)?;
+        }
+        Ok(spawned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_state_file_is_empty() {
+        let store = StateStore::new(std::env::temp_dir().join("dev-cli-test-state-missing.json"));
+        assert_eq!(store.load().unwrap(), SessionState::default());
+    }
+
+    #[test]
+    fn test_record_good_command_round_trip() {
+        let path = std::env::temp_dir().join("dev-cli-test-state-good.json");
+        let store = StateStore::new(&path);
+
+        store.record_good_command("npm run dev").unwrap();
+        assert_eq!(
+            store.load().unwrap().last_good_command.as_deref(),
+            Some("npm run dev")
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_spawned_processes_round_trip() {
+        let path = std::env::temp_dir().join("dev-cli-test-state-spawned.json");
+        let _ = fs::remove_file(&path);
+        let store = StateStore::new(&path);
+
+        store.record_spawned(100, "npm run dev").unwrap();
+        store.record_spawned(200, "npm run dev").unwrap();
+        store.forget_spawned(100).unwrap();
+
+        let spawned = store.take_spawned().unwrap();
+        assert_eq!(spawned.len(), 1);
+        assert_eq!(spawned[0].pid, 200);
+        assert!(store.load().unwrap().spawned.is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::theme::{self, Icon};
+
+/// Session counters shared between the server, the monitor and the Ctrl+C handler
+pub type SharedStats = Arc<Mutex<SessionStats>>;
+
+/// Counters collected over a monitoring session
+#[derive(Debug, Clone, Default)]
+pub struct SessionStats {
+    pub attempts: u32,
+    pub restarts: u32,
+    pub spawn_failures: u32,
+    /// Restarts caused by a `stall_patterns` match (also counted in `restarts`)
+    pub stalls: u32,
+    /// Error pattern → number of matches, whether or not the match caused a restart
+    pub pattern_hits: HashMap<String, u32>,
+    /// Line of the error that caused the last restart, and how many restarts in a row it caused
+    pub last_error: Option<(String, u32)>,
+    /// Boot timings of every attempt that reached readiness
+    pub boots: Vec<BootTiming>,
+}
+
+/// How long one attempt took to boot, measured from the spawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootTiming {
+    /// Until the first line of output, if any came before readiness
+    pub first_output: Option<Duration>,
+    /// Until the ready pattern was seen
+    pub ready: Duration,
+}
+
+impl BootTiming {
+    /// `⏱️ Boot: first output 40ms, ready 3.2s later (3.24s total)`
+    pub fn line(&self) -> String {
+        match self.first_output {
+            Some(first_output) => format!(
+                "{} Boot: first output {}, ready {} later ({} total)",
+                theme::icon(Icon::Timing),
+                format_duration(first_output),
+                format_duration(self.ready.saturating_sub(first_output)),
+                format_duration(self.ready)
+            ),
+            None => format!(
+                "{} Boot: ready after {}",
+                theme::icon(Icon::Timing),
+                format_duration(self.ready)
+            ),
+        }
+    }
+}
+
+/// `40ms` below a second, `3.2s` from there on
+pub fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+impl SessionStats {
+    pub fn record_pattern_hit(&mut self, pattern: &str) {
+        *self.pattern_hits.entry(pattern.to_string()).or_default() += 1;
+    }
+
+    /// Remember the line of an error that causes a restart; returns how many restarts in a
+    /// row it has caused, starting over at 1 when the error differs from the previous one
+    pub fn record_restart_error(&mut self, line: &str) -> u32 {
+        match &mut self.last_error {
+            Some((last, repeats)) if last == line => *repeats += 1,
+            _ => self.last_error = Some((line.to_string(), 1)),
+        }
+        self.last_error.as_ref().map_or(1, |(_, repeats)| *repeats)
+    }
+
+    /// Fill a `max_restarts_message` template: `{count}` is the number of crash restarts,
+    /// `{last_error}` the line or reason that ended the last attempt
+    pub fn render_message(&self, template: &str, last_error: &str) -> String {
+        template
+            .replace("{count}", &self.restarts.to_string())
+            .replace("{last_error}", last_error)
+    }
+
+    /// `⏱️ Boot times: average 3.2s over 4 boot(s) (first output 40ms)`, or `None` before
+    /// the server was first ready
+    pub fn boot_summary(&self) -> Option<String> {
+        let count = self.boots.len() as u32;
+        if count == 0 {
+            return None;
+        }
+        let ready: Duration = self.boots.iter().map(|boot| boot.ready).sum();
+        let mut summary = format!(
+            "{} Boot times: average {} over {} boot(s)",
+            theme::icon(Icon::Timing),
+            format_duration(ready / count),
+            count
+        );
+        let first_outputs: Vec<Duration> = self
+            .boots
+            .iter()
+            .filter_map(|boot| boot.first_output)
+            .collect();
+        if !first_outputs.is_empty() {
+            let total: Duration = first_outputs.iter().sum();
+            summary.push_str(&format!(
+                " (first output {})",
+                format_duration(total / first_outputs.len() as u32)
+            ));
+        }
+        Some(summary)
+    }
+
+    /// Patterns by number of hits, most frequent first (ties by name)
+    pub fn ranked_patterns(&self) -> Vec<(&str, u32)> {
+        let mut ranked: Vec<(&str, u32)> = self
+            .pattern_hits
+            .iter()
+            .map(|(pattern, hits)| (pattern.as_str(), *hits))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        ranked
+    }
+
+    /// `🔎 Error patterns: [Error ×12, FATAL ×3`, or `None` before the first match
+    pub fn pattern_summary(&self) -> Option<String> {
+        if self.pattern_hits.is_empty() {
+            return None;
+        }
+        let ranked: Vec<String> = self
+            .ranked_patterns()
+            .into_iter()
+            .map(|(pattern, hits)| format!("{} ×{}", pattern, hits))
+            .collect();
+        Some(format!(
+            "{} Error patterns: {}",
+            theme::icon(Icon::Patterns),
+            ranked.join(", ")
+        ))
+    }
+
+    /// Summary printed when the monitor stops: counters, then the pattern breakdown if any
+    pub fn summary(&self) -> String {
+        let stalls = if self.stalls > 0 {
+            format!(" ({} after a stall)", self.stalls)
+        } else {
+            String::new()
+        };
+        let mut summary = format!(
+            "{} Session summary: {} attempt(s), {} crash restart(s){}, {} spawn failure(s)",
+            theme::icon(Icon::Summary),
+            self.attempts,
+            self.restarts,
+            stalls,
+            self.spawn_failures
+        );
+        if let Some(patterns) = self.pattern_summary() {
+            summary.push('\n');
+            summary.push_str(&patterns);
+        }
+        if let Some(boots) = self.boot_summary() {
+            summary.push('\n');
+            summary.push_str(&boots);
+        }
+        summary
+    }
+}
+
+/// Outcomes of a `--repeat` run, one entry per iteration
+#[derive(Debug, Clone, Default)]
+pub struct RepeatTally {
+    /// `None` for a successful iteration, otherwise why it failed
+    pub outcomes: Vec<Option<String>>,
+}
+
+impl RepeatTally {
+    pub fn record_success(&mut self) {
+        self.outcomes.push(None);
+    }
+
+    pub fn record_failure(&mut self, reason: impl Into<String>) {
+        self.outcomes.push(Some(reason.into()));
+    }
+
+    pub fn successes(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.is_none()).count()
+    }
+
+    /// Summary line followed by one line per failed iteration
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} Repeat summary: {}/{} succeeded",
+            theme::icon(Icon::Repeat),
+            self.successes(),
+            self.outcomes.len()
+        );
+        for (i, outcome) in self.outcomes.iter().enumerate() {
+            if let Some(reason) = outcome {
+                summary.push_str(&format!("\n   #{} failed: {}", i + 1, reason));
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_distinguishes_counters() {
+        let stats = SessionStats {
+            attempts: 6,
+            restarts: 2,
+            spawn_failures: 3,
+            ..SessionStats::default()
+        };
+        assert_eq!(
+            stats.summary(),
+            "📊 Session summary: 6 attempt(s), 2 crash restart(s), 3 spawn failure(s)"
+        );
+
+        let stalled = SessionStats { stalls: 1, ..stats };
+        assert_eq!(
+            stalled.summary(),
+            "📊 Session summary: 6 attempt(s), 2 crash restart(s) (1 after a stall), 3 spawn failure(s)"
+        );
+    }
+
+    #[test]
+    fn test_boot_timings_and_their_average() {
+        let first = BootTiming {
+            first_output: Some(Duration::from_millis(40)),
+            ready: Duration::from_millis(3200),
+        };
+        assert_eq!(
+            first.line(),
+            "⏱️ Boot: first output 40ms, ready 3.2s later (3.2s total)"
+        );
+        let second = BootTiming {
+            first_output: None,
+            ready: Duration::from_millis(800),
+        };
+        assert_eq!(second.line(), "⏱️ Boot: ready after 800ms");
+
+        let mut stats = SessionStats::default();
+        assert_eq!(stats.boot_summary(), None);
+        stats.boots = vec![first, second];
+        assert_eq!(
+            stats.boot_summary().unwrap(),
+            "⏱️ Boot times: average 2.0s over 2 boot(s) (first output 40ms)"
+        );
+    }
+
+    #[test]
+    fn test_repeated_restart_errors_are_counted_until_one_differs() {
+        let mut stats = SessionStats::default();
+        assert_eq!(stats.record_restart_error("[Error] port in use"), 1);
+        assert_eq!(stats.record_restart_error("[Error] port in use"), 2);
+        assert_eq!(stats.record_restart_error("[Error] port in use"), 3);
+        assert_eq!(stats.record_restart_error("[Error] missing module"), 1);
+        assert_eq!(stats.record_restart_error("[Error] port in use"), 1);
+    }
+
+    #[test]
+    fn test_render_message_fills_placeholders() {
+        let stats = SessionStats {
+            restarts: 5,
+            ..SessionStats::default()
+        };
+        assert_eq!(
+            stats.render_message(
+                "Crashed {count} times ({last_error}), see https://wiki/runbook",
+                "[Error] port in use"
+            ),
+            "Crashed 5 times ([Error] port in use), see https://wiki/runbook"
+        );
+        assert_eq!(
+            stats.render_message("No placeholders", "boom"),
+            "No placeholders"
+        );
+    }
+
+    #[test]
+    fn test_pattern_hits_accumulate_and_rank() {
+        let mut stats = SessionStats::default();
+        for _ in 0..3 {
+            stats.record_pattern_hit("FATAL");
+        }
+        for _ in 0..12 {
+            stats.record_pattern_hit("[Error");
+        }
+        stats.record_pattern_hit("panic");
+        stats.record_pattern_hit("OOM");
+
+        assert_eq!(stats.pattern_hits["[Error"], 12);
+        assert_eq!(
+            stats.pattern_summary().as_deref(),
+            Some("🔎 Error patterns: [Error ×12, FATAL ×3, OOM ×1, panic ×1")
+        );
+        assert!(SessionStats::default().pattern_summary().is_none());
+    }
+
+    #[test]
+    fn test_repeat_summary_lists_failures() {
+        let mut tally = RepeatTally::default();
+        tally.record_success();
+        tally.record_failure("error pattern detected");
+        tally.record_success();
+        assert_eq!(
+            tally.summary(),
+            "🔁 Repeat summary: 2/3 succeeded\n   #2 failed: error pattern detected"
+        );
+    }
+}
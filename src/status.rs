@@ -0,0 +1,111 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use crate::color;
+use crate::error::{Result, ServerError};
+
+/// `monitor_log` value that sends dev's status lines to stderr instead of a file
+pub const STDERR_TARGET: &str = "stderr";
+
+/// Where dev's own status lines go; the server's output never passes through here
+enum Sink {
+    Stdout,
+    Stderr,
+    File(File),
+}
+
+static SINK: Mutex<Sink> = Mutex::new(Sink::Stdout);
+
+/// Print one of dev's own status lines (`📡 Starting...`, `🔄 Restarting...`) to the
+/// sink chosen by `monitor_log`; stdout until [`init`] says otherwise
+macro_rules! status {
+    ($($arg:tt)*) => {
+        $crate::status::write_line(format_args!($($arg)*))
+    };
+}
+
+/// Route status lines to `monitor_log`: a file path (appended to), `stderr`, or stdout when unset
+pub fn init(monitor_log: Option<&str>) -> Result<()> {
+    let sink = match monitor_log {
+        None => Sink::Stdout,
+        Some(STDERR_TARGET) => Sink::Stderr,
+        Some(path) => Sink::File(open_append(path)?),
+    };
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = sink;
+    Ok(())
+}
+
+/// Whether status lines may contain color codes for their current sink
+pub fn color_enabled() -> bool {
+    match *SINK.lock().unwrap_or_else(|e| e.into_inner()) {
+        Sink::Stdout => color::stdout_enabled(),
+        Sink::Stderr => color::stderr_enabled(),
+        Sink::File(_) => false,
+    }
+}
+
+pub fn write_line(args: fmt::Arguments) {
+    // A poisoned lock only means another thread panicked mid-line; keep logging
+    let mut sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+    match &mut *sink {
+        Sink::Stdout => println!("{}", args),
+        Sink::Stderr => eprintln!("{}", args),
+        Sink::File(file) => {
+            let _ = writeln!(file, "{}", args);
+        }
+    }
+}
+
+/// Open a log file for appending, creating it if needed
+pub fn open_append(path: &str) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| ServerError::IoError(format!("Failed to open {}: {}", path, e)))
+}
+
+/// Copy of the server's output, without dev's decorations or colors (`log_file`)
+pub struct ChildLog {
+    file: Mutex<File>,
+}
+
+impl ChildLog {
+    pub fn open(path: &str) -> Result<Self> {
+        Ok(Self {
+            file: Mutex::new(open_append(path)?),
+        })
+    }
+
+    pub fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{}", line)
+    }
+}
+
+pub(crate) use status;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_child_log_keeps_plain_lines() {
+        let path = std::env::temp_dir().join("dev-cli-test-child-log.txt");
+        let _ = fs::remove_file(&path);
+
+        let log = ChildLog::open(path.to_str().unwrap()).unwrap();
+        log.write_line("listening on 3000").unwrap();
+        log.write_line("[Error] boom").unwrap();
+        drop(log);
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "listening on 3000\n[Error] boom\n"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+use std::sync::RwLock;
+
+use crate::error::{Result, ServerError};
+
+/// What a decorative prefix stands for; each theme picks its own string for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    Warning,
+    Failure,
+    Success,
+    Restart,
+    Reload,
+    Stop,
+    Start,
+    Launch,
+    Exit,
+    Hook,
+    Warmup,
+    Config,
+    Setup,
+    Cleanup,
+    Summary,
+    Patterns,
+    Repeat,
+    Waiting,
+    Timeout,
+    PromptTimeout,
+    Watch,
+    ErrorDetected,
+    Stall,
+    Resource,
+    Disconnected,
+    OutputClosed,
+    Hint,
+    Fallback,
+    Test,
+    /// How long the server took to boot
+    Timing,
+    /// Prefix of a line the server printed to stdout
+    Stdout,
+    /// Prefix of a line the server printed to stderr
+    Stderr,
+}
+
+/// Named set of prefixes for dev's output (`theme` in dev-cli.json)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Emoji,
+    /// Single ASCII characters
+    Minimal,
+    /// Nerd Font glyphs, for terminals with a patched font
+    NerdFont,
+    /// Bracketed words such as `[error]`, for logs and screen readers
+    Plain,
+}
+
+pub const THEME_NAMES: [&str; 4] = ["emoji", "minimal", "nerdfont", "plain"];
+
+static THEME: RwLock<Theme> = RwLock::new(Theme::Emoji);
+
+impl Theme {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "emoji" => Ok(Theme::Emoji),
+            "minimal" => Ok(Theme::Minimal),
+            "nerdfont" => Ok(Theme::NerdFont),
+            "plain" => Ok(Theme::Plain),
+            _ => Err(ServerError::ConfigError(format!(
+                "unknown theme '{}' (available: {})",
+                name,
+                THEME_NAMES.join(", ")
+            ))),
+        }
+    }
+
+    pub fn icon(self, icon: Icon) -> &'static str {
+        match self {
+            Theme::Emoji => emoji(icon),
+            Theme::Minimal => minimal(icon),
+            Theme::NerdFont => nerdfont(icon),
+            Theme::Plain => plain(icon),
+        }
+    }
+}
+
+/// Use `theme` for every prefix printed from now on
+pub fn init(theme: Theme) {
+    *THEME.write().unwrap_or_else(|e| e.into_inner()) = theme;
+}
+
+/// The current theme's prefix for `icon`
+pub fn icon(icon: Icon) -> &'static str {
+    THEME.read().unwrap_or_else(|e| e.into_inner()).icon(icon)
+}
+
+fn emoji(icon: Icon) -> &'static str {
+    match icon {
+        Icon::Warning => "⚠️",
+        Icon::Failure => "❌",
+        Icon::Success => "✅",
+        Icon::Restart => "🔄",
+        Icon::Reload => "♻️",
+        Icon::Stop => "🛑",
+        Icon::Start => "📡",
+        Icon::Launch => "🚀",
+        Icon::Exit => "📋",
+        Icon::Hook => "🪝",
+        Icon::Warmup => "🔥",
+        Icon::Config => "📄",
+        Icon::Setup => "🔧",
+        Icon::Cleanup => "🧹",
+        Icon::Summary => "📊",
+        Icon::Patterns => "🔎",
+        Icon::Repeat => "🔁",
+        Icon::Waiting => "⏳",
+        Icon::Timeout => "⌛",
+        Icon::PromptTimeout => "⏱️",
+        Icon::Watch => "👀",
+        Icon::ErrorDetected => "🔍",
+        Icon::Stall => "🐌",
+        Icon::Resource => "🩺",
+        Icon::Disconnected => "🔌",
+        Icon::OutputClosed => "📭",
+        Icon::Hint => "💡",
+        Icon::Fallback => "↩️",
+        Icon::Test => "🧪",
+        Icon::Timing => "⏱️",
+        Icon::Stdout => "📤",
+        Icon::Stderr => "📥",
+    }
+}
+
+fn minimal(icon: Icon) -> &'static str {
+    match icon {
+        Icon::Warning
+        | Icon::ErrorDetected
+        | Icon::Stall
+        | Icon::Resource
+        | Icon::Disconnected
+        | Icon::OutputClosed => "!",
+        Icon::Failure => "x",
+        Icon::Success => "+",
+        Icon::Restart | Icon::Reload => "~",
+        Icon::Stop | Icon::Exit | Icon::Cleanup => "-",
+        Icon::Start | Icon::Launch | Icon::Fallback => ">",
+        Icon::Hook | Icon::Warmup => "$",
+        Icon::Config | Icon::Setup | Icon::Test => "*",
+        Icon::Summary | Icon::Patterns | Icon::Repeat => "=",
+        Icon::Waiting | Icon::Timeout | Icon::PromptTimeout | Icon::Timing => ".",
+        Icon::Watch | Icon::Hint => "?",
+        Icon::Stdout | Icon::Stderr => "|",
+    }
+}
+
+fn nerdfont(icon: Icon) -> &'static str {
+    match icon {
+        Icon::Warning => "\u{f071}",
+        Icon::Failure => "\u{f00d}",
+        Icon::Success => "\u{f00c}",
+        Icon::Restart => "\u{f021}",
+        Icon::Reload => "\u{f1b8}",
+        Icon::Stop => "\u{f04d}",
+        Icon::Start => "\u{f04b}",
+        Icon::Launch => "\u{f135}",
+        Icon::Exit => "\u{f011}",
+        Icon::Hook => "\u{f120}",
+        Icon::Warmup => "\u{f06d}",
+        Icon::Config => "\u{f15c}",
+        Icon::Setup => "\u{f0ad}",
+        Icon::Cleanup => "\u{f1f8}",
+        Icon::Summary => "\u{f080}",
+        Icon::Patterns => "\u{f002}",
+        Icon::Repeat => "\u{f01e}",
+        Icon::Waiting => "\u{f252}",
+        Icon::Timeout => "\u{f253}",
+        Icon::PromptTimeout => "\u{f017}",
+        Icon::Watch => "\u{f06e}",
+        Icon::ErrorDetected => "\u{f188}",
+        Icon::Stall => "\u{f254}",
+        Icon::Resource => "\u{f0f1}",
+        Icon::Disconnected => "\u{f1e6}",
+        Icon::OutputClosed => "\u{f01c}",
+        Icon::Hint => "\u{f0eb}",
+        Icon::Fallback => "\u{f0e2}",
+        Icon::Test => "\u{f0c3}",
+        Icon::Timing => "\u{f2f2}",
+        Icon::Stdout => "\u{f054}",
+        Icon::Stderr => "\u{f12a}",
+    }
+}
+
+fn plain(icon: Icon) -> &'static str {
+    match icon {
+        Icon::Warning => "[warn]",
+        Icon::Failure => "[fail]",
+        Icon::Success => "[ok]",
+        Icon::Restart => "[restart]",
+        Icon::Reload => "[reload]",
+        Icon::Stop => "[stop]",
+        Icon::Start => "[start]",
+        Icon::Launch => "[dev]",
+        Icon::Exit => "[exit]",
+        Icon::Hook => "[hook]",
+        Icon::Warmup => "[warmup]",
+        Icon::Config | Icon::Setup => "[config]",
+        Icon::Cleanup => "[cleanup]",
+        Icon::Summary => "[summary]",
+        Icon::Patterns => "[patterns]",
+        Icon::Repeat => "[repeat]",
+        Icon::Waiting => "[wait]",
+        Icon::Timeout | Icon::PromptTimeout => "[timeout]",
+        Icon::Watch => "[watch]",
+        Icon::ErrorDetected => "[match]",
+        Icon::Stall => "[stall]",
+        Icon::Resource => "[resource]",
+        Icon::Disconnected => "[ssh]",
+        Icon::OutputClosed => "[eof]",
+        Icon::Hint => "[hint]",
+        Icon::Fallback => "[fallback]",
+        Icon::Test => "[test]",
+        Icon::Timing => "[timing]",
+        Icon::Stdout => "[out]",
+        Icon::Stderr => "[err]",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_names_are_validated() {
+        for name in THEME_NAMES {
+            assert!(Theme::from_name(name).is_ok());
+        }
+        assert_eq!(Theme::from_name("NerdFont").unwrap(), Theme::NerdFont);
+        assert!(matches!(
+            Theme::from_name("neon"),
+            Err(ServerError::ConfigError(message)) if message.contains("emoji, minimal, nerdfont, plain")
+        ));
+    }
+
+    #[test]
+    fn test_themes_pick_their_own_prefixes() {
+        assert_eq!(Theme::Emoji.icon(Icon::Restart), "🔄");
+        assert_eq!(Theme::Minimal.icon(Icon::Restart), "~");
+        assert_eq!(Theme::Plain.icon(Icon::Restart), "[restart]");
+        assert_eq!(Theme::NerdFont.icon(Icon::Failure), "\u{f00d}");
+    }
+}
@@ -0,0 +1,104 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::ExitStatus;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cli_config::CliConfig;
+use crate::command::{CommandBuilder, CommandType};
+use crate::error::Result;
+use crate::process::{self, ChildState, ProcessManager, ShutdownWait};
+
+/// How long the setup trial lets the command run before stopping it
+pub const TRIAL_DURATION: Duration = Duration::from_secs(5);
+
+/// How the command behaved during a setup trial
+#[derive(Debug)]
+pub enum TrialOutcome {
+    /// Still running when the trial ended, as a dev server should be
+    StillRunning,
+    Exited(ExitStatus),
+    /// The exit status was lost to another reaper
+    Unknown,
+}
+
+impl TrialOutcome {
+    /// A command that failed this quickly almost certainly has a typo or a missing tool
+    pub fn failed(&self) -> bool {
+        matches!(self, TrialOutcome::Exited(status) if !status.success())
+    }
+}
+
+/// Run `run_command` for up to `duration`, showing its output, then stop it and everything
+/// it started. Used by interactive setup to catch a broken command before it's saved.
+pub fn run(cli_config: &CliConfig, duration: Duration) -> Result<TrialOutcome> {
+    let mut process = ProcessManager::spawn(CommandBuilder::build(CommandType::Dev(Box::new(
+        cli_config.clone(),
+    ))))?;
+    // Not joined: a server that forked may hold the pipes open a little longer
+    if let Some(stdout) = process.take_stdout() {
+        thread::spawn(move || show_output(stdout));
+    }
+    if let Some(stderr) = process.take_stderr() {
+        thread::spawn(move || show_output(stderr));
+    }
+
+    let started = Instant::now();
+    while started.elapsed() < duration {
+        match process.try_wait()? {
+            ChildState::Running => thread::sleep(Duration::from_millis(50)),
+            ChildState::Exited(status) => return Ok(TrialOutcome::Exited(status)),
+            ChildState::ReapedElsewhere => return Ok(TrialOutcome::Unknown),
+        }
+    }
+
+    // Collected first, since the children are reparented once the shell is gone
+    #[cfg(not(windows))]
+    let descendants = process::descendant_pids(process.id());
+    process.kill_and_wait(&ShutdownWait::default())?;
+    #[cfg(not(windows))]
+    for pid in descendants {
+        process::signal_pid(pid, true);
+    }
+    Ok(TrialOutcome::StillRunning)
+}
+
+fn show_output(output: impl Read) {
+    for line in BufReader::new(output).lines().map_while(|line| line.ok()) {
+        println!("   │ {}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(run_command: &str) -> CliConfig {
+        CliConfig {
+            run_command: run_command.to_string(),
+            ..CliConfig::default()
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_trial_tells_a_broken_command_from_a_running_one() {
+        let outcome = run(
+            &config("echo 'sh: pnmp: not found'; exit 127"),
+            TRIAL_DURATION,
+        )
+        .unwrap();
+        assert!(outcome.failed(), "{:?}", outcome);
+
+        let outcome = run(
+            &config("echo listening; sleep 30"),
+            Duration::from_millis(300),
+        )
+        .unwrap();
+        assert!(matches!(outcome, TrialOutcome::StillRunning));
+        assert!(!outcome.failed());
+
+        // A command that finishes on its own is fine too
+        let outcome = run(&config("true"), TRIAL_DURATION).unwrap();
+        assert!(!outcome.failed(), "{:?}", outcome);
+    }
+}
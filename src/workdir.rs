@@ -0,0 +1,273 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::error::{Result, ServerError};
+use crate::rerun::IGNORED_DIRS;
+
+/// The copy the server currently runs in, shared with the Ctrl+C handler so it can remove it
+pub type CurrentCopy = Arc<Mutex<Option<PathBuf>>>;
+
+/// Never copied or linked: a copy shouldn't be able to change the repository
+const SKIPPED: [&str; 1] = [".git"];
+
+/// Paths left out of a copy: `target`, `node_modules` and what the source's `.gitignore`
+/// lists. Negations (`!`) aren't supported and are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgnoreList {
+    patterns: Vec<IgnorePattern>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct IgnorePattern {
+    glob: String,
+    /// Matched against the path from the source root instead of the name at any depth
+    anchored: bool,
+    dir_only: bool,
+}
+
+impl IgnoreList {
+    /// The default list plus the entries of `gitignore`
+    pub fn parse(gitignore: &str) -> Self {
+        let mut patterns: Vec<IgnorePattern> = IGNORED_DIRS
+            .iter()
+            .map(|dir| IgnorePattern {
+                glob: dir.to_string(),
+                anchored: false,
+                dir_only: true,
+            })
+            .collect();
+        for line in gitignore.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+            let dir_only = line.ends_with('/');
+            let line = line.trim_end_matches('/');
+            let anchored = line.contains('/');
+            let glob = line.trim_start_matches('/');
+            if glob.is_empty() {
+                continue;
+            }
+            patterns.push(IgnorePattern {
+                glob: glob.to_string(),
+                anchored,
+                dir_only,
+            });
+        }
+        Self { patterns }
+    }
+
+    /// Whether the entry at `relative` (from the source root, `/`-separated) is left out
+    pub fn is_ignored(&self, relative: &str, is_dir: bool) -> bool {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        self.patterns.iter().any(|pattern| {
+            (is_dir || !pattern.dir_only)
+                && if pattern.anchored {
+                    wildcard_match(&pattern.glob, relative)
+                } else {
+                    wildcard_match(&pattern.glob, name)
+                }
+        })
+    }
+}
+
+/// `*` matches any run of characters but `/`, `**` any run at all, `?` one character
+fn wildcard_match(glob: &str, text: &str) -> bool {
+    fn matches(glob: &[char], text: &[char]) -> bool {
+        match glob {
+            [] => text.is_empty(),
+            ['*', '*', rest @ ..] => (0..=text.len()).any(|skip| matches(rest, &text[skip..])),
+            ['*', rest @ ..] => (0..=text.len())
+                .take_while(|&skip| skip == 0 || text[skip - 1] != '/')
+                .any(|skip| matches(rest, &text[skip..])),
+            ['?', rest @ ..] => !text.is_empty() && text[0] != '/' && matches(rest, &text[1..]),
+            [c, rest @ ..] => text.first() == Some(c) && matches(rest, &text[1..]),
+        }
+    }
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&glob, &text)
+}
+
+/// Runs every start of the server in a new copy of the project (`fresh_workdir`), so
+/// changes a run makes to its files are gone by the next one
+pub struct FreshWorkdir {
+    source: PathBuf,
+    ignore: IgnoreList,
+    copies: AtomicU32,
+    current: CurrentCopy,
+}
+
+impl FreshWorkdir {
+    pub fn new(source: &Path, current: CurrentCopy) -> Result<Self> {
+        let source = source.canonicalize().map_err(|e| {
+            ServerError::ConfigError(format!(
+                "fresh_workdir {} can't be read: {}",
+                source.display(),
+                e
+            ))
+        })?;
+        let gitignore = fs::read_to_string(source.join(".gitignore")).unwrap_or_default();
+        Ok(Self {
+            source,
+            ignore: IgnoreList::parse(&gitignore),
+            copies: AtomicU32::new(0),
+            current,
+        })
+    }
+
+    /// The directory that is copied
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Discard the previous copy and make a new one; returns the directory to start in
+    pub fn refresh(&self) -> Result<PathBuf> {
+        discard(&self.current);
+        let number = self.copies.fetch_add(1, Ordering::SeqCst) + 1;
+        let copy = env::temp_dir().join(format!("dev-fresh-{}-{}", std::process::id(), number));
+        // Recorded first, so a copy interrupted halfway is still removed
+        *self.current.lock().unwrap() = Some(copy.clone());
+        copy_tree(&self.source, &copy, "", &self.ignore).map_err(|e| {
+            ServerError::IoError(format!(
+                "Failed to copy {} to {}: {}",
+                self.source.display(),
+                copy.display(),
+                e
+            ))
+        })?;
+        Ok(copy)
+    }
+}
+
+impl Drop for FreshWorkdir {
+    fn drop(&mut self) {
+        discard(&self.current);
+    }
+}
+
+/// Remove the copy in use, if any; best effort, since the server may still be exiting
+pub fn discard(current: &CurrentCopy) {
+    // try_lock: never hang an exit on a lock held by an interrupted thread
+    let Ok(mut current) = current.try_lock() else {
+        return;
+    };
+    if let Some(copy) = current.take() {
+        let _ = fs::remove_dir_all(copy);
+    }
+}
+
+/// Copy `source` to `target`. Ignored entries are linked instead on Unix, so dependencies
+/// and build output are there without being copied, and left out elsewhere.
+fn copy_tree(source: &Path, target: &Path, relative: &str, ignore: &IgnoreList) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if SKIPPED.contains(&name.as_ref()) {
+            continue;
+        }
+        let path = entry.path();
+        let to = target.join(entry.file_name());
+        let kind = entry.file_type()?;
+        let relative = if relative.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", relative, name)
+        };
+
+        if ignore.is_ignored(&relative, kind.is_dir()) {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&path, &to)?;
+        } else if kind.is_dir() {
+            copy_tree(&path, &to, &relative, ignore)?;
+        } else if kind.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(&path)?, &to)?;
+            #[cfg(windows)]
+            fs::copy(&path, &to).map(|_| ())?;
+        } else {
+            fs::copy(&path, &to)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_list_follows_gitignore_basics() {
+        let ignore = IgnoreList::parse("# build\n/dist\n*.log\ncache/\n!keep.log\nsrc/gen/**\n");
+        assert!(ignore.is_ignored("node_modules", true));
+        assert!(ignore.is_ignored("packages/web/node_modules", true));
+        assert!(ignore.is_ignored("dist", true));
+        // Anchored to the root
+        assert!(!ignore.is_ignored("packages/web/dist", true));
+        assert!(ignore.is_ignored("logs/server.log", false));
+        assert!(ignore.is_ignored("keep.log", false));
+        assert!(ignore.is_ignored("cache", true));
+        assert!(!ignore.is_ignored("cache", false));
+        assert!(ignore.is_ignored("src/gen/api/client.rs", false));
+        assert!(!ignore.is_ignored("src/main.rs", false));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_each_refresh_starts_from_the_source() {
+        let source = env::temp_dir().join("dev-cli-test-fresh-workdir");
+        let _ = fs::remove_dir_all(&source);
+        fs::create_dir_all(source.join("src")).unwrap();
+        fs::create_dir_all(source.join("node_modules/express")).unwrap();
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::write(source.join("src/app.js"), "original").unwrap();
+        fs::write(source.join("server.log"), "old").unwrap();
+        fs::write(source.join(".gitignore"), "*.log\n").unwrap();
+
+        let current = CurrentCopy::default();
+        let workdir = FreshWorkdir::new(&source, Arc::clone(&current)).unwrap();
+        let first = workdir.refresh().unwrap();
+        assert_eq!(
+            fs::read_to_string(first.join("src/app.js")).unwrap(),
+            "original"
+        );
+        assert!(!first.join(".git").exists());
+        // Ignored entries are linked to the source, not copied
+        assert!(
+            fs::symlink_metadata(first.join("node_modules"))
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert!(first.join("node_modules/express").is_dir());
+        assert!(
+            fs::symlink_metadata(first.join("server.log"))
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+
+        fs::write(first.join("src/app.js"), "changed by a test").unwrap();
+        let second = workdir.refresh().unwrap();
+        assert!(!first.exists());
+        assert_eq!(
+            fs::read_to_string(second.join("src/app.js")).unwrap(),
+            "original"
+        );
+        assert_eq!(current.lock().unwrap().as_deref(), Some(second.as_path()));
+
+        drop(workdir);
+        assert!(!second.exists());
+        assert_eq!(
+            fs::read_to_string(source.join("src/app.js")).unwrap(),
+            "original"
+        );
+        fs::remove_dir_all(&source).unwrap();
+    }
+}